@@ -0,0 +1,26 @@
+CREATE TABLE IF NOT EXISTS public.devices
+(
+    id           varchar(255)    not null,
+    client_id    varchar(255)    not null,
+    tenant_id    varchar(255)    not null,
+
+    push_type    public.provider not null,
+    device_token text            not null,
+
+    created_at   timestamptz     not null default now(),
+
+    PRIMARY KEY (client_id, id),
+    CONSTRAINT fk_devices_client_id FOREIGN KEY (client_id)
+        REFERENCES public.clients (id) ON DELETE CASCADE
+);
+
+-- Existing registrations become the client's default device
+INSERT INTO public.devices (id, client_id, tenant_id, push_type, device_token, created_at)
+SELECT 'default', id, tenant_id, push_type, device_token, created_at
+FROM public.clients;
+
+ALTER TABLE public.clients
+    DROP COLUMN push_type;
+
+ALTER TABLE public.clients
+    DROP COLUMN device_token;
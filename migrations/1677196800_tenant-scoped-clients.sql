@@ -0,0 +1,37 @@
+-- Foreign keys have to be dropped before the primary keys they reference
+ALTER TABLE public.devices
+    DROP CONSTRAINT fk_devices_client_id;
+
+ALTER TABLE public.notifications
+    DROP CONSTRAINT fk_notifications_client_id;
+
+-- Clients re-registered by another tenant had their `tenant_id` overwritten,
+-- realign devices and drop the notifications that were left behind
+UPDATE public.devices d
+SET tenant_id = c.tenant_id
+FROM public.clients c
+WHERE c.id = d.client_id;
+
+DELETE
+FROM public.notifications n
+WHERE NOT EXISTS(SELECT 1 FROM public.clients c WHERE c.id = n.client_id AND c.tenant_id = n.tenant_id);
+
+ALTER TABLE public.clients
+    DROP CONSTRAINT clients_pkey;
+
+ALTER TABLE public.clients
+    ADD PRIMARY KEY (tenant_id, id);
+
+ALTER TABLE public.devices
+    DROP CONSTRAINT devices_pkey;
+
+ALTER TABLE public.devices
+    ADD PRIMARY KEY (tenant_id, client_id, id);
+
+ALTER TABLE public.devices
+    ADD CONSTRAINT fk_devices_tenant_id_client_id FOREIGN KEY (tenant_id, client_id)
+        REFERENCES public.clients (tenant_id, id) ON DELETE CASCADE ON UPDATE CASCADE;
+
+ALTER TABLE public.notifications
+    ADD CONSTRAINT fk_notifications_tenant_id_client_id FOREIGN KEY (tenant_id, client_id)
+        REFERENCES public.clients (tenant_id, id) ON UPDATE CASCADE;
@@ -0,0 +1,5 @@
+ALTER TABLE public.clients
+    ADD COLUMN last_seen_at timestamptz not null default now();
+
+UPDATE public.clients
+SET last_seen_at = created_at;
@@ -0,0 +1,8 @@
+ALTER TABLE public.devices
+    ADD COLUMN os text,
+    ADD COLUMN bundle_id text,
+    ADD COLUMN app_version text,
+    ADD COLUMN sdk_name text,
+    ADD COLUMN sdk_version text,
+    ADD COLUMN locale text,
+    ADD COLUMN timezone text;
@@ -0,0 +1,3 @@
+ALTER TABLE public.clients ADD COLUMN last_push_success_at timestamptz;
+
+CREATE INDEX idx_clients_last_seen_at ON public.clients (last_seen_at);
@@ -0,0 +1 @@
+CREATE INDEX IF NOT EXISTS idx_notifications_last_received_at ON public.notifications (last_received_at);
@@ -0,0 +1,7 @@
+CREATE TYPE public.notification_status AS ENUM ('pending', 'delivered', 'failed');
+
+-- Existing notifications were treated as handled so keep them that way
+ALTER TABLE public.notifications
+    ADD COLUMN status public.notification_status NOT NULL DEFAULT 'delivered';
+ALTER TABLE public.notifications
+    ALTER COLUMN status SET DEFAULT 'pending';
@@ -0,0 +1,5 @@
+-- `previous_payloads` now records every payload received for a notification
+-- along with when it was received, previously it was never written to
+UPDATE public.notifications
+SET previous_payloads = ARRAY [jsonb_build_object('payload', last_payload, 'received_at', last_received_at)]
+WHERE cardinality(previous_payloads) = 0;
@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS public.tenant_usage
+(
+    tenant_id               varchar(255) not null,
+    day                     date         not null,
+
+    received_notifications  bigint       not null default 0,
+    sent_fcm_notifications  bigint       not null default 0,
+    sent_apns_notifications bigint       not null default 0,
+    failed_notifications    bigint       not null default 0,
+    registrations           bigint       not null default 0,
+
+    PRIMARY KEY (tenant_id, day)
+);
@@ -0,0 +1,7 @@
+-- Deleting a client removes its notifications along with its devices
+ALTER TABLE public.notifications
+    DROP CONSTRAINT fk_notifications_tenant_id_client_id;
+
+ALTER TABLE public.notifications
+    ADD CONSTRAINT fk_notifications_tenant_id_client_id FOREIGN KEY (tenant_id, client_id)
+        REFERENCES public.clients (tenant_id, id) ON DELETE CASCADE ON UPDATE CASCADE;
@@ -0,0 +1,12 @@
+-- Notification IDs are only unique within a tenant, the same ID from another
+-- tenant is a different notification
+ALTER TABLE public.notifications
+    DROP CONSTRAINT notifications_pkey;
+
+ALTER TABLE public.notifications
+    ADD PRIMARY KEY (tenant_id, id);
+
+-- Only the most recent payloads are kept
+UPDATE public.notifications
+SET previous_payloads = previous_payloads[cardinality(previous_payloads) - 19:]
+WHERE cardinality(previous_payloads) > 20;
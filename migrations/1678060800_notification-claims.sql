@@ -0,0 +1,4 @@
+-- Set while a request is delivering the notification so a concurrent retry
+-- doesn't deliver it again, cleared when its status is recorded
+ALTER TABLE public.notifications
+    ADD COLUMN claimed_at timestamptz;
@@ -0,0 +1,10 @@
+-- Quotas count notifications rather than deliveries to each device. Past days
+-- only have per-device counts, which are at least the number of notifications
+ALTER TABLE public.tenant_usage
+    ADD COLUMN delivered_notifications bigint not null default 0;
+UPDATE public.tenant_usage
+SET delivered_notifications = sent_fcm_notifications + sent_apns_notifications;
+
+-- Snapshot of the tenant's clients, null for days before snapshots were taken
+ALTER TABLE public.tenant_usage
+    ADD COLUMN registered_clients bigint;
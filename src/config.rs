@@ -5,6 +5,8 @@ use {
             Error,
             Error::{InvalidConfiguration, NoApnsConfigured},
         },
+        log::prelude::*,
+        middleware::resolve_tenant::TenantResolver,
         providers::ProviderKind,
         stores::tenant::ApnsType,
     },
@@ -26,10 +28,106 @@ pub struct Config {
     pub relay_url: String,
     #[serde(default = "default_validate_signatures")]
     pub validate_signatures: bool,
+    #[serde(default = "default_validate_client_signatures")]
+    pub validate_client_signatures: bool,
     pub database_url: String,
     pub tenant_database_url: Option<String>,
     #[serde(default = "default_tenant_id")]
     pub default_tenant_id: String,
+    /// Grants operators access to every tenant
+    pub admin_api_key: Option<String>,
+
+    // CLIENT REAPER
+    /// Clients idle for longer than this are deleted, disabled when unset
+    pub client_reaper_max_idle_days: Option<u32>,
+    #[serde(default = "default_client_reaper_interval_secs")]
+    pub client_reaper_interval_secs: u64,
+    /// Only count and log the stale clients rather than deleting them
+    #[serde(default = "default_client_reaper_dry_run")]
+    pub client_reaper_dry_run: bool,
+    #[serde(default = "default_client_reaper_batch_size")]
+    pub client_reaper_batch_size: i64,
+
+    // NOTIFICATION RETENTION
+    /// Notifications last received longer ago than this are deleted, `0`
+    /// disables the cleanup
+    #[serde(default = "default_notification_retention_days")]
+    pub notification_retention_days: u32,
+    #[serde(default = "default_notification_cleanup_interval_secs")]
+    pub notification_cleanup_interval_secs: u64,
+    #[serde(default = "default_notification_cleanup_batch_size")]
+    pub notification_cleanup_batch_size: i64,
+
+    // TENANT DELETION
+    /// Days a deleted tenant can be restored for before it and its clients are
+    /// purged
+    #[serde(default = "default_tenant_deletion_grace_period_days")]
+    pub tenant_deletion_grace_period_days: u32,
+    #[serde(default = "default_tenant_purge_interval_secs")]
+    pub tenant_purge_interval_secs: u64,
+
+    // TENANT RENAMES
+    /// How often renames whose clients couldn't be moved to the new ID are
+    /// retried
+    #[serde(default = "default_tenant_rename_retry_interval_secs")]
+    pub tenant_rename_retry_interval_secs: u64,
+
+    // USAGE
+    /// How often each tenant's registered clients are recorded in its daily
+    /// usage
+    #[serde(default = "default_usage_snapshot_interval_secs")]
+    pub usage_snapshot_interval_secs: u64,
+
+    // RATE LIMITING
+    /// Pushes allowed per second for each tenant, unlimited when unset
+    pub tenant_push_rate_limit_per_second: Option<u32>,
+    /// Pushes allowed per minute for each client, unlimited when unset
+    pub client_push_rate_limit_per_minute: Option<u32>,
+    /// Registrations allowed per minute from each IP address, unlimited when
+    /// unset
+    pub ip_registration_rate_limit_per_minute: Option<u32>,
+    /// Proxies in front of Echo Server that append to `X-Forwarded-For`, the
+    /// client's IP address is read from that header when this is set
+    #[serde(default = "default_trusted_proxy_count")]
+    pub trusted_proxy_count: usize,
+
+    // WEBHOOKS
+    /// Attempts made to deliver each tenant webhook event
+    #[serde(default = "default_webhook_max_attempts")]
+    pub webhook_max_attempts: u32,
+    /// Delay before the first retry, it doubles after every attempt
+    #[serde(default = "default_webhook_retry_delay_ms")]
+    pub webhook_retry_delay_ms: u64,
+    #[serde(default = "default_webhook_timeout_secs")]
+    pub webhook_timeout_secs: u64,
+    /// Allows webhooks to loopback, private and other non-public addresses,
+    /// only meant for local development
+    #[serde(default = "default_webhook_allow_private_addresses")]
+    pub webhook_allow_private_addresses: bool,
+
+    // VERSIONING
+    /// HTTP date after which the unversioned aliases of the `/v1` routes may be
+    /// removed, sent in their `Sunset` header
+    #[serde(default = "default_unversioned_routes_sunset")]
+    pub unversioned_routes_sunset: String,
+
+    // TENANT RESOLUTION
+    /// How client requests without a `:tenant_id` in the path are matched to a
+    /// tenant, comma separated and tried in order
+    #[serde(default = "default_tenant_resolvers")]
+    pub tenant_resolvers: Vec<TenantResolver>,
+    /// The host tenants are subdomains of for the `subdomain` resolver e.g.
+    /// `echo.example.com`
+    pub tenant_subdomain_host: Option<String>,
+
+    // TENANT CACHE
+    /// How long tenant lookups are cached in memory, caching is disabled when 0
+    #[serde(default = "default_tenant_cache_ttl_secs")]
+    pub tenant_cache_ttl_secs: u64,
+    /// Most tenants held in the cache, the least recently used are evicted
+    #[serde(default = "default_tenant_cache_capacity")]
+    pub tenant_cache_capacity: usize,
+
     #[serde(default = "default_is_test", skip)]
     /// This is an internal flag to disable logging, cannot be defined by user
     pub is_test: bool,
@@ -67,14 +165,6 @@ impl Config {
             ));
         }
 
-        if !self.single_tenant_supported_providers().is_empty()
-            && self.tenant_database_url.is_some()
-        {
-            return Err(InvalidConfiguration(
-                "tenant database and providers keys found in config".to_string(),
-            ));
-        }
-
         if let Some(tenant_database_url) = &self.tenant_database_url {
             if tenant_database_url == &self.database_url {
                 return Err(InvalidConfiguration(
@@ -84,6 +174,68 @@ impl Config {
             }
         }
 
+        match chrono::DateTime::parse_from_rfc2822(&self.unversioned_routes_sunset) {
+            // Not fatal so restarts keep working, the date should be moved forward
+            // or the routes removed
+            Ok(sunset) if sunset <= chrono::Utc::now() => {
+                warn!(
+                    "`UNVERSIONED_ROUTES_SUNSET` ({}) has passed, clients are told the \
+                     unversioned routes may already be gone",
+                    self.unversioned_routes_sunset
+                );
+            }
+            Ok(_) => {}
+            Err(_) => {
+                return Err(InvalidConfiguration(
+                    "`UNVERSIONED_ROUTES_SUNSET` must be an HTTP date e.g. Fri, 01 Oct 2027 \
+                     00:00:00 GMT"
+                        .to_string(),
+                ));
+            }
+        }
+
+        // Nothing would ever be allowed, configure the limit as unset instead
+        for (name, limit) in [
+            (
+                "TENANT_PUSH_RATE_LIMIT_PER_SECOND",
+                self.tenant_push_rate_limit_per_second,
+            ),
+            (
+                "CLIENT_PUSH_RATE_LIMIT_PER_MINUTE",
+                self.client_push_rate_limit_per_minute,
+            ),
+            (
+                "IP_REGISTRATION_RATE_LIMIT_PER_MINUTE",
+                self.ip_registration_rate_limit_per_minute,
+            ),
+        ] {
+            if limit == Some(0) {
+                return Err(InvalidConfiguration(format!(
+                    "`{name}` must be at least 1, leave it unset to disable the limit"
+                )));
+            }
+        }
+
+        // A batch of zero would never finish and Postgres rejects a negative `LIMIT`
+        if self.notification_cleanup_batch_size < 1 {
+            return Err(InvalidConfiguration(
+                "`NOTIFICATION_CLEANUP_BATCH_SIZE` must be at least 1".to_string(),
+            ));
+        }
+        if self.client_reaper_batch_size < 1 {
+            return Err(InvalidConfiguration(
+                "`CLIENT_REAPER_BATCH_SIZE` must be at least 1".to_string(),
+            ));
+        }
+
+        if self.tenant_resolvers.contains(&TenantResolver::Subdomain)
+            && self.tenant_subdomain_host.is_none()
+        {
+            return Err(InvalidConfiguration(
+                "the subdomain tenant resolver requires `TENANT_SUBDOMAIN_HOST`".to_string(),
+            ));
+        }
+
         // Check that APNS config is valid when it has been configured
         match self.get_apns_type() {
             Ok(_) => Ok(()),
@@ -94,6 +246,12 @@ impl Config {
         Ok(())
     }
 
+    /// Both provider keys and a tenant database are configured, so the default
+    /// tenant is served alongside the hosted tenants
+    pub fn is_hybrid(&self) -> bool {
+        self.tenant_database_url.is_some() && !self.single_tenant_supported_providers().is_empty()
+    }
+
     pub fn single_tenant_supported_providers(&self) -> Vec<ProviderKind> {
         let mut supported = vec![];
 
@@ -171,6 +329,10 @@ fn default_validate_signatures() -> bool {
     true
 }
 
+fn default_validate_client_signatures() -> bool {
+    true
+}
+
 fn default_relay_url() -> String {
     "https://relay.walletconnect.com".to_string()
 }
@@ -179,6 +341,82 @@ fn default_tenant_id() -> String {
     "0000-0000-0000-0000".to_string()
 }
 
+fn default_client_reaper_interval_secs() -> u64 {
+    60 * 60
+}
+
+fn default_client_reaper_dry_run() -> bool {
+    false
+}
+
+fn default_client_reaper_batch_size() -> i64 {
+    1000
+}
+
+fn default_notification_retention_days() -> u32 {
+    30
+}
+
+fn default_notification_cleanup_interval_secs() -> u64 {
+    60 * 60
+}
+
+fn default_notification_cleanup_batch_size() -> i64 {
+    1000
+}
+
+fn default_usage_snapshot_interval_secs() -> u64 {
+    60 * 60
+}
+
+fn default_trusted_proxy_count() -> usize {
+    0
+}
+
+fn default_unversioned_routes_sunset() -> String {
+    "Fri, 01 Oct 2027 00:00:00 GMT".to_string()
+}
+
+fn default_tenant_deletion_grace_period_days() -> u32 {
+    30
+}
+
+fn default_tenant_purge_interval_secs() -> u64 {
+    60 * 60
+}
+
+fn default_tenant_rename_retry_interval_secs() -> u64 {
+    60
+}
+
+fn default_webhook_max_attempts() -> u32 {
+    5
+}
+
+fn default_webhook_retry_delay_ms() -> u64 {
+    1000
+}
+
+fn default_webhook_timeout_secs() -> u64 {
+    10
+}
+
+fn default_webhook_allow_private_addresses() -> bool {
+    false
+}
+
+fn default_tenant_resolvers() -> Vec<TenantResolver> {
+    vec![TenantResolver::Path]
+}
+
+fn default_tenant_cache_ttl_secs() -> u64 {
+    30
+}
+
+fn default_tenant_cache_capacity() -> usize {
+    10_000
+}
+
 fn default_is_test() -> bool {
     false
 }
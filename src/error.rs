@@ -2,15 +2,106 @@ use {
     crate::{
         handlers::{ErrorField, ErrorLocation, ResponseError},
         log::prelude::*,
-        middleware::validate_signature::{SIGNATURE_HEADER_NAME, TIMESTAMP_HEADER_NAME},
+        middleware::{
+            resolve_tenant::TENANT_CLIENT_KEY_HEADER_NAME,
+            validate_client_jwt::CLIENT_AUTH_HEADER_NAME,
+            validate_signature::{SIGNATURE_HEADER_NAME, TIMESTAMP_HEADER_NAME},
+            validate_tenant_auth::TENANT_AUTH_HEADER_NAME,
+        },
         stores::StoreError,
     },
-    axum::response::{IntoResponse, Response},
+    axum::{
+        http::{header::RETRY_AFTER, HeaderValue},
+        response::{IntoResponse, Response},
+    },
     hyper::StatusCode,
+    schemars::JsonSchema,
+    serde::Serialize,
 };
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// Machine readable codes for the errors in a failed `Response`, these are part
+/// of the API so existing codes must not be renamed
+#[derive(Serialize, Debug, Copy, Clone, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// APNS rejected the notification or the credentials
+    Apns,
+    /// FCM rejected the notification or the credentials
+    Fcm,
+    /// A database query failed
+    Database,
+    /// A request made by Echo Server failed
+    HttpRequest,
+    /// A value that should be hex encoded isn't
+    InvalidHex,
+    /// A signature or public key isn't valid Ed25519
+    InvalidSignature,
+    /// A value that should be base64 encoded isn't
+    InvalidBase64,
+    /// The requested resource doesn't exist
+    NotFound,
+    /// The identifier is already used, e.g. a notification ID sent to
+    /// another client
+    AlreadyExists,
+    /// Another request is delivering the notification, retry it later
+    NotificationInProgress,
+    /// The push type isn't a known provider
+    ProviderNotFound,
+    /// The push type isn't configured for this tenant
+    ProviderNotAvailable,
+    /// The relay's signature headers are missing
+    WebhookValidationFailed,
+    /// The client's JWT is missing or invalid
+    ClientAuthFailed,
+    /// The API key is missing or cannot access this tenant
+    TenantAuthFailed,
+    /// The client key doesn't belong to a tenant
+    InvalidClientKey,
+    /// The pagination cursor is invalid
+    InvalidCursor,
+    /// The tenant ID is invalid or unknown
+    InvalidTenantId,
+    /// The tenant ID is already used by another tenant
+    TenantIdTaken,
+    /// The default tenant cannot be changed through the API
+    DefaultTenantImmutable,
+    /// The tenant was renamed but its clients haven't been moved to the new ID
+    /// yet, they're moved in the background or by renaming the tenant again
+    TenantRenameIncomplete,
+    /// The request doesn't match the server's tenancy mode
+    TenancyMode,
+    /// Encrypted notifications must include a topic
+    MissingTopic,
+    /// The tenant's monthly quota has been used
+    QuotaExceeded,
+    /// Too many requests, see the `Retry-After` header
+    RateLimited,
+    /// `from` is after `to`
+    InvalidDateRange,
+    /// A required field is empty
+    EmptyField,
+    /// A field is longer than allowed
+    FieldTooLong,
+    /// The request body couldn't be parsed
+    InvalidBody,
+    /// The request's path, query or body doesn't match the endpoint
+    InvalidRequest,
+    /// The endpoint doesn't support the request's method
+    MethodNotAllowed,
+    /// The request body isn't JSON or a multipart form
+    UnsupportedContentType,
+    /// The APNS or FCM credentials are invalid
+    InvalidProviderUpdate,
+    /// The APNS type isn't `certificate` or `token`
+    InvalidApnsType,
+    /// The webhook URL or events are invalid
+    InvalidWebhook,
+    /// An unexpected error, please file an issue
+    Internal,
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error(transparent)]
@@ -31,6 +122,9 @@ pub enum Error {
     #[error(transparent)]
     Fcm(#[from] fcm::FcmError),
 
+    #[error("FCM rejected the message: {0:?}")]
+    FcmMessage(fcm::ErrorReason),
+
     #[error(transparent)]
     Io(#[from] std::io::Error),
 
@@ -67,6 +161,9 @@ pub enum Error {
     #[error("the `{0}` field must not be empty")]
     EmptyField(String),
 
+    #[error("the `{0}` field must be at most {1} characters")]
+    FieldTooLong(String, usize),
+
     #[error("a required environment variable cannot be found")]
     RequiredEnvNotFound,
 
@@ -85,6 +182,24 @@ pub enum Error {
     #[error("neither signature or timestamp header cannot not found")]
     MissingAllSignatureHeader,
 
+    #[error("client authorization header cannot not found")]
+    MissingClientAuth,
+
+    #[error("invalid client authorization: {0}")]
+    InvalidClientAuth(String),
+
+    #[error("tenant authorization header cannot not found")]
+    MissingTenantAuth,
+
+    #[error("the provided API key cannot access this tenant")]
+    UnauthorizedTenantAuth,
+
+    #[error("the client key doesn't belong to a tenant")]
+    UnknownClientKey,
+
+    #[error("invalid cursor: {0}")]
+    InvalidCursor(String),
+
     #[error("single-tenant request made while echo server in multi-tenant mode")]
     MissingTenantId,
 
@@ -97,6 +212,15 @@ pub enum Error {
     #[error("invalid tenant id: {0}")]
     InvalidTenantId(String),
 
+    #[error("tenant id is already taken: {0}")]
+    TenantIdTaken(String),
+
+    #[error("the default tenant is configured by the environment and cannot be changed")]
+    DefaultTenantImmutable,
+
+    #[error("tenant was renamed from {0} to {1} but its clients have not been moved yet")]
+    TenantRenameIncomplete(String, String),
+
     #[error("invalid options provided for {0}")]
     InvalidOptionsProvided(String),
 
@@ -106,8 +230,17 @@ pub enum Error {
     #[error(transparent)]
     MultipartError(#[from] axum::extract::multipart::MultipartError),
 
-    #[error("The provided multi-part body did not satisfy the requirements")]
-    InvalidMultipartBody,
+    #[error("the request body could not be parsed: {0}")]
+    InvalidRequestBody(String),
+
+    #[error("unsupported content type: {0}")]
+    UnsupportedContentType(String),
+
+    #[error("the provider update was invalid")]
+    InvalidProviderUpdate(Vec<ErrorField>),
+
+    #[error("the webhook was invalid")]
+    InvalidWebhook(Vec<ErrorField>),
 
     #[error("invalid apns type: {0}")]
     InvalidApnsType(String),
@@ -124,6 +257,18 @@ pub enum Error {
     #[error("client cannot be found")]
     ClientNotFound,
 
+    #[error("rate limit exceeded, retry after {0:?}")]
+    RateLimited(std::time::Duration),
+
+    #[error("monthly quota of {0} notifications exceeded")]
+    QuotaExceeded(i64),
+
+    #[error("notification {0} is already being delivered")]
+    NotificationInProgress(String),
+
+    #[error("invalid date range, `from` must not be after `to`")]
+    InvalidDateRange,
+
     #[error("this should not have occurred; used when case has been handled before")]
     InternalServerError,
 }
@@ -131,46 +276,57 @@ pub enum Error {
 impl IntoResponse for Error {
     fn into_response(self) -> Response {
         error!("responding with error ({:?})", self);
-        match self {
+        let retry_after = match &self {
+            Error::RateLimited(retry_after) => Some(retry_after.as_secs_f64().ceil() as u64),
+            _ => None,
+        };
+        let mut response = match self {
             Error::Apns(e) => crate::handlers::Response::new_failure(StatusCode::INTERNAL_SERVER_ERROR, vec![
                 ResponseError {
                     name: "apns".to_string(),
+                    code: ErrorCode::Apns,
                     message: e.to_string(),
                 }
             ], vec![]),
             Error::Fcm(e) => crate::handlers::Response::new_failure(StatusCode::INTERNAL_SERVER_ERROR, vec![
                 ResponseError {
                     name: "fcm".to_string(),
+                    code: ErrorCode::Fcm,
                     message: e.to_string(),
                 }
             ], vec![]),
             Error::Database(e) => crate::handlers::Response::new_failure(StatusCode::INTERNAL_SERVER_ERROR, vec![
                 ResponseError {
                     name: "sqlx".to_string(),
+                    code: ErrorCode::Database,
                     message: e.to_string(),
                 }
             ], vec![]),
-            Error::Hex(e) => crate::handlers::Response::new_failure(StatusCode::INTERNAL_SERVER_ERROR, vec![
+            Error::Hex(e) => crate::handlers::Response::new_failure(StatusCode::BAD_REQUEST, vec![
                 ResponseError {
                     name: "from_hex".to_string(),
+                    code: ErrorCode::InvalidHex,
                     message: e.to_string(),
                 }
             ], vec![]),
-            Error::Ed25519(e) => crate::handlers::Response::new_failure(StatusCode::INTERNAL_SERVER_ERROR, vec![
+            Error::Ed25519(e) => crate::handlers::Response::new_failure(StatusCode::BAD_REQUEST, vec![
                 ResponseError {
                     name: "ed25519".to_string(),
+                    code: ErrorCode::InvalidSignature,
                     message: e.to_string(),
                 }
             ], vec![]),
             Error::HttpRequest(e) => crate::handlers::Response::new_failure(StatusCode::INTERNAL_SERVER_ERROR, vec![
                 ResponseError {
                     name: "http_request".to_string(),
+                    code: ErrorCode::HttpRequest,
                     message: e.to_string(),
                 }
             ], vec![]),
-            Error::Base64Decode(e) => crate::handlers::Response::new_failure(StatusCode::INTERNAL_SERVER_ERROR, vec![
+            Error::Base64Decode(e) => crate::handlers::Response::new_failure(StatusCode::BAD_REQUEST, vec![
                 ResponseError {
                     name: "base64_decode".to_string(),
+                    code: ErrorCode::InvalidBase64,
                     message: e.to_string(),
                 }
             ], vec![]),
@@ -179,23 +335,42 @@ impl IntoResponse for Error {
                     StatusCode::INTERNAL_SERVER_ERROR,
                     vec![ResponseError {
                         name: "sqlx".to_string(),
+                        code: ErrorCode::Database,
                         message: e.to_string(),
                     }],
                     vec![],
                 ),
                 StoreError::NotFound(entity, id) => crate::handlers::Response::new_failure(
                     StatusCode::NOT_FOUND,
-                    vec![],
+                    vec![ResponseError {
+                        name: "not_found".to_string(),
+                        code: ErrorCode::NotFound,
+                        message: format!("Cannot find {entity} with specified identifier {id}"),
+                    }],
                     vec![ErrorField {
                         field: format!("{}.id", &entity),
                         description: format!("Cannot find {entity} with specified identifier {id}"),
                         location: ErrorLocation::Body, // TODO evaluate if correct location
                     }],
                 ),
+                StoreError::AlreadyExists(entity, id) => crate::handlers::Response::new_failure(
+                    StatusCode::CONFLICT,
+                    vec![ResponseError {
+                        name: "already_exists".to_string(),
+                        code: ErrorCode::AlreadyExists,
+                        message: format!("The {entity} with identifier {id} already exists"),
+                    }],
+                    vec![ErrorField {
+                        field: format!("{}.id", &entity),
+                        description: format!("The {entity} with identifier {id} already exists"),
+                        location: ErrorLocation::Body,
+                    }],
+                ),
             },
             Error::ProviderNotFound(p) => crate::handlers::Response::new_failure(StatusCode::BAD_REQUEST, vec![
                 ResponseError {
                     name: "provider_not_available".to_string(),
+                    code: ErrorCode::ProviderNotFound,
                     message: format!("The requested provider ({}) is not a valid provider", &p),
                 }
             ], vec![
@@ -208,6 +383,7 @@ impl IntoResponse for Error {
             Error::ProviderNotAvailable(p) => crate::handlers::Response::new_failure(StatusCode::BAD_REQUEST, vec![
                 ResponseError {
                     name: "provider_not_available".to_string(),
+                    code: ErrorCode::ProviderNotAvailable,
                     message: format!("The requested provider ({}) is not currently available", &p),
                 }
             ], vec![
@@ -220,6 +396,7 @@ impl IntoResponse for Error {
             Error::MissingAllSignatureHeader => crate::handlers::Response::new_failure(StatusCode::UNAUTHORIZED, vec![
                 ResponseError {
                     name: "webhook_validation_failed".to_string(),
+                    code: ErrorCode::WebhookValidationFailed,
                     message: "Failed to validate webhook, please ensure that all required headers are provided.".to_string(),
                 }
             ], vec![
@@ -237,6 +414,7 @@ impl IntoResponse for Error {
             Error::MissingSignatureHeader => crate::handlers::Response::new_failure(StatusCode::UNAUTHORIZED, vec![
                 ResponseError {
                     name: "webhook_validation_failed".to_string(),
+                    code: ErrorCode::WebhookValidationFailed,
                     message: "Failed to validate webhook, please ensure that all required headers are provided.".to_string(),
                 }
             ], vec![
@@ -249,6 +427,7 @@ impl IntoResponse for Error {
             Error::MissingTimestampHeader => crate::handlers::Response::new_failure(StatusCode::UNAUTHORIZED, vec![
                 ResponseError {
                     name: "webhook_validation_failed".to_string(),
+                    code: ErrorCode::WebhookValidationFailed,
                     message: "Failed to validate webhook, please ensure that all required headers are provided.".to_string(),
                 }
             ], vec![
@@ -258,9 +437,88 @@ impl IntoResponse for Error {
                     location: ErrorLocation::Header
                 }
             ]),
+            Error::MissingClientAuth => crate::handlers::Response::new_failure(StatusCode::UNAUTHORIZED, vec![
+                ResponseError {
+                    name: "client_auth_failed".to_string(),
+                    code: ErrorCode::ClientAuthFailed,
+                    message: "Failed to authenticate client, please provide a JWT signed by the client's did:key.".to_string(),
+                }
+            ], vec![
+                ErrorField {
+                    field: CLIENT_AUTH_HEADER_NAME.to_string(),
+                    description: "Missing bearer token".to_string(),
+                    location: ErrorLocation::Header
+                }
+            ]),
+            Error::InvalidClientAuth(reason) => crate::handlers::Response::new_failure(StatusCode::UNAUTHORIZED, vec![
+                ResponseError {
+                    name: "client_auth_failed".to_string(),
+                    code: ErrorCode::ClientAuthFailed,
+                    message: format!("Failed to authenticate client: {}", &reason),
+                }
+            ], vec![
+                ErrorField {
+                    field: CLIENT_AUTH_HEADER_NAME.to_string(),
+                    description: reason,
+                    location: ErrorLocation::Header
+                }
+            ]),
+            Error::MissingTenantAuth => crate::handlers::Response::new_failure(StatusCode::UNAUTHORIZED, vec![
+                ResponseError {
+                    name: "tenant_auth_failed".to_string(),
+                    code: ErrorCode::TenantAuthFailed,
+                    message: "Failed to authenticate, please provide the tenant's API key.".to_string(),
+                }
+            ], vec![
+                ErrorField {
+                    field: TENANT_AUTH_HEADER_NAME.to_string(),
+                    description: "Missing bearer token".to_string(),
+                    location: ErrorLocation::Header
+                }
+            ]),
+            Error::UnauthorizedTenantAuth => crate::handlers::Response::new_failure(StatusCode::FORBIDDEN, vec![
+                ResponseError {
+                    name: "tenant_auth_failed".to_string(),
+                    code: ErrorCode::TenantAuthFailed,
+                    message: "The provided API key cannot access this tenant.".to_string(),
+                }
+            ], vec![
+                ErrorField {
+                    field: TENANT_AUTH_HEADER_NAME.to_string(),
+                    description: "Invalid API key".to_string(),
+                    location: ErrorLocation::Header
+                }
+            ]),
+            Error::UnknownClientKey => crate::handlers::Response::new_failure(StatusCode::UNAUTHORIZED, vec![
+                ResponseError {
+                    name: "invalid_client_key".to_string(),
+                    code: ErrorCode::InvalidClientKey,
+                    message: "The provided client key doesn't belong to a tenant.".to_string(),
+                }
+            ], vec![
+                ErrorField {
+                    field: TENANT_CLIENT_KEY_HEADER_NAME.to_string(),
+                    description: "Unknown client key".to_string(),
+                    location: ErrorLocation::Header
+                }
+            ]),
+            Error::InvalidCursor(cursor) => crate::handlers::Response::new_failure(StatusCode::BAD_REQUEST, vec![
+                ResponseError {
+                    name: "cursor".to_string(),
+                    code: ErrorCode::InvalidCursor,
+                    message: format!("The provided cursor, {}, is invalid", &cursor),
+                }
+            ], vec![
+                ErrorField {
+                    field: "cursor".to_string(),
+                    description: format!("Invalid cursor, {}", &cursor),
+                    location: ErrorLocation::Query
+                }
+            ]),
             Error::InvalidTenantId(id) => crate::handlers::Response::new_failure(StatusCode::BAD_REQUEST, vec![
                 ResponseError {
                     name: "tenant".to_string(),
+                    code: ErrorCode::InvalidTenantId,
                     message: format!("The provided Tenant ID, {}, is invalid. Please ensure it's valid and the url is in the format /:tenant_id/...path", &id),
                 }
             ], vec![
@@ -274,6 +532,7 @@ impl IntoResponse for Error {
                 StatusCode::BAD_REQUEST,
                 vec![ResponseError {
                     name: "tenancy-mode".to_string(),
+                    code: ErrorCode::TenancyMode,
                     message: "single-tenant request made while echo server in multi-tenant mode".to_string(),
                 }],
                 vec![],
@@ -282,6 +541,7 @@ impl IntoResponse for Error {
                 StatusCode::BAD_REQUEST,
                 vec![ResponseError {
                     name: "tenancy-mode".to_string(),
+                    code: ErrorCode::TenancyMode,
                     message: "multi-tenant request made while echo server in single-tenant mode".to_string(),
                 }],
                 vec![],
@@ -290,22 +550,211 @@ impl IntoResponse for Error {
                 StatusCode::BAD_REQUEST,
                 vec![ResponseError {
                     name: "topic".to_string(),
+                    code: ErrorCode::MissingTopic,
                     message: "encrypted push notifications require topic to be set".to_string(),
                 }],
                 vec![],
             ),
             // If the client cannot be found we gracefully handle this
             Error::ClientNotFound => crate::handlers::Response::new_success(StatusCode::ACCEPTED),
-            e => crate::handlers::Response::new_failure(StatusCode::INTERNAL_SERVER_ERROR, vec![
+            Error::QuotaExceeded(quota) => crate::handlers::Response::new_failure(StatusCode::TOO_MANY_REQUESTS, vec![
+                ResponseError {
+                    name: "quota_exceeded".to_string(),
+                    code: ErrorCode::QuotaExceeded,
+                    message: format!("The tenant's monthly quota of {quota} notifications has been used, it resets at the start of next month (UTC)"),
+                }
+            ], vec![]),
+            Error::NotificationInProgress(id) => crate::handlers::Response::new_failure(StatusCode::CONFLICT, vec![
+                ResponseError {
+                    name: "notification_in_progress".to_string(),
+                    code: ErrorCode::NotificationInProgress,
+                    message: format!("The notification ({id}) is being delivered by another request, retry it later"),
+                }
+            ], vec![]),
+            Error::TenantRenameIncomplete(from, to) => crate::handlers::Response::new_failure(StatusCode::SERVICE_UNAVAILABLE, vec![
+                ResponseError {
+                    name: "tenant_rename_incomplete".to_string(),
+                    code: ErrorCode::TenantRenameIncomplete,
+                    message: format!("The tenant was renamed from {from} to {to} but its clients couldn't be moved yet, they're moved in the background or by renaming {to} again"),
+                }
+            ], vec![]),
+            Error::InvalidDateRange => crate::handlers::Response::new_failure(StatusCode::BAD_REQUEST, vec![
+                ResponseError {
+                    name: "date_range".to_string(),
+                    code: ErrorCode::InvalidDateRange,
+                    message: "`from` must not be after `to`".to_string(),
+                }
+            ], vec![
+                ErrorField {
+                    field: "from".to_string(),
+                    description: "Must not be after `to`".to_string(),
+                    location: ErrorLocation::Query
+                }
+            ]),
+            Error::InvalidRequestBody(reason) => crate::handlers::Response::new_failure(StatusCode::BAD_REQUEST, vec![
+                ResponseError {
+                    name: "invalid_body".to_string(),
+                    code: ErrorCode::InvalidBody,
+                    message: reason,
+                }
+            ], vec![]),
+            Error::UnsupportedContentType(content_type) => crate::handlers::Response::new_failure(StatusCode::UNSUPPORTED_MEDIA_TYPE, vec![
+                ResponseError {
+                    name: "unsupported_content_type".to_string(),
+                    code: ErrorCode::UnsupportedContentType,
+                    message: format!("Unsupported content type ({content_type}), expected `application/json` or `multipart/form-data`"),
+                }
+            ], vec![]),
+            Error::InvalidProviderUpdate(fields) => crate::handlers::Response::new_failure(StatusCode::BAD_REQUEST, vec![
+                ResponseError {
+                    name: "invalid_provider_update".to_string(),
+                    code: ErrorCode::InvalidProviderUpdate,
+                    message: "The provider configuration provided is invalid".to_string(),
+                }
+            ], fields),
+            Error::InvalidWebhook(fields) => crate::handlers::Response::new_failure(StatusCode::BAD_REQUEST, vec![
+                ResponseError {
+                    name: "invalid_webhook".to_string(),
+                    code: ErrorCode::InvalidWebhook,
+                    message: "The webhook provided is invalid".to_string(),
+                }
+            ], fields),
+            Error::TenantIdTaken(id) => crate::handlers::Response::new_failure(StatusCode::CONFLICT, vec![
+                ResponseError {
+                    name: "tenant_id_taken".to_string(),
+                    code: ErrorCode::TenantIdTaken,
+                    message: format!("The Tenant ID, {}, is already in use", &id),
+                }
+            ], vec![
+                ErrorField {
+                    field: "id".to_string(),
+                    description: format!("Tenant ID already in use, {}", &id),
+                    location: ErrorLocation::Body
+                }
+            ]),
+            Error::DefaultTenantImmutable => crate::handlers::Response::new_failure(StatusCode::BAD_REQUEST, vec![
+                ResponseError {
+                    name: "default_tenant_immutable".to_string(),
+                    code: ErrorCode::DefaultTenantImmutable,
+                    message: "The default tenant is configured by the environment and cannot be changed through the API".to_string(),
+                }
+            ], vec![]),
+            Error::EmptyField(field) => crate::handlers::Response::new_failure(StatusCode::BAD_REQUEST, vec![
+                ResponseError {
+                    name: "empty_field".to_string(),
+                    code: ErrorCode::EmptyField,
+                    message: format!("The `{}` field must not be empty", &field),
+                }
+            ], vec![
+                ErrorField {
+                    field,
+                    description: "Must not be empty".to_string(),
+                    location: ErrorLocation::Body
+                }
+            ]),
+            Error::FieldTooLong(field, max_length) => crate::handlers::Response::new_failure(StatusCode::BAD_REQUEST, vec![
+                ResponseError {
+                    name: "field_too_long".to_string(),
+                    code: ErrorCode::FieldTooLong,
+                    message: format!("The `{}` field must be at most {} characters", &field, max_length),
+                }
+            ], vec![
+                ErrorField {
+                    field,
+                    description: format!("Must be at most {max_length} characters"),
+                    location: ErrorLocation::Body
+                }
+            ]),
+            Error::InvalidApnsType(apns_type) => crate::handlers::Response::new_failure(StatusCode::BAD_REQUEST, vec![
+                ResponseError {
+                    name: "invalid_apns_type".to_string(),
+                    code: ErrorCode::InvalidApnsType,
+                    message: format!("The APNS type, {}, is invalid, expected `certificate` or `token`", &apns_type),
+                }
+            ], vec![]),
+            Error::InvalidOptionsProvided(options) => crate::handlers::Response::new_failure(StatusCode::BAD_REQUEST, vec![
+                ResponseError {
+                    name: "invalid_body".to_string(),
+                    code: ErrorCode::InvalidBody,
+                    message: format!("Invalid options provided for {}", &options),
+                }
+            ], vec![]),
+            Error::Json(e) => crate::handlers::Response::new_failure(StatusCode::BAD_REQUEST, vec![
+                ResponseError {
+                    name: "invalid_body".to_string(),
+                    code: ErrorCode::InvalidBody,
+                    message: e.to_string(),
+                }
+            ], vec![]),
+            Error::MultipartError(e) => crate::handlers::Response::new_failure(StatusCode::BAD_REQUEST, vec![
+                ResponseError {
+                    name: "invalid_body".to_string(),
+                    code: ErrorCode::InvalidBody,
+                    message: e.to_string(),
+                }
+            ], vec![]),
+            Error::FromUtf8Error(e) => crate::handlers::Response::new_failure(StatusCode::BAD_REQUEST, vec![
+                ResponseError {
+                    name: "invalid_body".to_string(),
+                    code: ErrorCode::InvalidBody,
+                    message: e.to_string(),
+                }
+            ], vec![]),
+            Error::FromRequestError | Error::ToBytesError => crate::handlers::Response::new_failure(StatusCode::BAD_REQUEST, vec![
+                ResponseError {
+                    name: "invalid_body".to_string(),
+                    code: ErrorCode::InvalidBody,
+                    message: "The request body could not be read".to_string(),
+                }
+            ], vec![]),
+            Error::RateLimited(retry_after) => crate::handlers::Response::new_failure(StatusCode::TOO_MANY_REQUESTS, vec![
+                ResponseError {
+                    name: "rate_limited".to_string(),
+                    code: ErrorCode::RateLimited,
+                    message: format!("Too many requests, retry after {} seconds", retry_after.as_secs_f64().ceil()),
+                }
+            ], vec![]),
+            // FCM answered but refused the message, e.g. the device's token is no
+            // longer registered
+            Error::FcmMessage(reason) => crate::handlers::Response::new_failure(StatusCode::BAD_GATEWAY, vec![
+                ResponseError {
+                    name: "fcm".to_string(),
+                    code: ErrorCode::Fcm,
+                    message: format!("FCM rejected the message: {reason:?}"),
+                }
+            ], vec![]),
+            Error::NoApnsConfigured => crate::handlers::Response::new_failure(StatusCode::BAD_REQUEST, vec![
+                ResponseError {
+                    name: "provider_not_available".to_string(),
+                    code: ErrorCode::ProviderNotAvailable,
+                    message: "APNS is not configured".to_string(),
+                }
+            ], vec![]),
+            // Only raised while starting up or by bugs, there's nothing the caller
+            // can do about them
+            Error::Envy(_)
+            | Error::Trace(_)
+            | Error::Metrics(_)
+            | Error::Prometheus(_)
+            | Error::Io(_)
+            | Error::DatabaseMigration(_)
+            | Error::RequiredEnvNotFound
+            | Error::InvalidConfiguration(_)
+            | Error::InternalServerError => crate::handlers::Response::new_failure(StatusCode::INTERNAL_SERVER_ERROR, vec![
                 ResponseError {
                     name: "unknown_error".to_string(),
+                    code: ErrorCode::Internal,
                     message: "This error should not have occurred. Please file an issue at: https://github.com/walletconnect/echo-server".to_string(),
-                },
-                ResponseError {
-                    name: "dbg".to_string(),
-                    message: format!("{e:?}"),
                 }
             ], vec![])
-        }.into_response()
+        }.into_response();
+
+        if let Some(retry_after) = retry_after {
+            response
+                .headers_mut()
+                .insert(RETRY_AFTER, HeaderValue::from(retry_after));
+        }
+
+        response
     }
 }
@@ -1,36 +1,72 @@
 use {
-    crate::{error::Error, increment_counter, state::AppState, stores::tenant::TenantUpdateParams},
+    crate::{
+        error::Error,
+        handlers::{record_audit, Response},
+        increment_counter,
+        middleware::{
+            request_id::RequestId,
+            validate_tenant_auth::{generate_api_key, hash_api_key, RequireTenantAuth},
+        },
+        state::AppState,
+        stores::tenant::{TenantAuditAction, TenantUpdateParams},
+    },
     axum::{extract::State, Json},
+    schemars::JsonSchema,
     serde::{Deserialize, Serialize},
+    serde_json::json,
     std::sync::Arc,
 };
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, JsonSchema)]
 pub struct TenantRegisterBody {
     /// The project ID
     pub id: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, JsonSchema)]
 pub struct TenantRegisterResponse {
     /// The generated tenant url for the specified project id
     pub url: String,
+    /// The tenant's API key, this is only returned once
+    pub api_key: String,
+    /// Identifies the tenant on client requests, unlike the API key it's safe
+    /// to ship in apps
+    pub client_key: String,
 }
 
 pub async fn handler(
     State(state): State<Arc<AppState>>,
+    tenant_auth: RequireTenantAuth,
+    request_id: RequestId,
     Json(body): Json<TenantRegisterBody>,
-) -> Result<Json<TenantRegisterResponse>, Error> {
-    // TODO authentication
-    // TODO validation
+) -> Result<Response<TenantRegisterResponse>, Error> {
+    let actor = tenant_auth.check_admin()?;
 
     let params = TenantUpdateParams { id: body.id };
+    params.validate()?;
 
-    let tenant = state.tenant_store.create_tenant(params).await?;
+    let api_key = generate_api_key();
+    let tenant = state
+        .tenant_store
+        .create_tenant(params, &hash_api_key(&api_key))
+        .await?;
+
+    record_audit(
+        &state,
+        &tenant.id,
+        &actor,
+        TenantAuditAction::CreateTenant,
+        json!({ "id": tenant.id }),
+        &request_id,
+    )
+    .await;
 
     increment_counter!(state.metrics, registered_tenants);
 
-    Ok(Json(TenantRegisterResponse {
+    Ok(TenantRegisterResponse {
         url: format!("{}/{}", state.config.public_url, tenant.id),
-    }))
+        api_key,
+        client_key: tenant.client_key.unwrap_or_default(),
+    }
+    .into())
 }
@@ -2,8 +2,9 @@ use {
     crate::{
         decrement_counter,
         error::Result,
-        handlers::{Response, DECENTRALIZED_IDENTIFIER_PREFIX},
+        handlers::{ClientPath, Response, DECENTRALIZED_IDENTIFIER_PREFIX},
         log::prelude::*,
+        middleware::{resolve_tenant::ResolvedTenant, validate_client_jwt::RequireClientAuth},
         state::AppState,
     },
     axum::extract::{Path, State as StateExtractor},
@@ -11,17 +12,20 @@ use {
 };
 
 pub async fn handler(
-    Path((tenant_id, id)): Path<(String, String)>,
+    Path(ClientPath { id }): Path<ClientPath>,
     StateExtractor(state): StateExtractor<Arc<AppState>>,
+    ResolvedTenant(tenant_id): ResolvedTenant,
+    client_auth: RequireClientAuth,
 ) -> Result<Response> {
     let id = id
         .trim_start_matches(DECENTRALIZED_IDENTIFIER_PREFIX)
         .to_string();
+    client_auth.check(&id)?;
 
-    state.client_store.delete_client(&tenant_id, &id).await?;
-    info!("client ({}) deleted for tenant ({})", id, tenant_id);
-
-    decrement_counter!(state.metrics, registered_clients);
+    if state.client_store.delete_client(&tenant_id, &id).await? {
+        info!("client ({}) deleted for tenant ({})", id, tenant_id);
+        decrement_counter!(state.metrics, registered_clients);
+    }
 
     Ok(Response::default())
 }
@@ -0,0 +1,33 @@
+use {
+    crate::{
+        error::Result,
+        handlers::{Response, DECENTRALIZED_IDENTIFIER_PREFIX},
+        log::prelude::*,
+        middleware::validate_client_jwt::RequireClientAuth,
+        state::AppState,
+    },
+    axum::extract::{Path, State as StateExtractor},
+    std::sync::Arc,
+};
+
+pub async fn handler(
+    Path((tenant_id, id, device_id)): Path<(String, String, String)>,
+    StateExtractor(state): StateExtractor<Arc<AppState>>,
+    client_auth: RequireClientAuth,
+) -> Result<Response> {
+    let id = id
+        .trim_start_matches(DECENTRALIZED_IDENTIFIER_PREFIX)
+        .to_string();
+    client_auth.check(&id)?;
+
+    state
+        .client_store
+        .delete_device(&tenant_id, &id, &device_id)
+        .await?;
+    info!(
+        "device ({}) deleted from client ({}) for tenant ({})",
+        device_id, id, tenant_id
+    );
+
+    Ok(Response::default())
+}
@@ -1,25 +1,39 @@
 use {
-    crate::{decrement_counter, error::Error, state::AppState},
-    axum::{
-        extract::{Path, State},
-        Json,
+    crate::{
+        decrement_counter,
+        error::Error,
+        handlers::{record_audit, Response},
+        middleware::{request_id::RequestId, validate_tenant_auth::RequireTenantAuth},
+        state::AppState,
+        stores::tenant::TenantAuditAction,
     },
-    serde::Serialize,
+    axum::extract::{Path, State},
+    serde_json::json,
     std::sync::Arc,
 };
 
-#[derive(Serialize)]
-pub struct DeleteTenantResponse {
-    success: bool,
-}
-
 pub async fn handler(
     State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
-) -> Result<Json<DeleteTenantResponse>, Error> {
+    tenant_auth: RequireTenantAuth,
+    request_id: RequestId,
+) -> Result<Response, Error> {
+    let tenant = state.tenant_store.get_tenant(&id).await?;
+    let actor = tenant_auth.check(&tenant)?;
+
     state.tenant_store.delete_tenant(&id).await?;
 
+    record_audit(
+        &state,
+        &id,
+        &actor,
+        TenantAuditAction::DeleteTenant,
+        json!({}),
+        &request_id,
+    )
+    .await;
+
     decrement_counter!(state.metrics, registered_tenants);
 
-    Ok(Json(DeleteTenantResponse { success: true }))
+    Ok(Response::default())
 }
@@ -0,0 +1,75 @@
+use {
+    crate::{
+        error::{Error, Error::InvalidCursor},
+        handlers::{
+            pagination::{decode_cursor, encode_cursor, page_size},
+            Response,
+        },
+        middleware::validate_tenant_auth::RequireTenantAuth,
+        state::AppState,
+        stores::tenant::TenantAuditEntry,
+    },
+    axum::extract::{Path, Query, State},
+    schemars::JsonSchema,
+    serde::{Deserialize, Serialize},
+    std::sync::Arc,
+};
+
+#[derive(Serialize, Deserialize, Default, JsonSchema)]
+pub struct AuditLogQuery {
+    /// The `next_cursor` from the previous page
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct AuditLogResponse {
+    /// Newest entries first
+    pub entries: Vec<TenantAuditEntry>,
+    /// Pass as the `cursor` to fetch the next page, `None` on the last page
+    pub next_cursor: Option<String>,
+}
+
+/// Lists the configuration changes made to a tenant. Operators can still read
+/// the log of a deleted tenant
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    tenant_auth: RequireTenantAuth,
+    Query(query): Query<AuditLogQuery>,
+) -> Result<Response<AuditLogResponse>, Error> {
+    if tenant_auth.check_admin().is_err() {
+        let tenant = state.tenant_store.get_tenant(&id).await?;
+        tenant_auth.check(&tenant)?;
+    }
+
+    let limit = page_size(query.limit);
+    let before = match &query.cursor {
+        Some(cursor) => Some(
+            decode_cursor(cursor)?
+                .parse::<i64>()
+                .map_err(|_| InvalidCursor(cursor.clone()))?,
+        ),
+        None => None,
+    };
+
+    // Fetch an extra entry to find out if there is another page
+    let mut entries = state
+        .tenant_store
+        .list_audit_entries(&id, before, limit + 1)
+        .await?;
+
+    let mut next_cursor = None;
+    if entries.len() as i64 > limit {
+        entries.truncate(limit as usize);
+        next_cursor = entries
+            .last()
+            .map(|entry| encode_cursor(&entry.id.to_string()));
+    }
+
+    Ok(AuditLogResponse {
+        entries,
+        next_cursor,
+    }
+    .into())
+}
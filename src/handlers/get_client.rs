@@ -0,0 +1,96 @@
+use {
+    crate::{
+        error::Result,
+        handlers::{Response, DECENTRALIZED_IDENTIFIER_PREFIX},
+        middleware::validate_tenant_auth::RequireTenantAuth,
+        state::AppState,
+        stores::client::{Client, Device, DeviceMetadata},
+    },
+    axum::extract::{Path, State as StateExtractor},
+    chrono::{DateTime, Utc},
+    schemars::JsonSchema,
+    serde::{Deserialize, Serialize},
+    std::sync::Arc,
+};
+
+/// Number of characters of a device token shown at either end when masked
+const TOKEN_VISIBLE_CHARACTERS: usize = 4;
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct ClientResponse {
+    pub id: String,
+    pub devices: Vec<DeviceResponse>,
+    pub created_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+    pub last_push_success_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct DeviceResponse {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub push_type: String,
+    /// The device token with all but the first and last few characters hidden
+    pub token: String,
+    pub metadata: DeviceMetadata,
+}
+
+impl From<Client> for ClientResponse {
+    fn from(client: Client) -> Self {
+        ClientResponse {
+            id: client.id,
+            devices: client.devices.into_iter().map(Into::into).collect(),
+            created_at: client.created_at,
+            last_seen_at: client.last_seen_at,
+            last_push_success_at: client.last_push_success_at,
+        }
+    }
+}
+
+impl From<Device> for DeviceResponse {
+    fn from(device: Device) -> Self {
+        DeviceResponse {
+            id: device.id,
+            push_type: device.push_type.into(),
+            token: mask_token(&device.token),
+            metadata: device.metadata,
+        }
+    }
+}
+
+pub async fn handler(
+    Path((tenant_id, id)): Path<(String, String)>,
+    StateExtractor(state): StateExtractor<Arc<AppState>>,
+    tenant_auth: RequireTenantAuth,
+) -> Result<Response<ClientResponse>> {
+    let tenant = state.tenant_store.get_tenant(&tenant_id).await?;
+    tenant_auth.check(&tenant)?;
+
+    let id = id.trim_start_matches(DECENTRALIZED_IDENTIFIER_PREFIX);
+    let client = state.client_store.get_client(&tenant.id, id).await?;
+
+    Ok(ClientResponse::from(client).into())
+}
+
+/// Hides all but the start and end of a token so it can be matched against a
+/// device without exposing it
+pub fn mask_token(token: &str) -> String {
+    let characters = token.chars().collect::<Vec<_>>();
+    if characters.len() <= TOKEN_VISIBLE_CHARACTERS * 2 {
+        return "*".repeat(characters.len());
+    }
+
+    let start = characters[..TOKEN_VISIBLE_CHARACTERS]
+        .iter()
+        .collect::<String>();
+    let end = characters[characters.len() - TOKEN_VISIBLE_CHARACTERS..]
+        .iter()
+        .collect::<String>();
+
+    format!(
+        "{}{}{}",
+        start,
+        "*".repeat(characters.len() - TOKEN_VISIBLE_CHARACTERS * 2),
+        end
+    )
+}
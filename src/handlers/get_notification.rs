@@ -0,0 +1,59 @@
+use {
+    crate::{
+        error::Result,
+        handlers::Response,
+        middleware::validate_tenant_auth::RequireTenantAuth,
+        state::AppState,
+        stores::notification::{Notification, NotificationStatus, ReceivedPayload},
+    },
+    axum::extract::{Path, State as StateExtractor},
+    chrono::{DateTime, Utc},
+    schemars::JsonSchema,
+    serde::{Deserialize, Serialize},
+    std::sync::Arc,
+};
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct NotificationHistoryResponse {
+    pub id: String,
+    pub client_id: String,
+    /// Named so it doesn't replace the envelope's `status`
+    pub delivery_status: NotificationStatus,
+    /// The most recent payloads received for the notification, oldest first
+    pub history: Vec<ReceivedPayload>,
+    pub created_at: DateTime<Utc>,
+    pub last_received_at: DateTime<Utc>,
+}
+
+impl From<Notification> for NotificationHistoryResponse {
+    fn from(notification: Notification) -> Self {
+        NotificationHistoryResponse {
+            id: notification.id,
+            client_id: notification.client_id,
+            delivery_status: notification.status,
+            history: notification
+                .previous_payloads
+                .into_iter()
+                .map(|payload| payload.0)
+                .collect(),
+            created_at: notification.created_at,
+            last_received_at: notification.last_received_at,
+        }
+    }
+}
+
+pub async fn handler(
+    Path((tenant_id, id)): Path<(String, String)>,
+    StateExtractor(state): StateExtractor<Arc<AppState>>,
+    tenant_auth: RequireTenantAuth,
+) -> Result<Response<NotificationHistoryResponse>> {
+    let tenant = state.tenant_store.get_tenant(&tenant_id).await?;
+    tenant_auth.check(&tenant)?;
+
+    let notification = state
+        .notification_store
+        .get_notification(&tenant.id, &id)
+        .await?;
+
+    Ok(NotificationHistoryResponse::from(notification).into())
+}
@@ -1,25 +1,31 @@
 use {
-    crate::{error::Error, providers::ProviderKind, state::AppState, stores::tenant::ApnsType},
-    axum::{
-        extract::{Path, State},
-        Json,
+    crate::{
+        error::Error,
+        handlers::Response,
+        providers::ProviderKind,
+        state::AppState,
+        stores::tenant::ApnsType,
     },
+    axum::extract::{Path, State},
+    schemars::JsonSchema,
     serde::Serialize,
     std::sync::Arc,
 };
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct GetTenantResponse {
     url: String,
     enabled_providers: Vec<String>,
     apns_topic: Option<String>,
     apns_type: Option<ApnsType>,
+    /// Identifies the tenant on client requests, it's publishable
+    client_key: Option<String>,
 }
 
 pub async fn handler(
     State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
-) -> Result<Json<GetTenantResponse>, Error> {
+) -> Result<Response<GetTenantResponse>, Error> {
     let tenant = state.tenant_store.get_tenant(&id).await?;
 
     let providers = tenant.providers();
@@ -29,6 +35,7 @@ pub async fn handler(
         enabled_providers: tenant.providers().iter().map(Into::into).collect(),
         apns_topic: None,
         apns_type: None,
+        client_key: tenant.client_key.clone(),
     };
 
     if providers.contains(&ProviderKind::Apns) {
@@ -36,5 +43,5 @@ pub async fn handler(
         res.apns_type = tenant.apns_type;
     }
 
-    Ok(Json(res))
+    Ok(res.into())
 }
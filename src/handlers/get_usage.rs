@@ -0,0 +1,70 @@
+use {
+    crate::{
+        error::{Error, Error::InvalidDateRange},
+        handlers::Response,
+        middleware::validate_tenant_auth::RequireTenantAuth,
+        state::AppState,
+        stores::usage::{DailyUsage, UsageCounts},
+    },
+    axum::extract::{Path, Query, State},
+    chrono::{Datelike, NaiveDate, Utc},
+    schemars::JsonSchema,
+    serde::{Deserialize, Serialize},
+    std::sync::Arc,
+};
+
+#[derive(Serialize, Deserialize, Default, JsonSchema)]
+pub struct UsageQuery {
+    /// First day to include, defaults to the start of the current month
+    pub from: Option<NaiveDate>,
+    /// Last day to include, defaults to today
+    pub to: Option<NaiveDate>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct UsageResponse {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    /// Days without any usage or a snapshot of the registered clients are
+    /// omitted
+    pub usage: Vec<DailyUsage>,
+    pub totals: UsageCounts,
+    /// The number of clients currently registered, `usage` has a daily
+    /// snapshot of it
+    pub registered_clients: i64,
+    pub monthly_notification_quota: Option<i64>,
+}
+
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    tenant_auth: RequireTenantAuth,
+    Query(query): Query<UsageQuery>,
+) -> Result<Response<UsageResponse>, Error> {
+    let tenant = state.tenant_store.get_tenant(&id).await?;
+    tenant_auth.check(&tenant)?;
+
+    let today = Utc::now().date_naive();
+    let to = query.to.unwrap_or(today);
+    let from = query.from.unwrap_or_else(|| to.with_day(1).unwrap_or(to));
+    if from > to {
+        return Err(InvalidDateRange);
+    }
+
+    let usage = state.usage_store.get_usage(&tenant.id, from, to).await?;
+    let totals = state
+        .usage_store
+        .get_usage_totals(&tenant.id, from, to)
+        .await?;
+    let registered_clients = state.client_store.count_clients(&tenant.id).await?;
+
+    Ok(UsageResponse {
+        from,
+        to,
+        usage,
+        totals,
+        registered_clients,
+        monthly_notification_quota: tenant.monthly_notification_quota,
+    }
+    .into())
+}
@@ -8,9 +8,10 @@ pub async fn handler(ExtractState(state): ExtractState<Arc<AppState>>) -> impl I
     (
         StatusCode::OK,
         format!(
-            "OK, echo-server v{}\nmultitenant: {}\ntelemetry: {}",
+            "OK, echo-server v{}\nmultitenant: {}\nhybrid: {}\ntelemetry: {}",
             state.build_info.crate_info.version,
             state.is_multitenant(),
+            state.is_hybrid(),
             state.metrics.is_some()
         ),
     )
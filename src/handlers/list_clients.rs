@@ -0,0 +1,81 @@
+use {
+    crate::{
+        error::Result,
+        handlers::{
+            get_client::ClientResponse,
+            pagination::{decode_cursor, encode_cursor, page_size},
+            Response,
+        },
+        middleware::validate_tenant_auth::RequireTenantAuth,
+        providers::ProviderKind,
+        state::AppState,
+        stores::client::ClientListParams,
+    },
+    axum::extract::{Path, Query, State as StateExtractor},
+    chrono::{DateTime, Utc},
+    schemars::JsonSchema,
+    serde::{Deserialize, Serialize},
+    std::sync::Arc,
+};
+
+#[derive(Serialize, Deserialize, Default, JsonSchema)]
+pub struct ListClientsQuery {
+    /// The `next_cursor` from the previous page
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+    /// Only return clients with a device registered for this provider
+    #[serde(rename = "type")]
+    pub push_type: Option<String>,
+    pub created_after: Option<DateTime<Utc>>,
+    pub created_before: Option<DateTime<Utc>>,
+    pub last_seen_after: Option<DateTime<Utc>>,
+    pub last_seen_before: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct ListClientsResponse {
+    pub clients: Vec<ClientResponse>,
+    /// Pass as the `cursor` to fetch the next page, `None` on the last page
+    pub next_cursor: Option<String>,
+}
+
+pub async fn handler(
+    Path(tenant_id): Path<String>,
+    StateExtractor(state): StateExtractor<Arc<AppState>>,
+    tenant_auth: RequireTenantAuth,
+    Query(query): Query<ListClientsQuery>,
+) -> Result<Response<ListClientsResponse>> {
+    let tenant = state.tenant_store.get_tenant(&tenant_id).await?;
+    tenant_auth.check(&tenant)?;
+
+    let limit = page_size(query.limit);
+
+    let params = ClientListParams {
+        after: query.cursor.as_deref().map(decode_cursor).transpose()?,
+        // Fetch an extra client to find out if there is another page
+        limit: limit + 1,
+        push_type: query
+            .push_type
+            .as_deref()
+            .map(ProviderKind::try_from)
+            .transpose()?,
+        created_after: query.created_after,
+        created_before: query.created_before,
+        last_seen_after: query.last_seen_after,
+        last_seen_before: query.last_seen_before,
+    };
+
+    let mut clients = state.client_store.list_clients(&tenant.id, params).await?;
+
+    let mut next_cursor = None;
+    if clients.len() as i64 > limit {
+        clients.truncate(limit as usize);
+        next_cursor = clients.last().map(|client| encode_cursor(&client.id));
+    }
+
+    Ok(ListClientsResponse {
+        clients: clients.into_iter().map(Into::into).collect(),
+        next_cursor,
+    }
+    .into())
+}
@@ -0,0 +1,109 @@
+use {
+    crate::{
+        error::Error,
+        handlers::{
+            pagination::{decode_cursor, encode_cursor, page_size},
+            Response,
+        },
+        middleware::validate_tenant_auth::RequireTenantAuth,
+        providers::ProviderKind,
+        state::AppState,
+        stores::tenant::{ApnsType, Tenant, TenantListParams},
+    },
+    axum::extract::{Query, State},
+    chrono::{DateTime, Utc},
+    schemars::JsonSchema,
+    serde::{Deserialize, Serialize},
+    std::sync::Arc,
+};
+
+#[derive(Serialize, Deserialize, Default, JsonSchema)]
+pub struct ListTenantsQuery {
+    /// The `next_cursor` from the previous page
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+    /// Only return tenants with this provider configured
+    pub provider: Option<String>,
+    pub created_after: Option<DateTime<Utc>>,
+    pub created_before: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct TenantSummary {
+    pub id: String,
+    pub url: String,
+    pub enabled_providers: Vec<String>,
+    pub apns_type: Option<ApnsType>,
+    pub client_count: i64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct ListTenantsResponse {
+    pub tenants: Vec<TenantSummary>,
+    /// Pass as the `cursor` to fetch the next page, `None` on the last page
+    pub next_cursor: Option<String>,
+}
+
+/// Lists every tenant so this requires the admin API key
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    tenant_auth: RequireTenantAuth,
+    Query(query): Query<ListTenantsQuery>,
+) -> Result<Response<ListTenantsResponse>, Error> {
+    tenant_auth.check_admin()?;
+
+    let limit = page_size(query.limit);
+
+    let params = TenantListParams {
+        after: query.cursor.as_deref().map(decode_cursor).transpose()?,
+        // Fetch an extra tenant to find out if there is another page
+        limit: limit + 1,
+        provider: query
+            .provider
+            .as_deref()
+            .map(ProviderKind::try_from)
+            .transpose()?,
+        created_after: query.created_after,
+        created_before: query.created_before,
+    };
+
+    let mut tenants = state.tenant_store.list_tenants(params).await?;
+
+    let mut next_cursor = None;
+    if tenants.len() as i64 > limit {
+        tenants.truncate(limit as usize);
+        next_cursor = tenants.last().map(|tenant| encode_cursor(&tenant.id));
+    }
+
+    let ids = tenants
+        .iter()
+        .map(|tenant| tenant.id.clone())
+        .collect::<Vec<_>>();
+    let client_counts = state.client_store.count_clients_by_tenant(&ids).await?;
+
+    Ok(ListTenantsResponse {
+        tenants: tenants
+            .into_iter()
+            .map(|tenant| {
+                let client_count = client_counts.get(&tenant.id).copied().unwrap_or_default();
+                summary(&state, tenant, client_count)
+            })
+            .collect(),
+        next_cursor,
+    }
+    .into())
+}
+
+fn summary(state: &AppState, tenant: Tenant, client_count: i64) -> TenantSummary {
+    TenantSummary {
+        url: format!("{}/{}", state.config.public_url, tenant.id),
+        enabled_providers: tenant.providers().iter().map(Into::into).collect(),
+        apns_type: tenant.get_apns_type(),
+        client_count,
+        id: tenant.id,
+        created_at: tenant.created_at,
+        updated_at: tenant.updated_at,
+    }
+}
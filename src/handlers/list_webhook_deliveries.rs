@@ -0,0 +1,75 @@
+use {
+    crate::{
+        error::{Error, Error::InvalidCursor},
+        handlers::{
+            pagination::{decode_cursor, encode_cursor, page_size},
+            Response,
+        },
+        middleware::validate_tenant_auth::RequireTenantAuth,
+        state::AppState,
+        stores::tenant::WebhookDelivery,
+    },
+    axum::extract::{Path, Query, State},
+    schemars::JsonSchema,
+    serde::{Deserialize, Serialize},
+    std::sync::Arc,
+};
+
+#[derive(Serialize, Deserialize, Default, JsonSchema)]
+pub struct WebhookDeliveriesQuery {
+    /// The `next_cursor` from the previous page
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct WebhookDeliveriesResponse {
+    /// Newest deliveries first
+    pub deliveries: Vec<WebhookDelivery>,
+    /// Pass as the `cursor` to fetch the next page, `None` on the last page
+    pub next_cursor: Option<String>,
+}
+
+/// Lists the webhook events sent to a tenant and whether they were delivered.
+/// Operators can still read the deliveries of a deleted tenant
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    tenant_auth: RequireTenantAuth,
+    Query(query): Query<WebhookDeliveriesQuery>,
+) -> Result<Response<WebhookDeliveriesResponse>, Error> {
+    if tenant_auth.check_admin().is_err() {
+        let tenant = state.tenant_store.get_tenant(&id).await?;
+        tenant_auth.check(&tenant)?;
+    }
+
+    let limit = page_size(query.limit);
+    let before = match &query.cursor {
+        Some(cursor) => Some(
+            decode_cursor(cursor)?
+                .parse::<i64>()
+                .map_err(|_| InvalidCursor(cursor.clone()))?,
+        ),
+        None => None,
+    };
+
+    // Fetch an extra delivery to find out if there is another page
+    let mut deliveries = state
+        .tenant_store
+        .list_webhook_deliveries(&id, before, limit + 1)
+        .await?;
+
+    let mut next_cursor = None;
+    if deliveries.len() as i64 > limit {
+        deliveries.truncate(limit as usize);
+        next_cursor = deliveries
+            .last()
+            .map(|delivery| encode_cursor(&delivery.id.to_string()));
+    }
+
+    Ok(WebhookDeliveriesResponse {
+        deliveries,
+        next_cursor,
+    }
+    .into())
+}
@@ -1,62 +1,109 @@
 use {
+    crate::{
+        error::ErrorCode,
+        log::prelude::*,
+        middleware::{request_id::RequestId, validate_tenant_auth::Actor},
+        state::AppState,
+        stores::{
+            tenant::{TenantAuditAction, TenantAuditParams},
+            usage::UsageCounts,
+        },
+    },
     axum::{response::IntoResponse, Json},
+    chrono::Utc,
     hyper::StatusCode,
     serde_json::{json, Value},
 };
 
 // Push
 pub mod delete_client;
+pub mod delete_device;
+pub mod get_client;
+pub mod get_notification;
 pub mod health;
+pub mod list_clients;
 pub mod metrics;
+pub mod openapi;
+pub mod pagination;
 pub mod push_message;
 pub mod register_client;
 pub mod single_tenant_wrappers;
 // Tenant Management
 pub mod create_tenant;
 pub mod delete_tenant;
+pub mod get_audit_log;
 pub mod get_tenant;
+pub mod get_usage;
+pub mod list_tenants;
+pub mod list_webhook_deliveries;
+pub mod remove_apns;
+pub mod remove_fcm;
+pub mod remove_webhook;
+pub mod rename_tenant;
+pub mod restore_tenant;
 pub mod update_apns;
 pub mod update_fcm;
+pub mod update_quota;
+pub mod update_rate_limit;
+pub mod update_webhook;
 
 pub const DECENTRALIZED_IDENTIFIER_PREFIX: &str = "did:key:";
 
-#[derive(serde::Serialize)]
+/// The client ID from `/clients/:id` and `/:tenant_id/clients/:id` routes, the
+/// tenant is found by `ResolvedTenant`
+#[derive(serde::Deserialize)]
+pub struct ClientPath {
+    pub id: String,
+}
+
+#[derive(serde::Serialize, Debug, schemars::JsonSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum ErrorLocation {
     Body,
-    // Note (Harry): Spec supports this but it currently isn't used
-    // Query,
+    Query,
     Header,
     Path,
 }
 
-#[derive(serde::Serialize)]
+#[derive(serde::Serialize, schemars::JsonSchema)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum ResponseStatus {
     Success,
     Failure,
 }
 
-#[derive(serde::Serialize)]
+#[derive(serde::Serialize, Debug, schemars::JsonSchema)]
 pub struct ErrorField {
     pub field: String,
     pub description: String,
     pub location: ErrorLocation,
 }
 
-#[derive(serde::Serialize)]
+#[derive(serde::Serialize, schemars::JsonSchema)]
 pub struct ResponseError {
+    /// Superseded by `code`, kept with its old values so clients that match on
+    /// it keep working
     pub name: String,
+    pub code: ErrorCode,
     pub message: String,
 }
 
-#[derive(serde::Serialize)]
-pub struct Response {
+/// The keys of the `Response` envelope, a payload is flattened alongside them
+/// so mustn't have fields with the same names
+pub const ENVELOPE_KEYS: [&str; 3] = ["status", "errors", "fields"];
+
+/// The envelope every endpoint responds with, an endpoint's payload is
+/// flattened alongside `status` when it has one
+#[derive(serde::Serialize, schemars::JsonSchema)]
+pub struct Response<T = ()> {
     pub status: ResponseStatus,
     #[serde(skip_serializing)]
+    #[schemars(skip)]
     pub status_code: StatusCode,
     pub errors: Option<Vec<ResponseError>>,
     pub fields: Option<Vec<ErrorField>>,
+    #[serde(flatten)]
+    pub data: T,
 }
 
 impl Response {
@@ -66,6 +113,7 @@ impl Response {
             status_code: status,
             errors: None,
             fields: None,
+            data: (),
         }
     }
 
@@ -79,11 +127,24 @@ impl Response {
             status_code: status,
             errors: Some(errors),
             fields: Some(fields),
+            data: (),
         }
     }
 }
 
-impl IntoResponse for Response {
+impl<T> Response<T> {
+    pub fn new_success_with_data(status: StatusCode, data: T) -> Self {
+        Response {
+            status: ResponseStatus::Success,
+            status_code: status,
+            errors: None,
+            fields: None,
+            data,
+        }
+    }
+}
+
+impl<T: serde::Serialize> IntoResponse for Response<T> {
     fn into_response(self) -> axum::response::Response {
         let status = self.status_code;
         let json: Json<Value> = self.into();
@@ -92,14 +153,59 @@ impl IntoResponse for Response {
     }
 }
 
-impl From<Response> for Json<Value> {
-    fn from(value: Response) -> Self {
+impl<T: serde::Serialize> From<Response<T>> for Json<Value> {
+    fn from(value: Response<T>) -> Self {
         Json(json!(value))
     }
 }
 
+/// Successful responses with a payload are `200 OK`
+impl<T> From<T> for Response<T> {
+    fn from(data: T) -> Self {
+        Response::new_success_with_data(StatusCode::OK, data)
+    }
+}
+
 impl Default for Response {
     fn default() -> Self {
         Response::new_success(StatusCode::OK)
     }
 }
+
+/// Records usage for billing, failures are only logged as they shouldn't fail
+/// the request being accounted for
+pub async fn record_usage(state: &AppState, tenant_id: &str, counts: UsageCounts) {
+    let today = Utc::now().date_naive();
+    if let Err(e) = state
+        .usage_store
+        .record_usage(tenant_id, today, counts)
+        .await
+    {
+        warn!("failed to record usage for tenant ({}): {}", tenant_id, e);
+    }
+}
+
+/// The change has already been made when this is called so a failure to audit
+/// it is only logged
+pub async fn record_audit(
+    state: &AppState,
+    tenant_id: &str,
+    actor: &Actor,
+    action: TenantAuditAction,
+    changes: Value,
+    request_id: &RequestId,
+) {
+    let params = TenantAuditParams {
+        tenant_id: tenant_id.to_string(),
+        actor: actor.to_string(),
+        action,
+        changes,
+        request_id: request_id.0.clone(),
+    };
+    if let Err(e) = state.tenant_store.record_audit_entry(params).await {
+        error!(
+            "failed to record audit entry {:?} for tenant ({}): {}",
+            action, tenant_id, e
+        );
+    }
+}
@@ -0,0 +1,5 @@
+use {crate::openapi, axum::Json, serde_json::Value};
+
+pub async fn handler() -> Json<Value> {
+    Json(openapi::spec())
+}
@@ -0,0 +1,25 @@
+use {
+    crate::error::{Error::InvalidCursor, Result},
+    base64::Engine as _,
+};
+
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 200;
+
+/// The number of items to return for a requested `limit`
+pub fn page_size(limit: Option<i64>) -> i64 {
+    limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE)
+}
+
+/// Cursors are opaque to callers, so can be changed without breaking them
+pub fn encode_cursor(id: &str) -> String {
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(id)
+}
+
+pub fn decode_cursor(cursor: &str) -> Result<String> {
+    base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(cursor)
+        .ok()
+        .and_then(|decoded| String::from_utf8(decoded).ok())
+        .ok_or_else(|| InvalidCursor(cursor.to_string()))
+}
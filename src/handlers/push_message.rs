@@ -2,26 +2,36 @@ use {
     crate::{
         blob::ENCRYPTED_FLAG,
         error::{
-            Error::{ClientNotFound, Store},
+            Error::{self, ClientNotFound, NotificationInProgress, QuotaExceeded, Store},
             Result,
         },
-        handlers::{Response, DECENTRALIZED_IDENTIFIER_PREFIX},
+        handlers::{record_usage, ClientPath, Response, DECENTRALIZED_IDENTIFIER_PREFIX},
         increment_counter,
         log::prelude::*,
-        middleware::validate_signature::RequireValidSignature,
-        providers::{Provider, PushProvider},
+        middleware::{resolve_tenant::ResolvedTenant, validate_signature::RequireValidSignature},
+        providers::{Provider, ProviderKind, PushProvider},
+        rate_limit::{self, RateLimitKind},
         state::AppState,
-        stores::StoreError,
+        stores::{
+            notification::NotificationStatus,
+            tenant::WebhookEventType,
+            usage::UsageCounts,
+            StoreError,
+        },
+        webhooks,
     },
     axum::{
         extract::{Json, Path, State as StateExtractor},
         http::StatusCode,
     },
+    chrono::{Datelike, Utc},
+    schemars::JsonSchema,
     serde::{Deserialize, Serialize},
+    serde_json::{json, Value},
     std::sync::Arc,
 };
 
-#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, JsonSchema)]
 pub struct MessagePayload {
     pub topic: Option<String>,
     pub flags: u32,
@@ -34,15 +44,20 @@ impl MessagePayload {
     }
 }
 
-#[derive(Serialize, Deserialize)]
+/// The status of a push that was sent to the client's devices, a notification
+/// that was already delivered is `200 OK` instead
+pub const PUSH_ACCEPTED_STATUS: StatusCode = StatusCode::ACCEPTED;
+
+#[derive(Serialize, Deserialize, JsonSchema)]
 pub struct PushMessageBody {
     pub id: String,
     pub payload: MessagePayload,
 }
 
 pub async fn handler(
-    Path((tenant_id, id)): Path<(String, String)>,
+    Path(ClientPath { id }): Path<ClientPath>,
     StateExtractor(state): StateExtractor<Arc<AppState>>,
+    ResolvedTenant(tenant_id): ResolvedTenant,
     RequireValidSignature(Json(body)): RequireValidSignature<Json<PushMessageBody>>,
 ) -> Result<Response> {
     increment_counter!(state.metrics, received_notifications);
@@ -51,74 +66,276 @@ pub async fn handler(
         .trim_start_matches(DECENTRALIZED_IDENTIFIER_PREFIX)
         .to_string();
 
+    let tenant = state.tenant_store.get_tenant(&tenant_id).await?;
+    // The client's limit is checked first so a push it rejects doesn't use up
+    // the tenant's limit
+    rate_limit::check(
+        &state,
+        RateLimitKind::ClientPush,
+        &format!("{tenant_id}:{id}"),
+    )
+    .await?;
+    rate_limit::check_tenant_push(&state, &tenant).await?;
+
+    record_usage(&state, &tenant_id, UsageCounts {
+        received_notifications: 1,
+        ..Default::default()
+    })
+    .await;
+
     let client = match state.client_store.get_client(&tenant_id, &id).await {
+        Ok(c) if c.devices.is_empty() => Err(ClientNotFound),
         Ok(c) => Ok(c),
         Err(StoreError::NotFound(_, _)) => Err(ClientNotFound),
         Err(e) => Err(Store(e)),
     }?;
-    info!("fetched client ({}) for tenant ({})", &id, &tenant_id);
-
-    if let Ok(_notification) = state
-        .notification_store
-        .get_notification(&body.id, &tenant_id)
-        .await
-    {
-        info!(
-            "notification ({}) already received for client ({})",
-            body.id, id
-        );
-        return Ok(Response::new_success(StatusCode::OK));
-    }
+    info!(
+        "fetched client ({}) with {} device(s) for tenant ({})",
+        &id,
+        client.devices.len(),
+        &tenant_id
+    );
 
+    // Every received payload is recorded, including retries of notifications
+    // that have already been delivered
     let notification = state
         .notification_store
         .create_or_update_notification(&body.id, &tenant_id, &id, &body.payload)
         .await?;
     info!(
-        "stored notification ({}) for tenant ({})",
-        &notification.id, &tenant_id
+        "stored notification ({}) for tenant ({}), received {} time(s)",
+        &notification.id,
+        &tenant_id,
+        notification.previous_payloads.len()
     );
 
-    // TODO make better by only ignoring if previously executed successfully
-    // If notification received more than once then discard
-    if notification.previous_payloads.len() > 1 {
-        info!(
-            "notification ({}) already received for client ({})",
+    // Only skip notifications that were previously delivered, a retry after a
+    // failed attempt should try again
+    match notification.status {
+        NotificationStatus::Delivered => {
+            info!(
+                "notification ({}) already delivered for client ({})",
+                body.id, id
+            );
+            return Ok(Response::new_success(StatusCode::OK));
+        }
+        NotificationStatus::Failed => info!(
+            "retrying notification ({}) for client ({}) after a failed delivery",
             body.id, id
+        ),
+        NotificationStatus::Pending => {}
+    }
+
+    if let Some(quota) = tenant.monthly_notification_quota {
+        let today = Utc::now().date_naive();
+        let month = today.with_day(1).unwrap_or(today);
+        let usage = state
+            .usage_store
+            .get_usage_totals(&tenant_id, month, today)
+            .await?;
+        if usage.delivered_notifications >= quota {
+            // Every push is rejected until the next month, but the tenant is
+            // only told about it once
+            if state
+                .tenant_store
+                .claim_quota_exceeded_event(&tenant_id, month)
+                .await?
+            {
+                webhooks::dispatch(
+                    &state,
+                    &tenant,
+                    WebhookEventType::QuotaExceeded,
+                    json!({ "monthly_notification_quota": quota }),
+                )
+                .await;
+            }
+
+            // A retry next month should deliver it
+            if let Err(e) = state
+                .notification_store
+                .update_notification_status(
+                    &tenant_id,
+                    &notification.id,
+                    NotificationStatus::Failed,
+                )
+                .await
+            {
+                warn!(
+                    "failed to record notification ({}) as rejected by the quota: {}",
+                    &notification.id, e
+                );
+            }
+            return Err(QuotaExceeded(quota));
+        }
+    }
+
+    // Concurrent requests for the same notification would otherwise all see it
+    // as undelivered and deliver it
+    if !state
+        .notification_store
+        .claim_notification(&tenant_id, &notification.id)
+        .await?
+    {
+        return match state
+            .notification_store
+            .get_notification(&tenant_id, &notification.id)
+            .await?
+            .status
+        {
+            NotificationStatus::Delivered => Ok(Response::new_success(StatusCode::OK)),
+            _ => Err(NotificationInProgress(notification.id)),
+        };
+    }
+
+    // Fan out to every device registered for the client, a failure for one
+    // device shouldn't prevent delivery to the others
+    let mut delivered = 0;
+    let mut usage = UsageCounts::default();
+    let mut last_error = None;
+    // Tenants are only told about each kind of failure once per notification
+    let mut failure_events = vec![];
+    for device in client.devices {
+        let mut provider = match tenant.provider(&device.push_type) {
+            Ok(provider) => provider,
+            Err(e) => {
+                add_failure_event(&mut failure_events, &e, &device.push_type, &device.id, &id);
+                warn!(
+                    "failed to fetch provider ({}) for device ({}) of client ({}) during \
+                     notification ({}): {}",
+                    device.push_type.as_str(),
+                    &device.id,
+                    &id,
+                    &notification.id,
+                    e
+                );
+                last_error = Some(e);
+                continue;
+            }
+        };
+        info!(
+            "fetched provider ({}) for tenant ({}) during notification ({})",
+            device.push_type.as_str(),
+            &tenant_id,
+            &notification.id
+        );
+
+        if let Err(e) = provider
+            .send_notification(device.token, body.payload.clone())
+            .await
+        {
+            add_failure_event(&mut failure_events, &e, &device.push_type, &device.id, &id);
+            warn!(
+                "failed to send notification ({}) to device ({}) of client ({}) with sdk ({} {}): \
+                 {}",
+                &notification.id,
+                &device.id,
+                &id,
+                device.metadata.sdk_name.as_deref().unwrap_or("unknown"),
+                device.metadata.sdk_version.as_deref().unwrap_or("unknown"),
+                e
+            );
+            last_error = Some(e);
+            continue;
+        }
+        info!(
+            "sent notification to provider ({}) for tenant ({}) during notification ({}) to \
+             device ({})",
+            device.push_type.as_str(),
+            &tenant_id,
+            &notification.id,
+            &device.id
         );
-        return Ok(Response::new_success(StatusCode::OK));
+
+        delivered += 1;
+
+        // Provider specific metrics
+        match provider {
+            Provider::Fcm(_) => {
+                increment_counter!(state.metrics, sent_fcm_notifications);
+                usage.sent_fcm_notifications += 1;
+            }
+            Provider::Apns(_) => {
+                increment_counter!(state.metrics, sent_apns_notifications);
+                usage.sent_apns_notifications += 1;
+            }
+            Provider::Noop(_) => {}
+        }
     }
 
-    let tenant = state.tenant_store.get_tenant(&tenant_id).await?;
-    info!(
-        "fetched tenant ({}) during notification ({})",
-        &tenant_id, &notification.id
-    );
+    for (event_type, data) in failure_events {
+        webhooks::dispatch(&state, &tenant, event_type, data).await;
+    }
 
-    let mut provider = tenant.provider(&client.push_type)?;
-    info!(
-        "fetched provider ({}) for tenant ({}) during notification ({})",
-        client.push_type.as_str(),
-        &tenant_id,
-        &notification.id
-    );
+    match delivered {
+        0 => usage.failed_notifications = 1,
+        _ => usage.delivered_notifications = 1,
+    }
+    record_usage(&state, &tenant_id, usage).await;
 
-    provider
-        .send_notification(client.token, body.payload)
-        .await?;
-    info!(
-        "sent notification to provider ({}) for tenant ({}) during notification ({})",
-        client.push_type.as_str(),
-        &tenant_id,
-        &notification.id
-    );
+    let status = match delivered {
+        0 => NotificationStatus::Failed,
+        _ => NotificationStatus::Delivered,
+    };
+    if let Err(e) = state
+        .notification_store
+        .update_notification_status(&tenant_id, &notification.id, status)
+        .await
+    {
+        warn!(
+            "failed to record status {:?} for notification ({}): {}",
+            status, &notification.id, e
+        );
+    }
+
+    if delivered == 0 {
+        if let Some(e) = last_error {
+            return Err(e);
+        }
+    }
 
-    // Provider specific metrics
-    match provider {
-        Provider::Fcm(_) => increment_counter!(state.metrics, sent_fcm_notifications),
-        Provider::Apns(_) => increment_counter!(state.metrics, sent_apns_notifications),
-        Provider::Noop(_) => {}
+    // The notification has already been delivered so this shouldn't fail the
+    // request
+    if let Err(e) = state
+        .client_store
+        .update_last_push_success(&tenant_id, &id)
+        .await
+    {
+        warn!(
+            "failed to record successful push for client ({}) during notification ({}): {}",
+            &id, &notification.id, e
+        );
     }
 
-    Ok(Response::new_success(StatusCode::ACCEPTED))
+    Ok(Response::new_success(PUSH_ACCEPTED_STATUS))
+}
+
+/// Queues a webhook event for a failed device, provider auth failures are only
+/// sent once per provider
+fn add_failure_event(
+    events: &mut Vec<(WebhookEventType, Value)>,
+    error: &Error,
+    provider: &ProviderKind,
+    device_id: &str,
+    client_id: &str,
+) {
+    let event_type = match webhooks::event_type_for(error) {
+        Some(event_type) => event_type,
+        None => return,
+    };
+    let data = match event_type {
+        WebhookEventType::ClientTokenInvalid => json!({
+            "provider": provider.as_str(),
+            "client_id": client_id,
+            "device_id": device_id,
+            "error": error.to_string(),
+        }),
+        _ => json!({
+            "provider": provider.as_str(),
+            "error": error.to_string(),
+        }),
+    };
+
+    if !events.contains(&(event_type, data.clone())) {
+        events.push((event_type, data));
+    }
 }
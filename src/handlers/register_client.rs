@@ -4,30 +4,63 @@ use {
             Error::{EmptyField, ProviderNotAvailable},
             Result,
         },
-        handlers::{Response, DECENTRALIZED_IDENTIFIER_PREFIX},
+        handlers::{record_usage, Response, DECENTRALIZED_IDENTIFIER_PREFIX},
         increment_counter,
         log::prelude::*,
+        middleware::{resolve_tenant::ResolvedTenant, validate_client_jwt::RequireClientAuth},
+        rate_limit::{self, RateLimitKind},
         state::AppState,
-        stores::client::Client,
+        stores::{
+            client::{Device, DeviceMetadata, DEFAULT_DEVICE_ID},
+            usage::UsageCounts,
+        },
+    },
+    axum::{
+        extract::{ConnectInfo, Json, State as StateExtractor},
+        http::HeaderMap,
     },
-    axum::extract::{Json, Path, State as StateExtractor},
+    opentelemetry::KeyValue,
+    schemars::JsonSchema,
     serde::{Deserialize, Serialize},
-    std::sync::Arc,
+    std::{net::SocketAddr, sync::Arc},
+    tracing::{instrument, Span},
 };
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, JsonSchema)]
 pub struct RegisterBody {
     pub client_id: String,
     #[serde(rename = "type")]
     pub push_type: String,
     pub token: String,
+    /// Identifies one of the client's devices, a client can register many
+    /// devices which will all receive its notifications
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device_id: Option<String>,
+    /// Details about the device and SDK, used for debugging and analytics
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<DeviceMetadata>,
 }
 
+#[instrument(
+    name = "register_client",
+    skip_all,
+    fields(tenant_id, client_id, device_id, push_type, sdk_name, sdk_version, os)
+)]
 pub async fn handler(
-    Path(tenant_id): Path<String>,
     StateExtractor(state): StateExtractor<Arc<AppState>>,
+    ResolvedTenant(tenant_id): ResolvedTenant,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    client_auth: RequireClientAuth,
     Json(body): Json<RegisterBody>,
 ) -> Result<Response> {
+    rate_limit::check(
+        &state,
+        RateLimitKind::IpRegistration,
+        &rate_limit::client_ip(&headers, addr.ip(), state.config.trusted_proxy_count).to_string(),
+    )
+    .await?;
+
     let push_type = body.push_type.as_str().try_into()?;
     let tenant = state.tenant_store.get_tenant(&tenant_id).await?;
     let supported_providers = tenant.providers();
@@ -42,21 +75,69 @@ pub async fn handler(
     let client_id = body
         .client_id
         .trim_start_matches(DECENTRALIZED_IDENTIFIER_PREFIX);
+    client_auth.check(client_id)?;
+
+    let device_id = body
+        .device_id
+        .unwrap_or_else(|| DEFAULT_DEVICE_ID.to_string());
+    if device_id.is_empty() {
+        return Err(EmptyField("device_id".to_string()));
+    }
 
-    state
+    let metadata = body.metadata.unwrap_or_default();
+    metadata.validate()?;
+
+    let span = Span::current();
+    span.record("tenant_id", tenant_id.as_str());
+    span.record("client_id", client_id);
+    span.record("device_id", device_id.as_str());
+    span.record("push_type", body.push_type.as_str());
+    span.record("sdk_name", metadata.sdk_name.as_deref());
+    span.record("sdk_version", metadata.sdk_version.as_deref());
+    span.record("os", metadata.os.as_deref());
+
+    let sdk_label = metadata.sdk_label();
+    let os_label = metadata.os_label();
+    let sdk_name = metadata
+        .sdk_name
+        .clone()
+        .unwrap_or_else(|| "unknown".to_string());
+    let sdk_version = metadata
+        .sdk_version
+        .clone()
+        .unwrap_or_else(|| "unknown".to_string());
+    let os = metadata.os.clone().unwrap_or_else(|| "unknown".to_string());
+
+    let created = state
         .client_store
-        .create_client(&tenant_id, client_id, Client {
+        .create_client(&tenant_id, client_id, Device {
+            id: device_id.clone(),
             push_type,
             token: body.token,
+            metadata,
         })
         .await?;
 
     info!(
-        "client registered for tenant ({}) using {}",
-        tenant_id, body.push_type
+        "client registered for tenant ({}) using {} on device ({}) with sdk ({} {}) on os ({})",
+        tenant_id, body.push_type, device_id, sdk_name, sdk_version, os
     );
 
-    increment_counter!(state.metrics, registered_clients);
+    // Re-registering a client, or adding another of its devices, isn't a new
+    // client
+    if created {
+        increment_counter!(state.metrics, registered_clients);
+    }
+    record_usage(&state, &tenant_id, UsageCounts {
+        registrations: 1,
+        ..Default::default()
+    })
+    .await;
+    increment_counter!(state.metrics, device_registrations, &[
+        KeyValue::new("push_type", push_type.as_str()),
+        KeyValue::new("sdk_name", sdk_label),
+        KeyValue::new("os", os_label),
+    ]);
 
     Ok(Response::default())
 }
@@ -0,0 +1,51 @@
+use {
+    crate::{
+        error::Error,
+        handlers::{record_audit, Response},
+        increment_counter,
+        log::prelude::*,
+        middleware::{request_id::RequestId, validate_tenant_auth::RequireTenantAuth},
+        state::AppState,
+        stores::tenant::TenantAuditAction,
+    },
+    axum::extract::{Path, State},
+    serde_json::json,
+    std::sync::Arc,
+};
+
+/// Removes the tenant's APNs configuration, pushes to clients registered for
+/// APNs will then fail as the provider is no longer available
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    tenant_auth: RequireTenantAuth,
+    request_id: RequestId,
+) -> Result<Response, Error> {
+    // -- check if tenant is real
+    let existing_tenant = state.tenant_store.get_tenant(&id).await?;
+    let actor = tenant_auth.check(&existing_tenant)?;
+
+    state.tenant_store.remove_tenant_apns(&id).await?;
+    record_audit(
+        &state,
+        &id,
+        &actor,
+        TenantAuditAction::RemoveApns,
+        json!({
+            "apns_type": null,
+            "apns_topic": null,
+            "apns_certificate": null,
+            "apns_certificate_password": null,
+            "apns_pkcs8_pem": null,
+            "apns_key_id": null,
+            "apns_team_id": null,
+        }),
+        &request_id,
+    )
+    .await;
+    info!("removed APNs configuration for tenant ({})", id);
+
+    increment_counter!(state.metrics, tenant_apns_updates);
+
+    Ok(Response::default())
+}
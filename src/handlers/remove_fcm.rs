@@ -0,0 +1,43 @@
+use {
+    crate::{
+        error::Error,
+        handlers::{record_audit, Response},
+        increment_counter,
+        log::prelude::*,
+        middleware::{request_id::RequestId, validate_tenant_auth::RequireTenantAuth},
+        state::AppState,
+        stores::tenant::TenantAuditAction,
+    },
+    axum::extract::{Path, State},
+    serde_json::json,
+    std::sync::Arc,
+};
+
+/// Removes the tenant's FCM configuration, pushes to clients registered for
+/// FCM will then fail as the provider is no longer available
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    tenant_auth: RequireTenantAuth,
+    request_id: RequestId,
+) -> Result<Response, Error> {
+    // -- check if tenant is real
+    let existing_tenant = state.tenant_store.get_tenant(&id).await?;
+    let actor = tenant_auth.check(&existing_tenant)?;
+
+    state.tenant_store.remove_tenant_fcm(&id).await?;
+    record_audit(
+        &state,
+        &id,
+        &actor,
+        TenantAuditAction::RemoveFcm,
+        json!({ "fcm_api_key": null }),
+        &request_id,
+    )
+    .await;
+    info!("removed FCM configuration for tenant ({})", id);
+
+    increment_counter!(state.metrics, tenant_fcm_updates);
+
+    Ok(Response::default())
+}
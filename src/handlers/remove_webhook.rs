@@ -0,0 +1,39 @@
+use {
+    crate::{
+        error::Error,
+        handlers::{record_audit, Response},
+        log::prelude::*,
+        middleware::{request_id::RequestId, validate_tenant_auth::RequireTenantAuth},
+        state::AppState,
+        stores::tenant::TenantAuditAction,
+    },
+    axum::extract::{Path, State},
+    serde_json::json,
+    std::sync::Arc,
+};
+
+/// Stops sending events to the tenant, previous deliveries are kept
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    tenant_auth: RequireTenantAuth,
+    request_id: RequestId,
+) -> Result<Response, Error> {
+    let tenant = state.tenant_store.get_tenant(&id).await?;
+    let actor = tenant_auth.check(&tenant)?;
+
+    state.tenant_store.remove_tenant_webhook(&id).await?;
+    info!("removed webhook for tenant ({})", id);
+
+    record_audit(
+        &state,
+        &id,
+        &actor,
+        TenantAuditAction::RemoveWebhook,
+        json!({ "webhook_url": null, "webhook_secret": null }),
+        &request_id,
+    )
+    .await;
+
+    Ok(Response::default())
+}
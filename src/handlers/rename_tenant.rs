@@ -0,0 +1,100 @@
+use {
+    crate::{
+        error::Error,
+        handlers::{record_audit, Response},
+        log::prelude::*,
+        middleware::{request_id::RequestId, validate_tenant_auth::RequireTenantAuth},
+        state::AppState,
+        stores::tenant::{TenantAuditAction, TenantUpdateParams},
+    },
+    axum::{
+        extract::{Path, State},
+        Json,
+    },
+    schemars::JsonSchema,
+    serde::{Deserialize, Serialize},
+    serde_json::json,
+    std::sync::Arc,
+};
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct TenantRenameBody {
+    /// The tenant's new ID
+    pub id: String,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct TenantRenameResponse {
+    /// The tenant url for the new ID
+    pub url: String,
+}
+
+/// Changes a tenant's ID and moves its clients, notifications and usage over
+/// to it. Tenants and clients live in separate databases so this isn't atomic,
+/// the tenant keeps its previous ID until the clients are moved and if that
+/// fails the move is retried by the `tenant_rename` job or the next rename
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    tenant_auth: RequireTenantAuth,
+    request_id: RequestId,
+    Json(body): Json<TenantRenameBody>,
+) -> Result<Response<TenantRenameResponse>, Error> {
+    let tenant = state.tenant_store.get_tenant(&id).await?;
+    let actor = tenant_auth.check(&tenant)?;
+
+    let params = TenantUpdateParams { id: body.id };
+    params.validate()?;
+    let new_id = params.id.clone();
+
+    if let Some(renamed_from) = &tenant.renamed_from {
+        move_clients(&state, renamed_from, &id).await?;
+    }
+
+    // Renaming a tenant to its own ID only completes a pending rename
+    if new_id != id {
+        state.tenant_store.update_tenant(&id, params).await?;
+        move_clients(&state, &id, &new_id).await?;
+
+        info!("renamed tenant ({}) to ({})", id, new_id);
+
+        record_audit(
+            &state,
+            &new_id,
+            &actor,
+            TenantAuditAction::RenameTenant,
+            json!({ "from": id, "to": new_id }),
+            &request_id,
+        )
+        .await;
+    }
+
+    Ok(TenantRenameResponse {
+        url: format!("{}/{}", state.config.public_url, new_id),
+    }
+    .into())
+}
+
+/// Moves the clients of a renamed tenant from its previous ID and completes the
+/// rename, it's safe to retry as clients already moved are left alone
+pub async fn move_clients(state: &AppState, from: &str, to: &str) -> Result<(), Error> {
+    let res = async {
+        state.client_store.rename_tenant_clients(from, to).await?;
+        state.tenant_store.complete_tenant_rename(to).await?;
+        Ok::<_, Error>(())
+    }
+    .await;
+
+    if let Err(e) = res {
+        error!(
+            "failed to move clients of renamed tenant ({}) to ({}): {}",
+            from, to, e
+        );
+        return Err(Error::TenantRenameIncomplete(
+            from.to_string(),
+            to.to_string(),
+        ));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,29 @@
+use {
+    crate::{
+        error::Error,
+        handlers::Response,
+        increment_counter,
+        log::prelude::*,
+        middleware::validate_tenant_auth::RequireTenantAuth,
+        state::AppState,
+    },
+    axum::extract::{Path, State},
+    std::sync::Arc,
+};
+
+/// Restores a tenant deleted within the grace period, this requires the admin
+/// API key
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    tenant_auth: RequireTenantAuth,
+) -> Result<Response, Error> {
+    tenant_auth.check_admin()?;
+
+    state.tenant_store.restore_tenant(&id).await?;
+    info!("restored tenant ({})", id);
+
+    increment_counter!(state.metrics, registered_tenants);
+
+    Ok(Response::default())
+}
@@ -1,61 +1,73 @@
 use {
     crate::{
         error::{Error::MissingTenantId, Result},
-        handlers::{push_message::PushMessageBody, register_client::RegisterBody, Response},
-        middleware::validate_signature::RequireValidSignature,
+        handlers::{
+            get_client::ClientResponse,
+            get_notification::NotificationHistoryResponse,
+            list_clients::{ListClientsQuery, ListClientsResponse},
+            Response,
+        },
+        middleware::{
+            validate_client_jwt::RequireClientAuth,
+            validate_tenant_auth::RequireTenantAuth,
+        },
         state::{AppState, State},
     },
-    axum::{
-        extract::{Path, State as StateExtractor},
-        Json,
-    },
+    axum::extract::{Path, Query, State as StateExtractor},
     std::sync::Arc,
 };
 
-pub async fn delete_handler(
-    Path(id): Path<String>,
-    state: StateExtractor<Arc<AppState>>,
-) -> Result<Response> {
-    if state.is_multitenant() {
+/// The default tenant is only served in single-tenant and hybrid modes
+fn default_tenant_id(state: &Arc<AppState>) -> Result<String> {
+    if state.is_multitenant() && !state.is_hybrid() {
         return Err(MissingTenantId);
     }
 
-    crate::handlers::delete_client::handler(
-        Path((state.config.default_tenant_id.clone(), id)),
-        state,
-    )
-    .await
+    Ok(state.config.default_tenant_id.clone())
 }
 
-pub async fn push_handler(
-    Path(id): Path<String>,
+pub async fn delete_device_handler(
+    Path((id, device_id)): Path<(String, String)>,
     state: StateExtractor<Arc<AppState>>,
-    valid_sig: RequireValidSignature<Json<PushMessageBody>>,
+    client_auth: RequireClientAuth,
 ) -> Result<Response> {
-    if state.is_multitenant() {
-        return Err(MissingTenantId);
-    }
+    let default_tenant_id = default_tenant_id(&state)?;
 
-    crate::handlers::push_message::handler(
-        Path((state.config.default_tenant_id.clone(), id)),
+    crate::handlers::delete_device::handler(
+        Path((default_tenant_id, id, device_id)),
         state,
-        valid_sig,
+        client_auth,
     )
     .await
 }
 
-pub async fn register_handler(
+pub async fn get_handler(
+    Path(id): Path<String>,
     state: StateExtractor<Arc<AppState>>,
-    body: Json<RegisterBody>,
-) -> Result<Response> {
-    if state.is_multitenant() {
-        return Err(MissingTenantId);
-    }
+    tenant_auth: RequireTenantAuth,
+) -> Result<Response<ClientResponse>> {
+    let default_tenant_id = default_tenant_id(&state)?;
 
-    crate::handlers::register_client::handler(
-        Path(state.config.default_tenant_id.clone()),
-        state,
-        body,
-    )
-    .await
+    crate::handlers::get_client::handler(Path((default_tenant_id, id)), state, tenant_auth).await
+}
+
+pub async fn get_notification_handler(
+    Path(id): Path<String>,
+    state: StateExtractor<Arc<AppState>>,
+    tenant_auth: RequireTenantAuth,
+) -> Result<Response<NotificationHistoryResponse>> {
+    let default_tenant_id = default_tenant_id(&state)?;
+
+    crate::handlers::get_notification::handler(Path((default_tenant_id, id)), state, tenant_auth)
+        .await
+}
+
+pub async fn list_handler(
+    state: StateExtractor<Arc<AppState>>,
+    tenant_auth: RequireTenantAuth,
+    query: Query<ListClientsQuery>,
+) -> Result<Response<ListClientsResponse>> {
+    let default_tenant_id = default_tenant_id(&state)?;
+
+    crate::handlers::list_clients::handler(Path(default_tenant_id), state, tenant_auth, query).await
 }
@@ -1,20 +1,26 @@
 use {
     crate::{
-        error::{Error, Error::InvalidMultipartBody},
+        error::{Error, Error::InvalidProviderUpdate},
+        handlers::{record_audit, ErrorField, ErrorLocation, Response},
         increment_counter,
+        middleware::{
+            json_or_multipart::JsonOrMultipart,
+            request_id::RequestId,
+            validate_tenant_auth::RequireTenantAuth,
+        },
         state::AppState,
-        stores::tenant::{TenantApnsUpdateAuth, TenantApnsUpdateParams},
-    },
-    axum::{
-        extract::{Multipart, Path, State},
-        Json,
+        stores::tenant::{TenantApnsUpdateAuth, TenantApnsUpdateParams, TenantAuditAction},
     },
+    axum::extract::{Multipart, Path, State},
     base64::Engine,
-    serde::{Deserialize, Serialize},
+    schemars::JsonSchema,
+    serde::Deserialize,
     std::sync::Arc,
 };
 
-#[derive(Deserialize)]
+/// Certificates and PEMs are base64 encoded when sent as JSON, multipart bodies
+/// send the files directly
+#[derive(Deserialize, Default, JsonSchema)]
 pub struct ApnsUpdateBody {
     pub apns_topic: Option<String>,
 
@@ -32,8 +38,73 @@ pub struct ApnsSqlUpdate {
     pub auth: Option<TenantApnsUpdateAuth>,
 }
 
+fn invalid_field(field: &str, description: &str) -> ErrorField {
+    ErrorField {
+        field: field.to_string(),
+        description: description.to_string(),
+        location: ErrorLocation::Body,
+    }
+}
+
 impl ApnsUpdateBody {
+    pub async fn from_multipart(mut form_body: Multipart) -> Result<Self, Error> {
+        let mut body = ApnsUpdateBody::default();
+        while let Some(field) = form_body.next_field().await? {
+            let name = field.name().unwrap_or("unknown").to_string();
+
+            // Check the lowercase name against list of known names for struct
+            match name.to_lowercase().as_str() {
+                "apns_topic" => {
+                    body.apns_topic = Some(field.text().await?);
+                }
+                "apns_certificate" => {
+                    let data = field.bytes().await?;
+                    let encoded_certificate =
+                        base64::engine::general_purpose::STANDARD.encode(&data);
+                    body.apns_certificate = Some(encoded_certificate);
+                }
+                "apns_certificate_password" => {
+                    body.apns_certificate_password = Some(field.text().await?);
+                }
+                "apns_pkcs8_pem" => {
+                    let data = field.bytes().await?;
+                    let encoded_pem = base64::engine::general_purpose::STANDARD.encode(&data);
+                    body.apns_pkcs8_pem = Some(encoded_pem);
+                }
+                "apns_key_id" => {
+                    body.apns_key_id = Some(field.text().await?);
+                }
+                "apns_team_id" => {
+                    body.apns_team_id = Some(field.text().await?);
+                }
+                _ => {
+                    // Unknown field, ignored
+                }
+            };
+        }
+
+        Ok(body)
+    }
+
     pub fn validate(&self) -> Result<ApnsSqlUpdate, Error> {
+        let mut invalid_encoding = vec![];
+        for (field, value) in [
+            ("apns_certificate", &self.apns_certificate),
+            ("apns_pkcs8_pem", &self.apns_pkcs8_pem),
+        ] {
+            if let Some(value) = value {
+                if base64::engine::general_purpose::STANDARD
+                    .decode(value)
+                    .is_err()
+                {
+                    invalid_encoding.push(invalid_field(field, "Must be base64 encoded"));
+                }
+            }
+        }
+        if !invalid_encoding.is_empty() {
+            return Err(InvalidProviderUpdate(invalid_encoding));
+        }
+
         // Match cases when the input is not valid and return false.
         // Input is valid if certificate and certificate_password is included for
         // updates. topic is required for new tenants
@@ -88,101 +159,123 @@ impl ApnsUpdateBody {
                 }),
             }),
             // All other cases are invalid
-            _ => Err(InvalidMultipartBody),
+            _ => Err(InvalidProviderUpdate(self.invalid_fields())),
         }
     }
-}
 
-#[derive(Serialize)]
-pub struct UpdateTenantApnsResponse {
-    success: bool,
+    /// Describes why the combination of fields provided isn't a valid update
+    fn invalid_fields(&self) -> Vec<ErrorField> {
+        let certificate = [
+            ("apns_certificate", &self.apns_certificate),
+            ("apns_certificate_password", &self.apns_certificate_password),
+        ];
+        let token = [
+            ("apns_pkcs8_pem", &self.apns_pkcs8_pem),
+            ("apns_key_id", &self.apns_key_id),
+            ("apns_team_id", &self.apns_team_id),
+        ];
+        let uses_certificate = certificate.iter().any(|(_, value)| value.is_some());
+        let uses_token = token.iter().any(|(_, value)| value.is_some());
+
+        match (uses_certificate, uses_token) {
+            (true, true) => vec![
+                invalid_field(
+                    "apns_certificate",
+                    "Cannot be provided alongside token authentication",
+                ),
+                invalid_field(
+                    "apns_pkcs8_pem",
+                    "Cannot be provided alongside certificate authentication",
+                ),
+            ],
+            (true, false) => certificate
+                .iter()
+                .filter(|(_, value)| value.is_none())
+                .map(|(field, _)| invalid_field(field, "Required for certificate authentication"))
+                .collect(),
+            (false, true) => token
+                .iter()
+                .filter(|(_, value)| value.is_none())
+                .map(|(field, _)| invalid_field(field, "Required for token authentication"))
+                .collect(),
+            (false, false) => vec![invalid_field(
+                "apns_topic",
+                "A topic or authentication details are required",
+            )],
+        }
+    }
 }
 
 pub async fn handler(
     State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
-    mut form_body: Multipart,
-) -> Result<Json<UpdateTenantApnsResponse>, Error> {
+    tenant_auth: RequireTenantAuth,
+    request_id: RequestId,
+    body: JsonOrMultipart<ApnsUpdateBody>,
+) -> Result<Response, Error> {
     // Ensure tenant real
-    let _existing_tenant = state.tenant_store.get_tenant(&id).await?;
-
-    // ---- retrieve body from form
-    let mut body = ApnsUpdateBody {
-        apns_topic: None,
+    let existing_tenant = state.tenant_store.get_tenant(&id).await?;
+    let actor = tenant_auth.check(&existing_tenant)?;
 
-        apns_certificate: None,
-        apns_certificate_password: None,
-
-        apns_pkcs8_pem: None,
-        apns_key_id: None,
-        apns_team_id: None,
+    // ---- retrieve body from json or form
+    let body = match body {
+        JsonOrMultipart::Json(body) => body,
+        JsonOrMultipart::Multipart(form_body) => ApnsUpdateBody::from_multipart(form_body).await?,
     };
-    while let Some(field) = form_body.next_field().await? {
-        let name = field.name().unwrap_or("unknown").to_string();
-
-        // Check the lowercase name against list of known names for struct
-        match name.to_lowercase().as_str() {
-            "apns_topic" => {
-                body.apns_topic = Some(field.text().await?);
-            }
-            "apns_certificate" => {
-                let data = field.bytes().await?;
-                let encoded_certificate = base64::engine::general_purpose::STANDARD.encode(&data);
-                body.apns_certificate = Some(encoded_certificate);
-            }
-            "apns_certificate_password" => {
-                body.apns_certificate_password = Some(field.text().await?);
-            }
-            "apns_pkcs8_pem" => {
-                let data = field.bytes().await?;
-                let encoded_pem = base64::engine::general_purpose::STANDARD.encode(&data);
-                body.apns_pkcs8_pem = Some(encoded_pem);
-            }
-            "apns_key_id" => {
-                body.apns_key_id = Some(field.text().await?);
-            }
-            "apns_team_id" => {
-                body.apns_team_id = Some(field.text().await?);
-            }
-            _ => {
-                // Unknown field, ignored
-            }
-        };
-    }
 
     let apns_updates = body.validate()?;
 
     if let Some(topic) = apns_updates.topic {
         // Just update topic
         let update_body = TenantApnsUpdateParams { apns_topic: topic };
+        let changes = update_body.audit_changes();
 
         let _new_tenant = state
             .tenant_store
             .update_tenant_apns(&id, update_body)
             .await?;
+        record_audit(
+            &state,
+            &id,
+            &actor,
+            TenantAuditAction::UpdateApns,
+            changes,
+            &request_id,
+        )
+        .await;
 
         if apns_updates.auth.is_none() {
             // Breakout early as there are no auth updates
 
             increment_counter!(state.metrics, tenant_apns_updates);
 
-            return Ok(Json(UpdateTenantApnsResponse { success: true }));
+            return Ok(Response::default());
         }
     }
 
     // ---- handler
     if let Some(auth) = apns_updates.auth {
+        let changes = auth.audit_changes();
         let _new_tenant = state
             .tenant_store
             .update_tenant_apns_auth(&id, auth)
             .await?;
+        record_audit(
+            &state,
+            &id,
+            &actor,
+            TenantAuditAction::UpdateApnsAuth,
+            changes,
+            &request_id,
+        )
+        .await;
 
         increment_counter!(state.metrics, tenant_apns_updates);
 
-        return Ok(Json(UpdateTenantApnsResponse { success: true }));
+        return Ok(Response::default());
     }
 
     // No auth updates or topic updates were carried out therefore the body was
     // invalid
-    Err(InvalidMultipartBody)
+    Err(InvalidProviderUpdate(body.invalid_fields()))
 }
@@ -1,66 +1,91 @@
 use {
     crate::{
-        error::{Error, Error::InvalidMultipartBody},
+        error::{Error, Error::InvalidProviderUpdate},
+        handlers::{record_audit, ErrorField, ErrorLocation, Response},
         increment_counter,
+        middleware::{
+            json_or_multipart::JsonOrMultipart,
+            request_id::RequestId,
+            validate_tenant_auth::RequireTenantAuth,
+        },
         state::AppState,
-        stores::tenant::TenantFcmUpdateParams,
+        stores::tenant::{TenantAuditAction, TenantFcmUpdateParams},
     },
-    axum::{
-        extract::{Multipart, Path, State},
-        Json,
-    },
-    serde::Serialize,
+    axum::extract::{Multipart, Path, State},
+    schemars::JsonSchema,
+    serde::Deserialize,
     std::sync::Arc,
 };
 
+#[derive(Deserialize, Default, JsonSchema)]
 pub struct FcmUpdateBody {
-    api_key: String,
-    /// Used to ensure that at least one value has changed
-    value_changed_: bool,
+    pub api_key: Option<String>,
 }
 
-#[derive(Serialize)]
-pub struct UpdateTenantFcmResponse {
-    success: bool,
+impl FcmUpdateBody {
+    pub async fn from_multipart(mut form_body: Multipart) -> Result<Self, Error> {
+        let mut body = FcmUpdateBody::default();
+        while let Some(field) = form_body.next_field().await? {
+            let name = field.name().unwrap_or("unknown").to_string();
+            let data = field.text().await?;
+
+            if name.to_lowercase().as_str() == "api_key" {
+                body.api_key = Some(data);
+            };
+        }
+
+        Ok(body)
+    }
+
+    pub fn validate(&self) -> Result<TenantFcmUpdateParams, Error> {
+        match &self.api_key {
+            Some(api_key) if !api_key.is_empty() => Ok(TenantFcmUpdateParams {
+                fcm_api_key: api_key.clone(),
+            }),
+            _ => Err(InvalidProviderUpdate(vec![ErrorField {
+                field: "api_key".to_string(),
+                description: "An FCM API key is required".to_string(),
+                location: ErrorLocation::Body,
+            }])),
+        }
+    }
 }
 
 pub async fn handler(
     State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
-    mut form_body: Multipart,
-) -> Result<Json<UpdateTenantFcmResponse>, Error> {
+    tenant_auth: RequireTenantAuth,
+    request_id: RequestId,
+    body: JsonOrMultipart<FcmUpdateBody>,
+) -> Result<Response, Error> {
     // -- check if tenant is real
-    let _existing_tenant = state.tenant_store.get_tenant(&id).await?;
+    let existing_tenant = state.tenant_store.get_tenant(&id).await?;
+    let actor = tenant_auth.check(&existing_tenant)?;
 
-    // ---- retrieve body from form
-    let mut body = FcmUpdateBody {
-        api_key: Default::default(),
-        value_changed_: false,
+    // ---- retrieve body from json or form
+    let body = match body {
+        JsonOrMultipart::Json(body) => body,
+        JsonOrMultipart::Multipart(form_body) => FcmUpdateBody::from_multipart(form_body).await?,
     };
-    while let Some(field) = form_body.next_field().await? {
-        let name = field.name().unwrap_or("unknown").to_string();
-        let data = field.text().await?;
-
-        if name.to_lowercase().as_str() == "api_key" {
-            body.api_key = data;
-            body.value_changed_ = true;
-        };
-    }
-    if !body.value_changed_ {
-        return Err(InvalidMultipartBody);
-    }
+    let update_body = body.validate()?;
 
     // ---- handler
-    let update_body = TenantFcmUpdateParams {
-        fcm_api_key: body.api_key,
-    };
-
+    let changes = update_body.audit_changes();
     let _new_tenant = state
         .tenant_store
         .update_tenant_fcm(&id, update_body)
         .await?;
+    record_audit(
+        &state,
+        &id,
+        &actor,
+        TenantAuditAction::UpdateFcm,
+        changes,
+        &request_id,
+    )
+    .await;
 
     increment_counter!(state.metrics, tenant_fcm_updates);
 
-    Ok(Json(UpdateTenantFcmResponse { success: true }))
+    Ok(Response::default())
 }
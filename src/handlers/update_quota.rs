@@ -0,0 +1,46 @@
+use {
+    crate::{
+        error::Error,
+        handlers::Response,
+        log::prelude::*,
+        middleware::validate_tenant_auth::RequireTenantAuth,
+        state::AppState,
+    },
+    axum::{
+        extract::{Path, State},
+        Json,
+    },
+    schemars::JsonSchema,
+    serde::{Deserialize, Serialize},
+    std::sync::Arc,
+};
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct QuotaUpdateBody {
+    /// Deliveries allowed per calendar month, `null` removes the quota
+    pub monthly_notification_quota: Option<u32>,
+}
+
+/// Quotas are set by operators so this requires the admin API key
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    tenant_auth: RequireTenantAuth,
+    Json(body): Json<QuotaUpdateBody>,
+) -> Result<Response, Error> {
+    tenant_auth.check_admin()?;
+
+    // -- check if tenant is real
+    let _existing_tenant = state.tenant_store.get_tenant(&id).await?;
+
+    state
+        .tenant_store
+        .update_tenant_quota(&id, body.monthly_notification_quota.map(Into::into))
+        .await?;
+    info!(
+        "updated monthly quota for tenant ({}) to {:?}",
+        id, body.monthly_notification_quota
+    );
+
+    Ok(Response::default())
+}
@@ -0,0 +1,49 @@
+use {
+    crate::{
+        error::Error,
+        handlers::Response,
+        log::prelude::*,
+        middleware::validate_tenant_auth::RequireTenantAuth,
+        state::AppState,
+    },
+    axum::{
+        extract::{Path, State},
+        Json,
+    },
+    schemars::JsonSchema,
+    serde::{Deserialize, Serialize},
+    std::{num::NonZeroU32, sync::Arc},
+};
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct RateLimitUpdateBody {
+    /// Pushes allowed per second, `null` falls back to the instance's limit
+    pub push_rate_limit_per_second: Option<NonZeroU32>,
+}
+
+/// Rate limits are set by operators so this requires the admin API key
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    tenant_auth: RequireTenantAuth,
+    Json(body): Json<RateLimitUpdateBody>,
+) -> Result<Response, Error> {
+    tenant_auth.check_admin()?;
+
+    // -- check if tenant is real
+    let _existing_tenant = state.tenant_store.get_tenant(&id).await?;
+
+    let push_rate_limit_per_second = body
+        .push_rate_limit_per_second
+        .map(|limit| i32::try_from(limit.get()).unwrap_or(i32::MAX));
+    state
+        .tenant_store
+        .update_tenant_rate_limit(&id, push_rate_limit_per_second)
+        .await?;
+    info!(
+        "updated push rate limit for tenant ({}) to {:?}",
+        id, body.push_rate_limit_per_second
+    );
+
+    Ok(Response::default())
+}
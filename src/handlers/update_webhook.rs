@@ -0,0 +1,103 @@
+use {
+    crate::{
+        error::{Error, Error::InvalidWebhook},
+        handlers::{record_audit, ErrorField, ErrorLocation, Response},
+        log::prelude::*,
+        middleware::{request_id::RequestId, validate_tenant_auth::RequireTenantAuth},
+        state::AppState,
+        stores::tenant::{TenantAuditAction, TenantWebhookUpdateParams},
+        webhooks,
+    },
+    axum::{
+        extract::{Path, State},
+        Json,
+    },
+    schemars::JsonSchema,
+    serde::{Deserialize, Serialize},
+    std::sync::Arc,
+};
+
+const MIN_SECRET_LENGTH: usize = 16;
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct WebhookUpdateBody {
+    /// Delivery failures are POSTed here
+    pub url: String,
+    /// Used to sign each event so the tenant can verify it came from us
+    pub secret: String,
+}
+
+impl WebhookUpdateBody {
+    pub fn validate(&self) -> Result<TenantWebhookUpdateParams, Error> {
+        let mut fields = vec![];
+
+        match reqwest::Url::parse(&self.url) {
+            Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+            _ => fields.push(ErrorField {
+                field: "url".to_string(),
+                description: "Must be an absolute http or https URL".to_string(),
+                location: ErrorLocation::Body,
+            }),
+        }
+
+        if self.secret.len() < MIN_SECRET_LENGTH {
+            fields.push(ErrorField {
+                field: "secret".to_string(),
+                description: format!("Must be at least {MIN_SECRET_LENGTH} characters"),
+                location: ErrorLocation::Body,
+            });
+        }
+
+        if !fields.is_empty() {
+            return Err(InvalidWebhook(fields));
+        }
+
+        Ok(TenantWebhookUpdateParams {
+            webhook_url: self.url.clone(),
+            webhook_secret: self.secret.clone(),
+        })
+    }
+}
+
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    tenant_auth: RequireTenantAuth,
+    request_id: RequestId,
+    Json(body): Json<WebhookUpdateBody>,
+) -> Result<Response, Error> {
+    let tenant = state.tenant_store.get_tenant(&id).await?;
+    let actor = tenant_auth.check(&tenant)?;
+
+    let update_body = body.validate()?;
+    if !state.config.webhook_allow_private_addresses {
+        webhooks::check_destination(&update_body.webhook_url)
+            .await
+            .map_err(|description| {
+                InvalidWebhook(vec![ErrorField {
+                    field: "url".to_string(),
+                    description,
+                    location: ErrorLocation::Body,
+                }])
+            })?;
+    }
+    let changes = update_body.audit_changes();
+
+    state
+        .tenant_store
+        .update_tenant_webhook(&id, update_body)
+        .await?;
+    info!("updated webhook for tenant ({})", id);
+
+    record_audit(
+        &state,
+        &id,
+        &actor,
+        TenantAuditAction::UpdateWebhook,
+        changes,
+        &request_id,
+    )
+    .await;
+
+    Ok(Response::default())
+}
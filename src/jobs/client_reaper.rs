@@ -0,0 +1,66 @@
+use {
+    crate::{error::Result, log::prelude::*, state::AppState},
+    chrono::Utc,
+    opentelemetry::{Context, KeyValue},
+    std::{sync::Arc, time::Duration},
+};
+
+/// Periodically removes clients that haven't re-registered or received a
+/// notification within `max_idle_days`
+pub async fn run(state: Arc<AppState>, max_idle_days: u32) {
+    let mut interval = tokio::time::interval(Duration::from_secs(
+        state.config.client_reaper_interval_secs,
+    ));
+
+    loop {
+        interval.tick().await;
+
+        if let Err(e) = reap(&state, max_idle_days).await {
+            warn!("failed to reap stale clients: {}", e);
+        }
+    }
+}
+
+/// Removes, or in dry-run mode only counts, clients idle for longer than
+/// `max_idle_days`. They're deleted in batches so a large backlog doesn't hold
+/// locks on the table for long
+pub async fn reap(state: &AppState, max_idle_days: u32) -> Result<u64> {
+    let idle_since = Utc::now() - chrono::Duration::days(max_idle_days.into());
+    let dry_run = state.config.client_reaper_dry_run;
+    let batch_size = state.config.client_reaper_batch_size;
+
+    let reaped = match dry_run {
+        true => state.client_store.count_stale_clients(idle_since).await? as u64,
+        false => {
+            let mut deleted = 0;
+            loop {
+                let batch = state
+                    .client_store
+                    .delete_stale_clients(idle_since, batch_size)
+                    .await?;
+                deleted += batch;
+
+                if batch < batch_size as u64 {
+                    break deleted;
+                }
+            }
+        }
+    };
+
+    info!(
+        "reaped {} clients idle since {} (dry run: {})",
+        reaped, idle_since, dry_run
+    );
+
+    if let Some(metrics) = &state.metrics {
+        let ctx = Context::current();
+        metrics
+            .reaped_clients
+            .add(&ctx, reaped, &[KeyValue::new("dry_run", dry_run)]);
+        if !dry_run {
+            metrics.registered_clients.add(&ctx, -(reaped as i64), &[]);
+        }
+    }
+
+    Ok(reaped)
+}
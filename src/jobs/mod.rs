@@ -0,0 +1,41 @@
+use {
+    crate::state::{AppState, State},
+    std::sync::Arc,
+    tokio::task::JoinHandle,
+};
+
+pub mod client_reaper;
+pub mod notification_cleanup;
+pub mod tenant_purge;
+pub mod tenant_rename;
+pub mod usage_snapshot;
+
+/// Spawns the enabled background jobs, the handles should be aborted when the
+/// server shuts down
+pub fn spawn(state: Arc<AppState>) -> Vec<JoinHandle<()>> {
+    let mut jobs = vec![];
+
+    if let Some(max_idle_days) = state.config.client_reaper_max_idle_days {
+        jobs.push(tokio::spawn(client_reaper::run(
+            state.clone(),
+            max_idle_days,
+        )));
+    }
+
+    if state.config.notification_retention_days > 0 {
+        jobs.push(tokio::spawn(notification_cleanup::run(
+            state.clone(),
+            state.config.notification_retention_days,
+        )));
+    }
+
+    jobs.push(tokio::spawn(usage_snapshot::run(state.clone())));
+
+    // Tenants can only be deleted or renamed in multi-tenant mode
+    if state.is_multitenant() {
+        jobs.push(tokio::spawn(tenant_purge::run(state.clone())));
+        jobs.push(tokio::spawn(tenant_rename::run(state.clone())));
+    }
+
+    jobs
+}
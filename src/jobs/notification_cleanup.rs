@@ -0,0 +1,68 @@
+use {
+    crate::{error::Result, log::prelude::*, state::AppState},
+    chrono::Utc,
+    opentelemetry::Context,
+    std::{
+        sync::{atomic::Ordering, Arc},
+        time::Duration,
+    },
+};
+
+/// Periodically removes notifications last received more than
+/// `retention_days` ago, they are only kept to deduplicate retried messages
+pub async fn run(state: Arc<AppState>, retention_days: u32) {
+    let mut interval = tokio::time::interval(Duration::from_secs(
+        state.config.notification_cleanup_interval_secs,
+    ));
+
+    loop {
+        interval.tick().await;
+
+        if let Err(e) = cleanup(&state, retention_days).await {
+            warn!("failed to clean up notifications: {}", e);
+        }
+    }
+}
+
+/// Deletes expired notifications in batches so a large backlog doesn't hold
+/// locks on the table for long
+pub async fn cleanup(state: &AppState, retention_days: u32) -> Result<u64> {
+    let received_before = Utc::now() - chrono::Duration::days(retention_days.into());
+    let batch_size = state.config.notification_cleanup_batch_size;
+
+    let mut deleted = 0;
+    loop {
+        let batch = state
+            .notification_store
+            .delete_notifications_received_before(received_before, batch_size)
+            .await?;
+        deleted += batch;
+
+        if let Some(metrics) = &state.metrics {
+            metrics
+                .deleted_notifications
+                .add(&Context::current(), batch, &[]);
+        }
+
+        if batch < batch_size as u64 {
+            break;
+        }
+    }
+
+    info!(
+        "deleted {} notifications received before {}",
+        deleted, received_before
+    );
+
+    if let Some(metrics) = &state.metrics {
+        let size = state
+            .notification_store
+            .get_notifications_table_size()
+            .await?;
+        metrics
+            .notifications_table_size
+            .store(size, Ordering::Relaxed);
+    }
+
+    Ok(deleted)
+}
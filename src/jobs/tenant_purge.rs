@@ -0,0 +1,53 @@
+use {
+    crate::{error::Result, log::prelude::*, state::AppState},
+    chrono::Utc,
+    opentelemetry::Context,
+    std::{sync::Arc, time::Duration},
+};
+
+/// Periodically purges tenants deleted longer ago than the grace period
+pub async fn run(state: Arc<AppState>) {
+    let mut interval =
+        tokio::time::interval(Duration::from_secs(state.config.tenant_purge_interval_secs));
+
+    loop {
+        interval.tick().await;
+
+        if let Err(e) = purge(&state).await {
+            warn!("failed to purge deleted tenants: {}", e);
+        }
+    }
+}
+
+/// Removes the clients, notifications and usage of each tenant past the grace
+/// period from the main database and then the tenant itself, returning how
+/// many tenants were purged
+pub async fn purge(state: &AppState) -> Result<usize> {
+    let deleted_before =
+        Utc::now() - chrono::Duration::days(state.config.tenant_deletion_grace_period_days.into());
+    let tenants = state
+        .tenant_store
+        .list_deleted_tenants(deleted_before)
+        .await?;
+
+    for tenant in &tenants {
+        // Clients go first so a failure part way through leaves the tenant to be
+        // retried on the next run
+        let clients = state.client_store.delete_tenant_clients(&tenant.id).await?;
+        state.usage_store.delete_usage(&tenant.id).await?;
+        state.tenant_store.purge_tenant(&tenant.id).await?;
+
+        info!(
+            "purged tenant ({}) and {} client(s), deleted at {:?}",
+            tenant.id, clients, tenant.deleted_at
+        );
+
+        if let Some(metrics) = &state.metrics {
+            metrics
+                .registered_clients
+                .add(&Context::current(), -(clients as i64), &[]);
+        }
+    }
+
+    Ok(tenants.len())
+}
@@ -0,0 +1,49 @@
+use {
+    crate::{
+        error::Result,
+        handlers::rename_tenant::move_clients,
+        log::prelude::*,
+        state::AppState,
+    },
+    std::{sync::Arc, time::Duration},
+};
+
+/// Periodically retries moving the clients of renamed tenants whose rename
+/// couldn't move them
+pub async fn run(state: Arc<AppState>) {
+    let mut interval = tokio::time::interval(Duration::from_secs(
+        state.config.tenant_rename_retry_interval_secs,
+    ));
+
+    loop {
+        interval.tick().await;
+
+        if let Err(e) = retry(&state).await {
+            warn!("failed to retry pending tenant renames: {}", e);
+        }
+    }
+}
+
+/// Moves the clients of each tenant with a pending rename, returning how many
+/// renames were completed
+pub async fn retry(state: &AppState) -> Result<usize> {
+    let tenants = state.tenant_store.list_pending_renames().await?;
+
+    let mut completed = 0;
+    for tenant in &tenants {
+        let Some(renamed_from) = &tenant.renamed_from else {
+            continue;
+        };
+
+        // Failures are logged and left for the next run
+        if move_clients(state, renamed_from, &tenant.id).await.is_ok() {
+            info!(
+                "completed rename of tenant ({}) to ({})",
+                renamed_from, tenant.id
+            );
+            completed += 1;
+        }
+    }
+
+    Ok(completed)
+}
@@ -0,0 +1,35 @@
+use {
+    crate::{error::Result, log::prelude::*, state::AppState},
+    chrono::Utc,
+    std::{sync::Arc, time::Duration},
+};
+
+/// Periodically records each tenant's number of registered clients in its
+/// daily usage, the last snapshot of a day is kept
+pub async fn run(state: Arc<AppState>) {
+    let mut interval = tokio::time::interval(Duration::from_secs(
+        state.config.usage_snapshot_interval_secs,
+    ));
+
+    loop {
+        interval.tick().await;
+
+        if let Err(e) = snapshot(&state).await {
+            warn!("failed to snapshot registered clients: {}", e);
+        }
+    }
+}
+
+/// Records the registered clients of every tenant against the current UTC
+/// date, returning how many tenants were recorded
+pub async fn snapshot(state: &AppState) -> Result<u64> {
+    let today = Utc::now().date_naive();
+    let tenants = state.usage_store.snapshot_registered_clients(today).await?;
+
+    info!(
+        "recorded registered clients of {} tenants for {}",
+        tenants, today
+    );
+
+    Ok(tenants)
+}
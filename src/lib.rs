@@ -1,10 +1,13 @@
 use {
-    crate::{state::TenantStoreArc, stores::tenant::DefaultTenantStore},
-    axum::{
-        http::Method,
-        routing::{delete, get, post},
-        Router,
+    crate::{
+        state::TenantStoreArc,
+        stores::{
+            tenant::DefaultTenantStore,
+            tenant_cache::CachedTenantStore,
+            tenant_hybrid::HybridTenantStore,
+        },
     },
+    axum::{http::HeaderValue, routing::get, Router},
     config::Config,
     opentelemetry::{sdk::Resource, KeyValue},
     sqlx::{
@@ -15,7 +18,7 @@ use {
     tokio::{select, sync::broadcast},
     tower::ServiceBuilder,
     tower_http::{
-        cors::{AllowOrigin, CorsLayer},
+        request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer},
         trace::{DefaultMakeSpan, DefaultOnRequest, DefaultOnResponse, TraceLayer},
     },
     tracing::{info, log::LevelFilter, warn, Level},
@@ -25,14 +28,19 @@ pub mod blob;
 pub mod config;
 pub mod error;
 pub mod handlers;
+pub mod jobs;
 pub mod log;
 pub mod macros;
 pub mod metrics;
 pub mod middleware;
+pub mod openapi;
 pub mod providers;
+pub mod rate_limit;
 pub mod relay;
+pub mod routes;
 pub mod state;
 pub mod stores;
+pub mod webhooks;
 
 pub async fn bootstap(mut shutdown: broadcast::Receiver<()>, config: Config) -> error::Result<()> {
     // Check config is valid and then throw the error if its not
@@ -52,8 +60,9 @@ pub async fn bootstap(mut shutdown: broadcast::Receiver<()>, config: Config) ->
     // to the root dir (the directory containing `Cargo.toml`).
     sqlx::migrate!("./migrations").run(&store).await?;
 
-    let mut tenant_store: TenantStoreArc =
+    let default_tenant_store: TenantStoreArc =
         Arc::new(DefaultTenantStore::new(Arc::new(config.clone()))?);
+    let mut tenant_store = default_tenant_store.clone();
     if let Some(tenant_database_url) = &config.tenant_database_url {
         let tenant_pg_options = PgConnectOptions::from_str(tenant_database_url)?
             .log_statements(LevelFilter::Debug)
@@ -71,7 +80,14 @@ pub async fn bootstap(mut shutdown: broadcast::Receiver<()>, config: Config) ->
             .run(&tenant_database)
             .await?;
 
-        tenant_store = Arc::new(tenant_database);
+        tenant_store = match config.is_hybrid() {
+            true => Arc::new(HybridTenantStore::new(
+                config.default_tenant_id.clone(),
+                default_tenant_store,
+                Arc::new(tenant_database),
+            )),
+            false => Arc::new(tenant_database),
+        };
     }
 
     let mut state = state::new_state(
@@ -79,12 +95,14 @@ pub async fn bootstap(mut shutdown: broadcast::Receiver<()>, config: Config) ->
         Arc::new(store.clone()),
         Arc::new(store.clone()),
         tenant_store,
+        Arc::new(store.clone()),
     )?;
 
     let mut supported_providers_string = "multi-tenant".to_string();
     let is_multitenant = state.config.tenant_database_url.is_some();
+    let is_hybrid = state.config.is_hybrid();
 
-    if !is_multitenant {
+    if !is_multitenant || is_hybrid {
         supported_providers_string = state
             .config
             .single_tenant_supported_providers()
@@ -93,6 +111,10 @@ pub async fn bootstap(mut shutdown: broadcast::Receiver<()>, config: Config) ->
             .collect::<Vec<&str>>()
             .join(", ");
     }
+    if is_hybrid {
+        supported_providers_string =
+            format!("multi-tenant, default tenant: {supported_providers_string}");
+    }
 
     // Fetch public key so it's cached for the first 6hrs
     let public_key = state.relay_client.public_key().await;
@@ -110,6 +132,16 @@ pub async fn bootstap(mut shutdown: broadcast::Receiver<()>, config: Config) ->
         ]))?);
     }
 
+    // Wrapped after the metrics are set so cache hits and misses are recorded
+    if state.config.tenant_cache_ttl_secs > 0 {
+        state.tenant_store = Arc::new(CachedTenantStore::new(
+            state.tenant_store.clone(),
+            Duration::from_secs(state.config.tenant_cache_ttl_secs),
+            state.config.tenant_cache_capacity,
+            state.metrics.clone(),
+        ));
+    }
+
     let port = state.config.port;
     let private_port = state.config.telemetry_prometheus_port.unwrap_or(3001);
     let build_version = state.build_info.crate_info.version.clone();
@@ -124,77 +156,48 @@ pub async fn bootstap(mut shutdown: broadcast::Receiver<()>, config: Config) ->
         .clone();
     let build_rustc_version = state.build_info.compiler.version.clone();
     let show_header = !state.config.disable_header;
-    // TODO use value again
-    let _allowed_origins = state.config.cors_allowed_origins.clone();
+    let unversioned_routes_sunset = state.config.unversioned_routes_sunset.clone();
+    let cors = middleware::cors::cors_layer(&state.config.cors_allowed_origins)?;
 
     let state_arc = Arc::new(state);
 
-    let global_middleware = ServiceBuilder::new().layer(
-        TraceLayer::new_for_http()
-            .make_span_with(DefaultMakeSpan::new().include_headers(true))
-            .on_request(DefaultOnRequest::new().level(Level::INFO))
-            .on_response(
-                DefaultOnResponse::new()
-                    .level(Level::INFO)
-                    .include_headers(true),
-            ),
-    );
-
-    let tenancy_routes = Router::new()
-        .route("/", post(handlers::create_tenant::handler))
-        .route(
-            "/:id",
-            get(handlers::get_tenant::handler).delete(handlers::delete_tenant::handler),
-        )
-        .route("/:id/fcm", post(handlers::update_fcm::handler))
-        .route("/:id/apns", post(handlers::update_apns::handler))
+    let global_middleware = ServiceBuilder::new()
+        .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
         .layer(
-            global_middleware.clone().layer(
-                CorsLayer::new()
-                    .allow_methods([Method::GET, Method::POST, Method::DELETE])
-                    /*
-                        TOOD: switch back to using the real configuration
-                        allowed_origins
-                            .iter()
-                            .map(|v| v.parse::<HeaderValue>().unwrap())
-                            .collect::<Vec<HeaderValue>>(), */
-                    .allow_origin(AllowOrigin::any()),
-            ),
-        );
-
-    let app = match is_multitenant {
-        false => Router::new()
-            .route("/health", get(handlers::health::handler))
-            .route(
-                "/clients",
-                post(handlers::single_tenant_wrappers::register_handler),
-            )
-            .route(
-                "/clients/:id",
-                delete(handlers::single_tenant_wrappers::delete_handler),
-            )
-            .route(
-                "/clients/:id",
-                post(handlers::single_tenant_wrappers::push_handler),
-            ),
-        true => Router::new()
-            .route("/health", get(handlers::health::handler))
-            .nest("/tenants", tenancy_routes)
-            .route(
-                "/:tenant_id/clients",
-                post(handlers::register_client::handler),
-            )
-            .route(
-                "/:tenant_id/clients/:id",
-                delete(handlers::delete_client::handler),
-            )
-            .route(
-                "/:tenant_id/clients/:id",
-                post(handlers::push_message::handler),
-            ),
-    }
-    .layer(global_middleware)
-    .with_state(state_arc.clone());
+            TraceLayer::new_for_http()
+                .make_span_with(DefaultMakeSpan::new().include_headers(true))
+                .on_request(DefaultOnRequest::new().level(Level::INFO))
+                .on_response(
+                    DefaultOnResponse::new()
+                        .level(Level::INFO)
+                        .include_headers(true),
+                ),
+        )
+        .layer(PropagateRequestIdLayer::x_request_id());
+
+    let v1_routes = routes::v1(is_multitenant, is_hybrid).into_router();
+
+    // Each API version is nested under its own prefix, the unversioned routes are
+    // deprecated aliases of `/v1` kept for SDKs that predate versioning
+    let app = Router::new()
+        .route("/health", get(handlers::health::handler))
+        .route("/openapi.json", get(handlers::openapi::handler))
+        .nest("/v1", v1_routes.clone())
+        .merge(v1_routes.layer(middleware::deprecation::deprecated_routes(
+            HeaderValue::from_str(&unversioned_routes_sunset).map_err(|_| {
+                error::Error::InvalidConfiguration(
+                    "`UNVERSIONED_ROUTES_SUNSET` is not a valid header value".to_string(),
+                )
+            })?,
+        )))
+        .layer(axum::middleware::map_response(
+            middleware::error_envelope::wrap_errors,
+        ))
+        .layer(cors)
+        .layer(global_middleware)
+        .with_state(state_arc.clone());
+
+    let jobs = jobs::spawn(state_arc.clone());
 
     let private_app = Router::new()
         .route("/metrics", get(handlers::metrics::handler))
@@ -228,10 +231,14 @@ providers: [{}]
     let private_addr = SocketAddr::from(([0, 0, 0, 0], private_port));
 
     select! {
-        _ = axum::Server::bind(&addr).serve(app.into_make_service()) => info!("Server terminating"),
+        _ = axum::Server::bind(&addr).serve(app.into_make_service_with_connect_info::<SocketAddr>()) => info!("Server terminating"),
         _ = axum::Server::bind(&private_addr).serve(private_app.into_make_service()) => info!("Internal Server terminating"),
         _ = shutdown.recv() => info!("Shutdown signal received, killing servers"),
     }
 
+    for job in jobs {
+        job.abort();
+    }
+
     Ok(())
 }
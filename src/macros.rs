@@ -8,6 +8,14 @@ macro_rules! increment_counter {
             debug!("incremented `{}` counter", stringify!($metric));
         }
     }};
+    ($state:ident$(.$property:ident)*, $metric:ident, $attributes:expr) => {{
+        use {opentelemetry::Context, tracing::debug};
+
+        if let Some(metrics) = &$state$(.$property)* {
+            metrics.$metric.add(&Context::current(), 1, $attributes);
+            debug!("incremented `{}` counter", stringify!($metric));
+        }
+    }};
 }
 
 #[macro_export]
@@ -11,6 +11,10 @@ use {
     },
     opentelemetry_prometheus::PrometheusExporter,
     prometheus_core::TextEncoder,
+    std::sync::{
+        atomic::{AtomicI64, Ordering},
+        Arc,
+    },
 };
 
 #[derive(Clone)]
@@ -22,10 +26,22 @@ pub struct Metrics {
     pub sent_apns_notifications: Counter<u64>,
 
     pub registered_clients: UpDownCounter<i64>,
+    pub device_registrations: Counter<u64>,
+    pub reaped_clients: Counter<u64>,
+
+    pub deleted_notifications: Counter<u64>,
+    /// Last measured size of the notifications table in bytes, reported by an
+    /// observable gauge
+    pub notifications_table_size: Arc<AtomicI64>,
     pub registered_tenants: UpDownCounter<i64>,
 
+    pub rate_limited_requests: Counter<u64>,
+
     pub tenant_apns_updates: Counter<u64>,
     pub tenant_fcm_updates: Counter<u64>,
+
+    pub tenant_cache_hits: Counter<u64>,
+    pub tenant_cache_misses: Counter<u64>,
 }
 
 impl Metrics {
@@ -53,6 +69,31 @@ impl Metrics {
             .with_description("The number of currently registered clients")
             .init();
 
+        let device_registrations_counter = meter
+            .u64_counter("device_registrations")
+            .with_description("The number of device registrations by push type, SDK and OS family")
+            .init();
+
+        let reaped_clients_counter = meter
+            .u64_counter("reaped_clients")
+            .with_description("The number of stale clients removed by the reaper")
+            .init();
+
+        let deleted_notifications_counter = meter
+            .u64_counter("deleted_notifications")
+            .with_description("The number of notifications removed by the retention cleanup")
+            .init();
+
+        let notifications_table_size = Arc::new(AtomicI64::new(0));
+        let notifications_table_size_gauge = meter
+            .i64_observable_gauge("notifications_table_size")
+            .with_description("The size of the notifications table in bytes")
+            .init();
+        let table_size = notifications_table_size.clone();
+        meter.register_callback(move |cx| {
+            notifications_table_size_gauge.observe(cx, table_size.load(Ordering::Relaxed), &[])
+        })?;
+
         let tenants_counter = meter
             .i64_up_down_counter("registered_tenants")
             .with_description("The number of currently registered tenants")
@@ -73,6 +114,11 @@ impl Metrics {
             .with_description("The number of notifications sent to APNS")
             .init();
 
+        let rate_limited_requests_counter = meter
+            .u64_counter("rate_limited_requests")
+            .with_description("The number of requests rejected by a rate limit")
+            .init();
+
         let tenant_apns_updates_counter = meter
             .u64_counter("tenant_apns_updates")
             .with_description("The number of times tenants have updated their APNS")
@@ -83,15 +129,32 @@ impl Metrics {
             .with_description("The number of times tenants have updated their APNS")
             .init();
 
+        let tenant_cache_hits_counter = meter
+            .u64_counter("tenant_cache_hits")
+            .with_description("The number of tenant lookups served from the cache")
+            .init();
+
+        let tenant_cache_misses_counter = meter
+            .u64_counter("tenant_cache_misses")
+            .with_description("The number of tenant lookups that missed the cache")
+            .init();
+
         Ok(Metrics {
             prometheus_exporter,
             registered_clients: clients_counter,
+            device_registrations: device_registrations_counter,
+            reaped_clients: reaped_clients_counter,
+            deleted_notifications: deleted_notifications_counter,
+            notifications_table_size,
             received_notifications: received_notification_counter,
             sent_fcm_notifications: sent_fcm_notification_counter,
             sent_apns_notifications: sent_apns_notification_counter,
             registered_tenants: tenants_counter,
+            rate_limited_requests: rate_limited_requests_counter,
             tenant_apns_updates: tenant_apns_updates_counter,
             tenant_fcm_updates: tenant_fcm_updates_counter,
+            tenant_cache_hits: tenant_cache_hits_counter,
+            tenant_cache_misses: tenant_cache_misses_counter,
         })
     }
 
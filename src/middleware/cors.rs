@@ -0,0 +1,132 @@
+use {
+    crate::{
+        error::{Error::InvalidConfiguration, Result},
+        middleware::{
+            deprecation::{DEPRECATION_HEADER_NAME, SUNSET_HEADER_NAME},
+            resolve_tenant::{TENANT_CLIENT_KEY_HEADER_NAME, TENANT_ID_HEADER_NAME},
+            validate_signature::{SIGNATURE_HEADER_NAME, TIMESTAMP_HEADER_NAME},
+        },
+    },
+    axum::http::{
+        header::{HeaderName, AUTHORIZATION, CONTENT_TYPE, RETRY_AFTER},
+        HeaderValue,
+        Method,
+    },
+    std::time::Duration,
+    tower_http::cors::{AllowOrigin, CorsLayer},
+};
+
+const ANY_ORIGIN: &str = "*";
+const SCHEME_SEPARATOR: &str = "://";
+const WILDCARD_SUBDOMAIN: &str = "*.";
+
+/// How long browsers may cache the result of a preflight request
+const PREFLIGHT_MAX_AGE: Duration = Duration::from_secs(60 * 60);
+
+/// An entry in `CORS_ALLOWED_ORIGINS`
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum AllowedOrigin {
+    /// e.g. `https://example.com`
+    Exact(HeaderValue),
+    /// e.g. `https://*.example.com`, this matches any subdomain of
+    /// `example.com` but not `example.com` itself
+    Subdomain { scheme: String, domain: String },
+}
+
+impl AllowedOrigin {
+    fn parse(origin: &str) -> Result<Self> {
+        let invalid = || {
+            InvalidConfiguration(format!(
+                "`CORS_ALLOWED_ORIGINS` contains an invalid origin ({origin}), expected `*`, \
+                 `scheme://host` or `scheme://*.host`"
+            ))
+        };
+
+        let (scheme, host) = origin.split_once(SCHEME_SEPARATOR).ok_or_else(invalid)?;
+        if scheme.is_empty() || host.is_empty() || host.contains('/') {
+            return Err(invalid());
+        }
+
+        match host.strip_prefix(WILDCARD_SUBDOMAIN) {
+            Some(domain) if !domain.is_empty() && !domain.contains('*') => {
+                Ok(AllowedOrigin::Subdomain {
+                    scheme: scheme.to_lowercase(),
+                    domain: domain.to_lowercase(),
+                })
+            }
+            Some(_) => Err(invalid()),
+            None if host.contains('*') => Err(invalid()),
+            None => Ok(AllowedOrigin::Exact(
+                HeaderValue::from_str(origin).map_err(|_| invalid())?,
+            )),
+        }
+    }
+
+    fn matches(&self, origin: &HeaderValue) -> bool {
+        match self {
+            AllowedOrigin::Exact(allowed) => allowed == origin,
+            AllowedOrigin::Subdomain { scheme, domain } => origin
+                .to_str()
+                .ok()
+                .map(str::to_lowercase)
+                .and_then(|origin| {
+                    let host = origin
+                        .strip_prefix(scheme)?
+                        .strip_prefix(SCHEME_SEPARATOR)?;
+                    let subdomain = host.strip_suffix(domain)?.strip_suffix('.')?;
+                    Some(subdomain.split('.').all(is_valid_label))
+                })
+                .unwrap_or_default(),
+        }
+    }
+}
+
+fn is_valid_label(label: &str) -> bool {
+    !label.is_empty() && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+/// The header name constants aren't all lowercase, so can't use `from_static`
+fn header_name(name: &str) -> HeaderName {
+    HeaderName::try_from(name).expect("header name constants are valid")
+}
+
+fn allow_origin(allowed_origins: &[String]) -> Result<AllowOrigin> {
+    if allowed_origins.iter().any(|origin| origin == ANY_ORIGIN) {
+        return Ok(AllowOrigin::any());
+    }
+
+    let allowed_origins = allowed_origins
+        .iter()
+        .map(|origin| AllowedOrigin::parse(origin))
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(AllowOrigin::predicate(move |origin, _| {
+        allowed_origins
+            .iter()
+            .any(|allowed| allowed.matches(origin))
+    }))
+}
+
+/// Builds the CORS layer from `CORS_ALLOWED_ORIGINS`, which accepts exact
+/// origins, wildcard subdomains like `https://*.example.com` or `*` to allow
+/// any origin
+pub fn cors_layer(allowed_origins: &[String]) -> Result<CorsLayer> {
+    Ok(CorsLayer::new()
+        .allow_origin(allow_origin(allowed_origins)?)
+        .allow_methods([Method::GET, Method::POST, Method::DELETE])
+        .allow_headers([
+            AUTHORIZATION,
+            CONTENT_TYPE,
+            header_name(TENANT_ID_HEADER_NAME),
+            header_name(TENANT_CLIENT_KEY_HEADER_NAME),
+            header_name(SIGNATURE_HEADER_NAME),
+            header_name(TIMESTAMP_HEADER_NAME),
+        ])
+        .expose_headers([
+            RETRY_AFTER,
+            HeaderName::from_static("x-request-id"),
+            header_name(DEPRECATION_HEADER_NAME),
+            header_name(SUNSET_HEADER_NAME),
+        ])
+        .max_age(PREFLIGHT_MAX_AGE))
+}
@@ -0,0 +1,32 @@
+use {
+    axum::http::{header::HeaderName, HeaderValue},
+    tower::{
+        layer::util::{Identity, Stack},
+        ServiceBuilder,
+    },
+    tower_http::set_header::SetResponseHeaderLayer,
+};
+
+pub const DEPRECATION_HEADER_NAME: &str = "deprecation";
+pub const SUNSET_HEADER_NAME: &str = "sunset";
+
+type DeprecationLayer = ServiceBuilder<
+    Stack<
+        SetResponseHeaderLayer<HeaderValue>,
+        Stack<SetResponseHeaderLayer<HeaderValue>, Identity>,
+    >,
+>;
+
+/// Marks responses from routes that have a versioned replacement, `sunset` is
+/// the HTTP date after which they may be removed
+pub fn deprecated_routes(sunset: HeaderValue) -> DeprecationLayer {
+    ServiceBuilder::new()
+        .layer(SetResponseHeaderLayer::overriding(
+            HeaderName::from_static(DEPRECATION_HEADER_NAME),
+            HeaderValue::from_static("true"),
+        ))
+        .layer(SetResponseHeaderLayer::overriding(
+            HeaderName::from_static(SUNSET_HEADER_NAME),
+            sunset,
+        ))
+}
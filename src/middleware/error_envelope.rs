@@ -0,0 +1,60 @@
+use {
+    crate::{
+        error::ErrorCode,
+        handlers::{Response, ResponseError},
+    },
+    axum::{
+        http::{
+            header::{CONTENT_LENGTH, CONTENT_TYPE},
+            StatusCode,
+        },
+        response::{IntoResponse, Response as HttpResponse},
+    },
+};
+
+/// Errors from axum itself, like extractor rejections and unknown routes, are
+/// plain text. This wraps them in the same `Response` envelope as the errors
+/// returned by handlers
+pub async fn wrap_errors(response: HttpResponse) -> HttpResponse {
+    let status = response.status();
+    let is_json = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|header| header.to_str().ok())
+        .map(|content_type| content_type.starts_with("application/json"))
+        .unwrap_or_default();
+    if is_json || !(status.is_client_error() || status.is_server_error()) {
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let message = match hyper::body::to_bytes(body).await {
+        Ok(bytes) if !bytes.is_empty() => String::from_utf8_lossy(&bytes).into_owned(),
+        _ => status.canonical_reason().unwrap_or_default().to_string(),
+    };
+    let (code, name) = match status {
+        StatusCode::NOT_FOUND => (ErrorCode::NotFound, "not_found"),
+        StatusCode::METHOD_NOT_ALLOWED => (ErrorCode::MethodNotAllowed, "method_not_allowed"),
+        StatusCode::UNSUPPORTED_MEDIA_TYPE => (
+            ErrorCode::UnsupportedContentType,
+            "unsupported_content_type",
+        ),
+        status if status.is_client_error() => (ErrorCode::InvalidRequest, "invalid_request"),
+        _ => (ErrorCode::Internal, "unknown_error"),
+    };
+
+    let error = ResponseError {
+        name: name.to_string(),
+        code,
+        message,
+    };
+    let mut wrapped = Response::new_failure(status, vec![error], vec![]).into_response();
+
+    // Keep headers like `Allow` and the request ID, but not those of the old body
+    let mut headers = parts.headers;
+    headers.remove(CONTENT_TYPE);
+    headers.remove(CONTENT_LENGTH);
+    wrapped.headers_mut().extend(headers);
+
+    wrapped
+}
@@ -0,0 +1,59 @@
+use {
+    crate::error::Error::{self, InvalidRequestBody, UnsupportedContentType},
+    async_trait::async_trait,
+    axum::{
+        extract::{
+            multipart::MultipartRejection,
+            rejection::JsonRejection,
+            FromRequest,
+            Multipart,
+        },
+        http::{header::CONTENT_TYPE, Request},
+        Json,
+    },
+    serde::de::DeserializeOwned,
+};
+
+/// Extracts a body sent as either `application/json` or `multipart/form-data`
+/// depending on the request's content type
+pub enum JsonOrMultipart<T> {
+    Json(T),
+    Multipart(Multipart),
+}
+
+#[async_trait]
+impl<S, B, T> FromRequest<S, B> for JsonOrMultipart<T>
+where
+    // these bounds are required by
+    // `async_trait`
+    B: Send + 'static,
+    S: Send + Sync,
+    T: DeserializeOwned,
+    Json<T>: FromRequest<S, B, Rejection = JsonRejection>,
+    Multipart: FromRequest<S, B, Rejection = MultipartRejection>,
+{
+    type Rejection = Error;
+
+    async fn from_request(req: Request<B>, state: &S) -> Result<Self, Self::Rejection> {
+        let content_type = req
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|header| header.to_str().ok())
+            .unwrap_or_default()
+            .to_lowercase();
+
+        if content_type.starts_with("application/json") {
+            let Json(body) = Json::<T>::from_request(req, state)
+                .await
+                .map_err(|e| InvalidRequestBody(e.body_text()))?;
+            Ok(Self::Json(body))
+        } else if content_type.starts_with("multipart/form-data") {
+            let multipart = Multipart::from_request(req, state)
+                .await
+                .map_err(|e| InvalidRequestBody(e.body_text()))?;
+            Ok(Self::Multipart(multipart))
+        } else {
+            Err(UnsupportedContentType(content_type))
+        }
+    }
+}
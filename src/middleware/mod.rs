@@ -1 +1,9 @@
+pub mod cors;
+pub mod deprecation;
+pub mod error_envelope;
+pub mod json_or_multipart;
+pub mod request_id;
+pub mod resolve_tenant;
+pub mod validate_client_jwt;
 pub mod validate_signature;
+pub mod validate_tenant_auth;
@@ -0,0 +1,31 @@
+use {
+    async_trait::async_trait,
+    axum::{extract::FromRequestParts, http::request::Parts},
+    std::convert::Infallible,
+    uuid::Uuid,
+};
+
+/// Set on every request by `SetRequestIdLayer` unless the caller provided one
+pub const REQUEST_ID_HEADER_NAME: &str = "x-request-id";
+
+/// The ID of the current request, used to correlate logs and audit entries
+pub struct RequestId(pub String);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for RequestId
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let request_id = parts
+            .headers
+            .get(REQUEST_ID_HEADER_NAME)
+            .and_then(|header| header.to_str().ok())
+            .map(ToString::to_string)
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
+
+        Ok(Self(request_id))
+    }
+}
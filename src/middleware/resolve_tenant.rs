@@ -0,0 +1,113 @@
+use {
+    crate::{
+        error::Error::{self, MissingTenantId, UnknownClientKey},
+        state::State,
+    },
+    async_trait::async_trait,
+    axum::{
+        extract::{FromRequestParts, Path},
+        http::{header::HOST, request::Parts},
+    },
+    serde::Deserialize,
+    std::collections::HashMap,
+};
+
+pub const TENANT_ID_HEADER_NAME: &str = "X-Tenant-Id";
+pub const TENANT_CLIENT_KEY_HEADER_NAME: &str = "X-Tenant-Client-Key";
+
+const TENANT_ID_PATH_PARAM: &str = "tenant_id";
+
+/// Ways of working out which tenant a request is for, they're tried in the
+/// order configured by `TENANT_RESOLVERS`
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TenantResolver {
+    /// The `:tenant_id` segment of `/:tenant_id/...` routes
+    Path,
+    /// `{tenant_id}.{TENANT_SUBDOMAIN_HOST}` in the `Host` header
+    Subdomain,
+    /// The `X-Tenant-Id` header
+    Header,
+    /// The tenant that owns the client key in the `X-Tenant-Client-Key`
+    /// header, an unknown key is rejected rather than falling through
+    ClientKey,
+}
+
+/// The ID of the tenant a client request is for. When none of the resolvers
+/// match, the default tenant is used in single-tenant and hybrid modes
+pub struct ResolvedTenant(pub String);
+
+impl TenantResolver {
+    async fn resolve<S>(&self, parts: &mut Parts, state: &S) -> Result<Option<String>, Error>
+    where
+        S: Send + Sync + State,
+    {
+        match self {
+            TenantResolver::Path => Ok(Path::<HashMap<String, String>>::from_request_parts(
+                parts, state,
+            )
+            .await
+            .ok()
+            .and_then(|Path(mut params)| params.remove(TENANT_ID_PATH_PARAM))),
+            TenantResolver::Subdomain => {
+                let subdomain_host = match state.config().tenant_subdomain_host {
+                    Some(subdomain_host) => subdomain_host,
+                    None => return Ok(None),
+                };
+
+                Ok(header_value(parts, HOST.as_str()).and_then(|host| {
+                    // Ignore the port, if there is one
+                    let host = host.split(':').next().unwrap_or_default();
+                    host.strip_suffix(&subdomain_host)
+                        .and_then(|tenant_id| tenant_id.strip_suffix('.'))
+                        .filter(|tenant_id| !tenant_id.is_empty() && !tenant_id.contains('.'))
+                        .map(ToString::to_string)
+                }))
+            }
+            TenantResolver::Header => Ok(header_value(parts, TENANT_ID_HEADER_NAME)
+                .filter(|tenant_id| !tenant_id.is_empty())
+                .map(ToString::to_string)),
+            TenantResolver::ClientKey => match header_value(parts, TENANT_CLIENT_KEY_HEADER_NAME) {
+                Some(client_key) => state
+                    .tenant_store()
+                    .find_tenant_by_client_key(client_key)
+                    .await?
+                    .map(|tenant| Some(tenant.id))
+                    .ok_or(UnknownClientKey),
+                None => Ok(None),
+            },
+        }
+    }
+}
+
+fn header_value<'a>(parts: &'a Parts, name: &str) -> Option<&'a str> {
+    parts
+        .headers
+        .get(name)
+        .and_then(|header| header.to_str().ok())
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for ResolvedTenant
+where
+    S: Send + Sync + State,
+{
+    type Rejection = Error;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let config = state.config();
+
+        if state.is_multitenant() {
+            for resolver in &config.tenant_resolvers {
+                if let Some(tenant_id) = resolver.resolve(parts, state).await? {
+                    return Ok(Self(tenant_id));
+                }
+            }
+        }
+
+        match !state.is_multitenant() || state.is_hybrid() {
+            true => Ok(Self(config.default_tenant_id)),
+            false => Err(MissingTenantId),
+        }
+    }
+}
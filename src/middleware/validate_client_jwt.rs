@@ -0,0 +1,200 @@
+use {
+    crate::{
+        error::Error::{self, InvalidClientAuth, MissingClientAuth},
+        handlers::DECENTRALIZED_IDENTIFIER_PREFIX,
+        state::State,
+    },
+    async_trait::async_trait,
+    axum::{
+        extract::FromRequestParts,
+        http::{header::AUTHORIZATION, request::Parts},
+    },
+    base64::Engine as _,
+    chrono::Utc,
+    ed25519_dalek::{PublicKey, Signature, Verifier},
+    serde::{Deserialize, Serialize},
+    tracing::span,
+};
+
+pub const CLIENT_AUTH_HEADER_NAME: &str = "Authorization";
+
+const BEARER_PREFIX: &str = "Bearer ";
+const JWT_DELIMITER: char = '.';
+const JWT_ALGORITHM: &str = "EdDSA";
+const JWT_TYPE: &str = "JWT";
+
+/// Multibase prefix for base58btc encoded keys
+const MULTIBASE_BASE58BTC_PREFIX: char = 'z';
+/// Multicodec prefix for Ed25519 public keys
+const MULTICODEC_ED25519_HEADER: [u8; 2] = [0xed, 0x01];
+
+/// Allowed clock drift between the client and echo server
+const JWT_LEEWAY_SECONDS: i64 = 60;
+/// Longest a token may be valid for, from `iat` to `exp`, so a leaked token
+/// can't be used indefinitely
+pub const JWT_MAX_LIFETIME_SECONDS: i64 = 60 * 60 * 24;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct JwtHeader {
+    pub alg: String,
+    pub typ: String,
+}
+
+impl Default for JwtHeader {
+    fn default() -> Self {
+        JwtHeader {
+            alg: JWT_ALGORITHM.to_string(),
+            typ: JWT_TYPE.to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ClientJwtClaims {
+    /// The client's `did:key`, the JWT must be signed with this key
+    pub iss: String,
+    /// Must match the `PUBLIC_URL` of this echo server
+    pub aud: String,
+    pub iat: i64,
+    pub exp: i64,
+}
+
+/// Proof that the request was made by the holder of a client's `did:key`. The
+/// client ID is `None` when client signature validation is disabled.
+pub struct RequireClientAuth(pub Option<String>);
+
+impl RequireClientAuth {
+    /// Check the authenticated client matches the client being modified
+    pub fn check(&self, client_id: &str) -> Result<(), Error> {
+        match &self.0 {
+            Some(authenticated) if authenticated != client_id => Err(InvalidClientAuth(
+                "token was not issued by the requested client".to_string(),
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for RequireClientAuth
+where
+    S: Send + Sync + State,
+{
+    type Rejection = Error;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        if !state.validate_client_signatures() {
+            // Skip client validation
+            return Ok(Self(None));
+        }
+
+        let s = span!(tracing::Level::DEBUG, "validate_client_jwt");
+        let _ = s.enter();
+
+        let jwt = parts
+            .headers
+            .get(AUTHORIZATION)
+            .and_then(|header| header.to_str().ok())
+            .and_then(|header| header.strip_prefix(BEARER_PREFIX))
+            .ok_or(MissingClientAuth)?;
+
+        let client_id = verify_client_jwt(jwt, &state.config().public_url)?;
+
+        Ok(Self(Some(client_id)))
+    }
+}
+
+/// Verifies a JWT signed by a client's `did:key` and returns the client ID
+pub fn verify_client_jwt(jwt: &str, audience: &str) -> Result<String, Error> {
+    let mut segments = jwt.split(JWT_DELIMITER);
+    let (header, claims, signature) = match (
+        segments.next(),
+        segments.next(),
+        segments.next(),
+        segments.next(),
+    ) {
+        (Some(header), Some(claims), Some(signature), None) => (header, claims, signature),
+        _ => return Err(InvalidClientAuth("malformed token".to_string())),
+    };
+
+    let decoded_header: JwtHeader = serde_json::from_slice(&decode_segment(header)?)
+        .map_err(|_| InvalidClientAuth("malformed token header".to_string()))?;
+    if decoded_header.alg != JWT_ALGORITHM {
+        return Err(InvalidClientAuth(format!(
+            "unsupported algorithm {}",
+            decoded_header.alg
+        )));
+    }
+
+    let decoded_claims: ClientJwtClaims = serde_json::from_slice(&decode_segment(claims)?)
+        .map_err(|_| InvalidClientAuth("malformed token claims".to_string()))?;
+
+    let public_key = did_key_to_public_key(&decoded_claims.iss)?;
+    let signature = Signature::from_bytes(&decode_segment(signature)?)
+        .map_err(|_| InvalidClientAuth("malformed signature".to_string()))?;
+    let message = format!("{header}{JWT_DELIMITER}{claims}");
+    if public_key.verify(message.as_bytes(), &signature).is_err() {
+        return Err(InvalidClientAuth("invalid signature".to_string()));
+    }
+
+    if decoded_claims.aud.trim_end_matches('/') != audience.trim_end_matches('/') {
+        return Err(InvalidClientAuth("invalid audience".to_string()));
+    }
+
+    let now = Utc::now().timestamp();
+    if decoded_claims.exp + JWT_LEEWAY_SECONDS < now {
+        return Err(InvalidClientAuth("token expired".to_string()));
+    }
+    if decoded_claims.iat - JWT_LEEWAY_SECONDS > now {
+        return Err(InvalidClientAuth("token issued in the future".to_string()));
+    }
+    if decoded_claims.exp - decoded_claims.iat > JWT_MAX_LIFETIME_SECONDS {
+        return Err(InvalidClientAuth(format!(
+            "token is valid for longer than {JWT_MAX_LIFETIME_SECONDS} seconds"
+        )));
+    }
+
+    Ok(decoded_claims
+        .iss
+        .trim_start_matches(DECENTRALIZED_IDENTIFIER_PREFIX)
+        .to_string())
+}
+
+/// Decodes the Ed25519 public key from a `did:key`
+pub fn did_key_to_public_key(did_key: &str) -> Result<PublicKey, Error> {
+    let encoded = did_key
+        .strip_prefix(DECENTRALIZED_IDENTIFIER_PREFIX)
+        .and_then(|key| key.strip_prefix(MULTIBASE_BASE58BTC_PREFIX))
+        .ok_or_else(|| InvalidClientAuth("issuer is not a base58btc did:key".to_string()))?;
+
+    let decoded = bs58::decode(encoded)
+        .into_vec()
+        .map_err(|_| InvalidClientAuth("issuer is not a base58btc did:key".to_string()))?;
+
+    match decoded.strip_prefix(&MULTICODEC_ED25519_HEADER) {
+        Some(key) => PublicKey::from_bytes(key)
+            .map_err(|_| InvalidClientAuth("issuer is not a valid Ed25519 key".to_string())),
+        None => Err(InvalidClientAuth(
+            "issuer is not an Ed25519 did:key".to_string(),
+        )),
+    }
+}
+
+/// Encodes an Ed25519 public key as a `did:key`
+pub fn public_key_to_did_key(public_key: &PublicKey) -> String {
+    let mut bytes = MULTICODEC_ED25519_HEADER.to_vec();
+    bytes.extend_from_slice(public_key.as_bytes());
+
+    format!(
+        "{}{}{}",
+        DECENTRALIZED_IDENTIFIER_PREFIX,
+        MULTIBASE_BASE58BTC_PREFIX,
+        bs58::encode(bytes).into_string()
+    )
+}
+
+fn decode_segment(segment: &str) -> Result<Vec<u8>, Error> {
+    base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(segment)
+        .map_err(|_| InvalidClientAuth("token is not base64url encoded".to_string()))
+}
@@ -0,0 +1,108 @@
+use {
+    crate::{
+        error::Error::{self, MissingTenantAuth, UnauthorizedTenantAuth},
+        state::State,
+        stores::tenant::Tenant,
+    },
+    async_trait::async_trait,
+    axum::{
+        extract::FromRequestParts,
+        http::{header::AUTHORIZATION, request::Parts},
+    },
+    sha2::{Digest, Sha256},
+    std::fmt::{Display, Formatter},
+    uuid::Uuid,
+};
+
+pub const TENANT_AUTH_HEADER_NAME: &str = "Authorization";
+
+const BEARER_PREFIX: &str = "Bearer ";
+
+/// Who made an authenticated tenant request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Actor {
+    /// An operator using the `ADMIN_API_KEY`
+    Admin,
+    /// A tenant using its own API key
+    Tenant(String),
+}
+
+impl Display for Actor {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Actor::Admin => write!(f, "admin"),
+            Actor::Tenant(id) => write!(f, "tenant:{id}"),
+        }
+    }
+}
+
+/// The API key presented by the caller, it is checked against the tenant once
+/// the handler has loaded it
+pub struct RequireTenantAuth {
+    api_key_hash: String,
+    is_admin: bool,
+}
+
+impl RequireTenantAuth {
+    /// Check the caller is either an admin or holds the tenant's API key
+    pub fn check(&self, tenant: &Tenant) -> Result<Actor, Error> {
+        if self.is_admin {
+            return Ok(Actor::Admin);
+        }
+
+        match &tenant.api_key_hash {
+            Some(hash) if hash == &self.api_key_hash => Ok(Actor::Tenant(tenant.id.clone())),
+            _ => Err(UnauthorizedTenantAuth),
+        }
+    }
+
+    /// Check the caller is an admin
+    pub fn check_admin(&self) -> Result<Actor, Error> {
+        match self.is_admin {
+            true => Ok(Actor::Admin),
+            false => Err(UnauthorizedTenantAuth),
+        }
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for RequireTenantAuth
+where
+    S: Send + Sync + State,
+{
+    type Rejection = Error;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let api_key = parts
+            .headers
+            .get(AUTHORIZATION)
+            .and_then(|header| header.to_str().ok())
+            .and_then(|header| header.strip_prefix(BEARER_PREFIX))
+            .ok_or(MissingTenantAuth)?;
+
+        let api_key_hash = hash_api_key(api_key);
+        let is_admin = match &state.config().admin_api_key {
+            Some(admin_api_key) => hash_api_key(admin_api_key) == api_key_hash,
+            None => false,
+        };
+
+        Ok(Self {
+            api_key_hash,
+            is_admin,
+        })
+    }
+}
+
+/// Generates a new random tenant API key
+pub fn generate_api_key() -> String {
+    format!(
+        "{}{}",
+        Uuid::new_v4().as_simple(),
+        Uuid::new_v4().as_simple()
+    )
+}
+
+/// API keys are only ever stored hashed
+pub fn hash_api_key(api_key: &str) -> String {
+    hex::encode(Sha256::digest(api_key.as_bytes()))
+}
@@ -0,0 +1,525 @@
+use {
+    crate::{
+        handlers::{
+            create_tenant::{TenantRegisterBody, TenantRegisterResponse},
+            get_audit_log::{AuditLogQuery, AuditLogResponse},
+            get_client::ClientResponse,
+            get_notification::NotificationHistoryResponse,
+            get_tenant::GetTenantResponse,
+            get_usage::{UsageQuery, UsageResponse},
+            list_clients::{ListClientsQuery, ListClientsResponse},
+            list_tenants::{ListTenantsQuery, ListTenantsResponse},
+            list_webhook_deliveries::{WebhookDeliveriesQuery, WebhookDeliveriesResponse},
+            push_message::{PushMessageBody, PUSH_ACCEPTED_STATUS},
+            register_client::RegisterBody,
+            rename_tenant::{TenantRenameBody, TenantRenameResponse},
+            update_apns::ApnsUpdateBody,
+            update_fcm::FcmUpdateBody,
+            update_quota::QuotaUpdateBody,
+            update_rate_limit::RateLimitUpdateBody,
+            update_webhook::WebhookUpdateBody,
+            Response,
+            ENVELOPE_KEYS,
+        },
+        middleware::{
+            resolve_tenant::{TENANT_CLIENT_KEY_HEADER_NAME, TENANT_ID_HEADER_NAME},
+            validate_signature::{SIGNATURE_HEADER_NAME, TIMESTAMP_HEADER_NAME},
+        },
+    },
+    hyper::StatusCode,
+    schemars::{
+        gen::{SchemaGenerator, SchemaSettings},
+        JsonSchema,
+    },
+    serde_json::{json, Map, Value},
+};
+
+const TENANT_API_KEY_SCHEME: &str = "tenant_api_key";
+const CLIENT_JWT_SCHEME: &str = "client_jwt";
+
+/// How an operation authenticates its caller
+#[derive(Copy, Clone)]
+enum Auth {
+    None,
+    /// The tenant's API key or the admin API key
+    Tenant,
+    /// A JWT signed by the client's key
+    Client,
+    /// The relay's Ed25519 signature of the body
+    Signature,
+}
+
+/// Builds the OpenAPI document, schemas are collected from the handler types
+/// as operations are added
+struct Document {
+    gen: SchemaGenerator,
+    paths: Map<String, Value>,
+}
+
+impl Document {
+    fn new() -> Self {
+        Document {
+            gen: SchemaSettings::openapi3().into_generator(),
+            paths: Map::new(),
+        }
+    }
+
+    fn operation(
+        &mut self,
+        method: &'static str,
+        path: &'static str,
+        summary: &'static str,
+        auth: Auth,
+    ) -> OperationBuilder<'_> {
+        let mut operation = Map::new();
+        operation.insert("summary".to_string(), json!(summary));
+        operation.insert(
+            "tags".to_string(),
+            json!([match path.contains("/tenants") {
+                true => "tenants",
+                false => "clients",
+            }]),
+        );
+
+        let mut parameters = path_parameters(path);
+        match auth {
+            Auth::None => {}
+            Auth::Tenant => {
+                operation.insert(
+                    "security".to_string(),
+                    json!([{ TENANT_API_KEY_SCHEME: [] }]),
+                );
+            }
+            Auth::Client => {
+                operation.insert("security".to_string(), json!([{ CLIENT_JWT_SCHEME: [] }]));
+            }
+            Auth::Signature => {
+                parameters.push(header_parameter(
+                    SIGNATURE_HEADER_NAME,
+                    "Hex encoded Ed25519 signature of the timestamp and body",
+                ));
+                parameters.push(header_parameter(
+                    TIMESTAMP_HEADER_NAME,
+                    "Unix timestamp the signature was made at",
+                ));
+            }
+        }
+
+        OperationBuilder {
+            document: self,
+            method,
+            path,
+            operation,
+            parameters,
+            status: StatusCode::OK,
+        }
+    }
+
+    fn into_value(mut self) -> Value {
+        let schemas: Map<String, Value> = self
+            .gen
+            .take_definitions()
+            .into_iter()
+            .map(|(name, schema)| (name, json!(schema)))
+            .collect();
+
+        json!({
+            "openapi": "3.0.3",
+            "info": {
+                "title": "Echo Server",
+                "version": "1",
+                "description": "Routes are served under `/v1`. The unversioned routes are \
+                    deprecated aliases of `/v1` and respond with `Deprecation` and `Sunset` \
+                    headers. Routes with a `{tenant_id}` path segment and the `/v1/tenants` \
+                    routes are only served in multi-tenant and hybrid modes.",
+            },
+            "paths": self.paths,
+            "components": {
+                "schemas": schemas,
+                "securitySchemes": {
+                    TENANT_API_KEY_SCHEME: {
+                        "type": "http",
+                        "scheme": "bearer",
+                        "description": "The tenant's API key, or the admin API key",
+                    },
+                    CLIENT_JWT_SCHEME: {
+                        "type": "http",
+                        "scheme": "bearer",
+                        "bearerFormat": "JWT",
+                        "description": "A JWT signed by the client's Ed25519 key",
+                    },
+                },
+            },
+        })
+    }
+}
+
+struct OperationBuilder<'a> {
+    document: &'a mut Document,
+    method: &'static str,
+    path: &'static str,
+    operation: Map<String, Value>,
+    parameters: Vec<Value>,
+    status: StatusCode,
+}
+
+impl OperationBuilder<'_> {
+    /// Adds each field of `T` as a query parameter
+    fn query<T: JsonSchema>(mut self) -> Self {
+        let root = self.document.gen.root_schema_for::<T>();
+        if let Some(object) = root.schema.object {
+            for (name, schema) in object.properties {
+                self.parameters.push(json!({
+                    "name": name,
+                    "in": "query",
+                    "required": object.required.contains(&name),
+                    "schema": schema,
+                }));
+            }
+        }
+
+        self
+    }
+
+    /// The tenant can also be given by the headers `ResolvedTenant` checks
+    fn resolved_tenant(mut self) -> Self {
+        for name in [TENANT_ID_HEADER_NAME, TENANT_CLIENT_KEY_HEADER_NAME] {
+            self.parameters.push(json!({
+                "name": name,
+                "in": "header",
+                "required": false,
+                "schema": { "type": "string" },
+            }));
+        }
+
+        self
+    }
+
+    fn body<T: JsonSchema>(mut self) -> Self {
+        let schema = json!(self.document.gen.subschema_for::<T>());
+        self.operation.insert(
+            "requestBody".to_string(),
+            json!({
+                "required": true,
+                "content": { "application/json": { "schema": schema } },
+            }),
+        );
+
+        self
+    }
+
+    /// As `body`, but also accepts a form with the same fields
+    fn json_or_multipart_body<T: JsonSchema>(mut self) -> Self {
+        let schema = json!(self.document.gen.subschema_for::<T>());
+        self.operation.insert(
+            "requestBody".to_string(),
+            json!({
+                "required": true,
+                "content": {
+                    "application/json": { "schema": schema },
+                    "multipart/form-data": { "schema": schema },
+                },
+            }),
+        );
+
+        self
+    }
+
+    /// The status of the successful response, for handlers that don't use
+    /// `200 OK`
+    fn status(mut self, status: StatusCode) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// Adds the successful response, with `T` as its payload, and the operation
+    /// to the document. Panics if `T` has a field named like one of the
+    /// envelope's, as serializing it would overwrite that key
+    fn response<T: JsonSchema>(mut self) {
+        if let Some(object) = self.document.gen.root_schema_for::<T>().schema.object {
+            if let Some(key) = ENVELOPE_KEYS
+                .iter()
+                .find(|key| object.properties.contains_key(**key))
+            {
+                panic!(
+                    "{} has a `{key}` field, which would replace the envelope's",
+                    T::schema_name()
+                );
+            }
+        }
+
+        let success = json!(self.document.gen.subschema_for::<Response<T>>());
+        let failure = json!(self.document.gen.subschema_for::<Response>());
+        self.operation.insert(
+            "responses".to_string(),
+            json!({
+                self.status.as_str(): {
+                    "description": "Success",
+                    "content": { "application/json": { "schema": success } },
+                },
+                "default": {
+                    "description": "Failure",
+                    "content": { "application/json": { "schema": failure } },
+                },
+            }),
+        );
+        if !self.parameters.is_empty() {
+            self.operation
+                .insert("parameters".to_string(), Value::Array(self.parameters));
+        }
+
+        self.document
+            .paths
+            .entry(self.path)
+            .or_insert_with(|| json!({}))
+            .as_object_mut()
+            .expect("Paths are objects")
+            .insert(self.method.to_string(), Value::Object(self.operation));
+    }
+}
+
+fn path_parameters(path: &str) -> Vec<Value> {
+    path.split('/')
+        .filter_map(|segment| segment.strip_prefix('{')?.strip_suffix('}'))
+        .map(|name| {
+            json!({
+                "name": name,
+                "in": "path",
+                "required": true,
+                "schema": { "type": "string" },
+            })
+        })
+        .collect()
+}
+
+fn header_parameter(name: &str, description: &str) -> Value {
+    json!({
+        "name": name,
+        "in": "header",
+        "required": true,
+        "description": description,
+        "schema": { "type": "string" },
+    })
+}
+
+/// The OpenAPI 3 document for the public API, served at `/openapi.json`
+pub fn spec() -> Value {
+    let mut doc = Document::new();
+
+    // Clients, for the default tenant or the tenant found by `TENANT_RESOLVERS`
+    doc.operation("post", "/v1/clients", "Register a client", Auth::Client)
+        .resolved_tenant()
+        .body::<RegisterBody>()
+        .response::<()>();
+    doc.operation("get", "/v1/clients", "List clients", Auth::Tenant)
+        .query::<ListClientsQuery>()
+        .response::<ListClientsResponse>();
+    doc.operation("get", "/v1/clients/{id}", "Get a client", Auth::Tenant)
+        .response::<ClientResponse>();
+    doc.operation(
+        "post",
+        "/v1/clients/{id}",
+        "Push a message to a client",
+        Auth::Signature,
+    )
+    .resolved_tenant()
+    .body::<PushMessageBody>()
+    .status(PUSH_ACCEPTED_STATUS)
+    .response::<()>();
+    doc.operation(
+        "delete",
+        "/v1/clients/{id}",
+        "Delete a client",
+        Auth::Client,
+    )
+    .resolved_tenant()
+    .response::<()>();
+    doc.operation(
+        "delete",
+        "/v1/clients/{id}/devices/{device_id}",
+        "Delete one of a client's devices",
+        Auth::Client,
+    )
+    .response::<()>();
+    doc.operation(
+        "get",
+        "/v1/notifications/{id}",
+        "Get a notification's delivery history",
+        Auth::Tenant,
+    )
+    .response::<NotificationHistoryResponse>();
+
+    // Clients of a hosted tenant
+    doc.operation(
+        "post",
+        "/v1/{tenant_id}/clients",
+        "Register a client",
+        Auth::Client,
+    )
+    .body::<RegisterBody>()
+    .response::<()>();
+    doc.operation(
+        "get",
+        "/v1/{tenant_id}/clients",
+        "List clients",
+        Auth::Tenant,
+    )
+    .query::<ListClientsQuery>()
+    .response::<ListClientsResponse>();
+    doc.operation(
+        "get",
+        "/v1/{tenant_id}/clients/{id}",
+        "Get a client",
+        Auth::Tenant,
+    )
+    .response::<ClientResponse>();
+    doc.operation(
+        "post",
+        "/v1/{tenant_id}/clients/{id}",
+        "Push a message to a client",
+        Auth::Signature,
+    )
+    .body::<PushMessageBody>()
+    .status(PUSH_ACCEPTED_STATUS)
+    .response::<()>();
+    doc.operation(
+        "delete",
+        "/v1/{tenant_id}/clients/{id}",
+        "Delete a client",
+        Auth::Client,
+    )
+    .response::<()>();
+    doc.operation(
+        "delete",
+        "/v1/{tenant_id}/clients/{id}/devices/{device_id}",
+        "Delete one of a client's devices",
+        Auth::Client,
+    )
+    .response::<()>();
+    doc.operation(
+        "get",
+        "/v1/{tenant_id}/notifications/{id}",
+        "Get a notification's delivery history",
+        Auth::Tenant,
+    )
+    .response::<NotificationHistoryResponse>();
+
+    // Tenants
+    doc.operation("post", "/v1/tenants", "Create a tenant", Auth::Tenant)
+        .body::<TenantRegisterBody>()
+        .response::<TenantRegisterResponse>();
+    doc.operation("get", "/v1/tenants", "List tenants", Auth::Tenant)
+        .query::<ListTenantsQuery>()
+        .response::<ListTenantsResponse>();
+    doc.operation("get", "/v1/tenants/{id}", "Get a tenant", Auth::None)
+        .response::<GetTenantResponse>();
+    doc.operation(
+        "delete",
+        "/v1/tenants/{id}",
+        "Delete a tenant",
+        Auth::Tenant,
+    )
+    .response::<()>();
+    doc.operation(
+        "post",
+        "/v1/tenants/{id}/fcm",
+        "Set a tenant's FCM credentials",
+        Auth::Tenant,
+    )
+    .json_or_multipart_body::<FcmUpdateBody>()
+    .response::<()>();
+    doc.operation(
+        "delete",
+        "/v1/tenants/{id}/fcm",
+        "Remove a tenant's FCM credentials",
+        Auth::Tenant,
+    )
+    .response::<()>();
+    doc.operation(
+        "post",
+        "/v1/tenants/{id}/apns",
+        "Set a tenant's APNS credentials",
+        Auth::Tenant,
+    )
+    .json_or_multipart_body::<ApnsUpdateBody>()
+    .response::<()>();
+    doc.operation(
+        "delete",
+        "/v1/tenants/{id}/apns",
+        "Remove a tenant's APNS credentials",
+        Auth::Tenant,
+    )
+    .response::<()>();
+    doc.operation(
+        "post",
+        "/v1/tenants/{id}/restore",
+        "Restore a deleted tenant",
+        Auth::Tenant,
+    )
+    .response::<()>();
+    doc.operation(
+        "post",
+        "/v1/tenants/{id}/rename",
+        "Rename a tenant",
+        Auth::Tenant,
+    )
+    .body::<TenantRenameBody>()
+    .response::<TenantRenameResponse>();
+    doc.operation(
+        "get",
+        "/v1/tenants/{id}/usage",
+        "Get a tenant's usage",
+        Auth::Tenant,
+    )
+    .query::<UsageQuery>()
+    .response::<UsageResponse>();
+    doc.operation(
+        "post",
+        "/v1/tenants/{id}/quota",
+        "Set a tenant's monthly quota",
+        Auth::Tenant,
+    )
+    .body::<QuotaUpdateBody>()
+    .response::<()>();
+    doc.operation(
+        "post",
+        "/v1/tenants/{id}/rate-limit",
+        "Set a tenant's push rate limit",
+        Auth::Tenant,
+    )
+    .body::<RateLimitUpdateBody>()
+    .response::<()>();
+    doc.operation(
+        "get",
+        "/v1/tenants/{id}/audit",
+        "List a tenant's audit log",
+        Auth::Tenant,
+    )
+    .query::<AuditLogQuery>()
+    .response::<AuditLogResponse>();
+    doc.operation(
+        "post",
+        "/v1/tenants/{id}/webhook",
+        "Set a tenant's webhook",
+        Auth::Tenant,
+    )
+    .body::<WebhookUpdateBody>()
+    .response::<()>();
+    doc.operation(
+        "delete",
+        "/v1/tenants/{id}/webhook",
+        "Remove a tenant's webhook",
+        Auth::Tenant,
+    )
+    .response::<()>();
+    doc.operation(
+        "get",
+        "/v1/tenants/{id}/webhook/deliveries",
+        "List a tenant's webhook deliveries",
+        Auth::Tenant,
+    )
+    .query::<WebhookDeliveriesQuery>()
+    .response::<WebhookDeliveriesResponse>();
+
+    doc.into_value()
+}
@@ -1,6 +1,7 @@
 use {
     crate::{
         blob::DecryptedPayloadBlob,
+        error::Error::FcmMessage,
         handlers::push_message::MessagePayload,
         providers::PushProvider,
     },
@@ -41,7 +42,8 @@ impl PushProvider for FcmProvider {
 
             let fcm_message = message_builder.finalize();
 
-            let _ = self.client.send(fcm_message).await?;
+            let response = self.client.send(fcm_message).await?;
+            check_response(&response)?;
         } else {
             let blob = DecryptedPayloadBlob::from_base64_encoded(payload.blob)?;
 
@@ -54,13 +56,31 @@ impl PushProvider for FcmProvider {
 
             let fcm_message = message_builder.finalize();
 
-            let _ = self.client.send(fcm_message).await?;
+            let response = self.client.send(fcm_message).await?;
+            check_response(&response)?;
         }
 
         Ok(())
     }
 }
 
+/// FCM reports failures for individual messages, such as an unregistered
+/// token, in the body of a successful response
+fn check_response(response: &fcm::FcmResponse) -> crate::error::Result<()> {
+    let error = response.error.or_else(|| {
+        response
+            .results
+            .iter()
+            .flatten()
+            .find_map(|result| result.error)
+    });
+
+    match error {
+        Some(reason) => Err(FcmMessage(reason)),
+        None => Ok(()),
+    }
+}
+
 // Manual Impl Because `fcm::Client` does not derive anything and doesn't need
 // to be accounted for
 
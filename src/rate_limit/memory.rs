@@ -0,0 +1,129 @@
+use {
+    crate::rate_limit::{RateLimit, RateLimiter},
+    async_trait::async_trait,
+    std::{
+        collections::{BTreeMap, HashMap},
+        sync::Mutex,
+        time::{Duration, Instant},
+    },
+};
+
+/// Number of buckets kept, the least recently used is dropped to make room for
+/// a new one
+const MAX_BUCKETS: usize = 100_000;
+
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+    /// Position in the LRU order
+    last_used: u64,
+}
+
+impl Bucket {
+    fn refill(&mut self, limit: RateLimit, now: Instant) {
+        let elapsed = now.duration_since(self.updated_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * refill_rate(limit)).min(limit.capacity as f64);
+        self.updated_at = now;
+    }
+}
+
+#[derive(Default)]
+struct Buckets {
+    entries: HashMap<String, Bucket>,
+    /// Keys by when they were last used, the first is evicted when full
+    lru: BTreeMap<u64, String>,
+    tick: u64,
+}
+
+impl Buckets {
+    /// The bucket for `key`, created full if there isn't one, marked as the
+    /// most recently used
+    fn get_or_insert(
+        &mut self,
+        key: &str,
+        limit: RateLimit,
+        now: Instant,
+        capacity: usize,
+    ) -> &mut Bucket {
+        self.tick += 1;
+        let tick = self.tick;
+
+        match self.entries.get_mut(key) {
+            Some(bucket) => {
+                self.lru.remove(&bucket.last_used);
+                bucket.last_used = tick;
+            }
+            None => {
+                while self.entries.len() >= capacity {
+                    match self.lru.pop_first() {
+                        Some((_, key)) => self.entries.remove(&key),
+                        None => break,
+                    };
+                }
+                self.entries.insert(key.to_string(), Bucket {
+                    tokens: limit.capacity as f64,
+                    updated_at: now,
+                    last_used: tick,
+                });
+            }
+        }
+        self.lru.insert(tick, key.to_string());
+
+        self.entries.get_mut(key).expect("bucket was just inserted")
+    }
+}
+
+/// Tokens added to a bucket per second
+fn refill_rate(limit: RateLimit) -> f64 {
+    limit.capacity as f64 / limit.period.as_secs_f64()
+}
+
+/// Keeps buckets in memory, limits are therefore per instance of Echo Server
+pub struct InMemoryRateLimiter {
+    buckets: Mutex<Buckets>,
+    capacity: usize,
+}
+
+impl InMemoryRateLimiter {
+    pub fn new() -> Self {
+        Self::with_capacity(MAX_BUCKETS)
+    }
+
+    /// Keeps at most `capacity` buckets, evicting the least recently used
+    pub fn with_capacity(capacity: usize) -> Self {
+        InMemoryRateLimiter {
+            buckets: Default::default(),
+            capacity,
+        }
+    }
+}
+
+impl Default for InMemoryRateLimiter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl RateLimiter for InMemoryRateLimiter {
+    async fn check(&self, key: &str, limit: RateLimit) -> Result<(), Duration> {
+        // An empty bucket is never refilled, so nothing is ever allowed
+        if limit.capacity == 0 {
+            return Err(limit.period);
+        }
+
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+        let bucket = buckets.get_or_insert(key, limit, now, self.capacity);
+        bucket.refill(limit, now);
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return Ok(());
+        }
+
+        Err(Duration::from_secs_f64(
+            (1.0 - bucket.tokens) / refill_rate(limit),
+        ))
+    }
+}
@@ -0,0 +1,158 @@
+use {
+    crate::{
+        error::{Error::RateLimited, Result},
+        log::prelude::*,
+        state::AppState,
+        stores::tenant::Tenant,
+    },
+    async_trait::async_trait,
+    axum::http::HeaderMap,
+    opentelemetry::{Context, KeyValue},
+    std::{net::IpAddr, time::Duration},
+};
+
+const FORWARDED_FOR_HEADER_NAME: &str = "X-Forwarded-For";
+
+pub mod memory;
+
+/// A token bucket allowing `capacity` requests at once, refilled at a steady
+/// rate so `capacity` requests are allowed every `period`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RateLimit {
+    pub capacity: u32,
+    pub period: Duration,
+}
+
+impl RateLimit {
+    pub fn per_second(capacity: u32) -> Self {
+        RateLimit {
+            capacity,
+            period: Duration::from_secs(1),
+        }
+    }
+
+    pub fn per_minute(capacity: u32) -> Self {
+        RateLimit {
+            capacity,
+            period: Duration::from_secs(60),
+        }
+    }
+}
+
+/// The limits Echo Server enforces, used to pick the configured `RateLimit`
+/// and to label rejections in metrics
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RateLimitKind {
+    /// Pushes per tenant, protects a tenant's provider credentials
+    TenantPush,
+    /// Pushes per client, protects a wallet from being spammed
+    ClientPush,
+    /// Registrations per IP address
+    IpRegistration,
+}
+
+impl RateLimitKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RateLimitKind::TenantPush => "tenant_push",
+            RateLimitKind::ClientPush => "client_push",
+            RateLimitKind::IpRegistration => "ip_registration",
+        }
+    }
+
+    fn limit(&self, state: &AppState) -> Option<RateLimit> {
+        let config = &state.config;
+        match self {
+            RateLimitKind::TenantPush => config
+                .tenant_push_rate_limit_per_second
+                .map(RateLimit::per_second),
+            RateLimitKind::ClientPush => config
+                .client_push_rate_limit_per_minute
+                .map(RateLimit::per_minute),
+            RateLimitKind::IpRegistration => config
+                .ip_registration_rate_limit_per_minute
+                .map(RateLimit::per_minute),
+        }
+    }
+}
+
+#[async_trait]
+pub trait RateLimiter {
+    /// Takes a token from the bucket for `key`, when the bucket is empty the
+    /// time until a token is available is returned as the error
+    async fn check(&self, key: &str, limit: RateLimit) -> std::result::Result<(), Duration>;
+}
+
+/// Checks the configured limit of `kind` for `key`, does nothing if that limit
+/// isn't configured
+pub async fn check(state: &AppState, kind: RateLimitKind, key: &str) -> Result<()> {
+    check_limit(state, kind, key, kind.limit(state)).await
+}
+
+/// Checks the tenant's push limit, which is the tenant's own limit when an
+/// operator has set one and the configured limit otherwise
+pub async fn check_tenant_push(state: &AppState, tenant: &Tenant) -> Result<()> {
+    let limit = tenant
+        .push_rate_limit_per_second
+        .and_then(|limit| u32::try_from(limit).ok())
+        .map(RateLimit::per_second)
+        .or_else(|| RateLimitKind::TenantPush.limit(state));
+
+    check_limit(state, RateLimitKind::TenantPush, &tenant.id, limit).await
+}
+
+/// The address of the client making the request. Behind proxies the
+/// connection is from the closest proxy, so with `TRUSTED_PROXY_COUNT` set the
+/// address is taken from `X-Forwarded-For` instead. Each proxy appends the
+/// address it received the request from, so only the entries added by the
+/// trusted proxies are used as anything before them can be spoofed
+pub fn client_ip(headers: &HeaderMap, connection_ip: IpAddr, trusted_proxy_count: usize) -> IpAddr {
+    if trusted_proxy_count == 0 {
+        return connection_ip;
+    }
+
+    let forwarded_for = headers
+        .get_all(FORWARDED_FOR_HEADER_NAME)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .collect::<Vec<_>>();
+
+    forwarded_for
+        .len()
+        .checked_sub(trusted_proxy_count)
+        .and_then(|index| forwarded_for.get(index))
+        .and_then(|ip| ip.parse().ok())
+        .unwrap_or(connection_ip)
+}
+
+async fn check_limit(
+    state: &AppState,
+    kind: RateLimitKind,
+    key: &str,
+    limit: Option<RateLimit>,
+) -> Result<()> {
+    let limit = match limit {
+        Some(limit) => limit,
+        None => return Ok(()),
+    };
+
+    let key = format!("{}:{}", kind.as_str(), key);
+    if let Err(retry_after) = state.rate_limiter.check(&key, limit).await {
+        info!("rate limited ({}), retry after {:?}", key, retry_after);
+
+        if let Some(metrics) = &state.metrics {
+            metrics
+                .rate_limited_requests
+                .add(&Context::current(), 1, &[KeyValue::new(
+                    "limit",
+                    kind.as_str(),
+                )]);
+        }
+
+        return Err(RateLimited(retry_after));
+    }
+
+    Ok(())
+}
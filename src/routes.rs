@@ -0,0 +1,165 @@
+use {
+    crate::{handlers, state::AppState},
+    axum::{
+        handler::Handler,
+        http::Method,
+        routing::{on, MethodFilter},
+        Router,
+    },
+    std::sync::Arc,
+};
+
+/// A `Router` that records the method and path of each route it's given, so the
+/// OpenAPI document can be checked against the routes that are actually served
+pub struct Routes {
+    router: Router<Arc<AppState>>,
+    registered: Vec<(Method, String)>,
+}
+
+impl Routes {
+    fn new() -> Self {
+        Routes {
+            router: Router::new(),
+            registered: vec![],
+        }
+    }
+
+    fn get<H, T>(self, path: &str, handler: H) -> Self
+    where
+        H: Handler<T, Arc<AppState>>,
+        T: 'static,
+    {
+        self.on(Method::GET, path, handler)
+    }
+
+    fn post<H, T>(self, path: &str, handler: H) -> Self
+    where
+        H: Handler<T, Arc<AppState>>,
+        T: 'static,
+    {
+        self.on(Method::POST, path, handler)
+    }
+
+    fn delete<H, T>(self, path: &str, handler: H) -> Self
+    where
+        H: Handler<T, Arc<AppState>>,
+        T: 'static,
+    {
+        self.on(Method::DELETE, path, handler)
+    }
+
+    fn on<H, T>(mut self, method: Method, path: &str, handler: H) -> Self
+    where
+        H: Handler<T, Arc<AppState>>,
+        T: 'static,
+    {
+        let filter =
+            MethodFilter::try_from(method.clone()).expect("Routes only use standard methods");
+        // Routes with the same path are merged, so each method can be added on its own
+        self.router = self.router.route(path, on(filter, handler));
+        self.registered.push((method, path.to_string()));
+        self
+    }
+
+    fn nest(mut self, prefix: &str, routes: Routes) -> Self {
+        self.router = self.router.nest(prefix, routes.router);
+        self.registered
+            .extend(
+                routes
+                    .registered
+                    .into_iter()
+                    .map(|(method, path)| match path.as_str() {
+                        "/" => (method, prefix.to_string()),
+                        _ => (method, format!("{prefix}{path}")),
+                    }),
+            );
+        self
+    }
+
+    fn merge(mut self, routes: Routes) -> Self {
+        self.router = self.router.merge(routes.router);
+        self.registered.extend(routes.registered);
+        self
+    }
+
+    /// The method and path of every route, paths use axum's `:param` syntax
+    pub fn registered(&self) -> &[(Method, String)] {
+        &self.registered
+    }
+
+    pub fn into_router(self) -> Router<Arc<AppState>> {
+        self.router
+    }
+}
+
+/// The routes served under `/v1` and as the deprecated unversioned aliases,
+/// which of them are served depends on the tenancy mode
+pub fn v1(is_multitenant: bool, is_hybrid: bool) -> Routes {
+    let tenancy_routes = Routes::new()
+        .post("/", handlers::create_tenant::handler)
+        .get("/", handlers::list_tenants::handler)
+        .get("/:id", handlers::get_tenant::handler)
+        .delete("/:id", handlers::delete_tenant::handler)
+        .post("/:id/fcm", handlers::update_fcm::handler)
+        .delete("/:id/fcm", handlers::remove_fcm::handler)
+        .post("/:id/apns", handlers::update_apns::handler)
+        .delete("/:id/apns", handlers::remove_apns::handler)
+        .post("/:id/restore", handlers::restore_tenant::handler)
+        .post("/:id/rename", handlers::rename_tenant::handler)
+        .get("/:id/usage", handlers::get_usage::handler)
+        .post("/:id/quota", handlers::update_quota::handler)
+        .post("/:id/rate-limit", handlers::update_rate_limit::handler)
+        .get("/:id/audit", handlers::get_audit_log::handler)
+        .post("/:id/webhook", handlers::update_webhook::handler)
+        .delete("/:id/webhook", handlers::remove_webhook::handler)
+        .get(
+            "/:id/webhook/deliveries",
+            handlers::list_webhook_deliveries::handler,
+        );
+
+    // The tenant is found by `ResolvedTenant`, falling back to the default tenant
+    // in single-tenant and hybrid modes
+    let resolved_tenant_routes = Routes::new()
+        .post("/clients", handlers::register_client::handler)
+        .post("/clients/:id", handlers::push_message::handler)
+        .delete("/clients/:id", handlers::delete_client::handler);
+
+    let single_tenant_routes = Routes::new()
+        .get("/clients", handlers::single_tenant_wrappers::list_handler)
+        .get(
+            "/clients/:id",
+            handlers::single_tenant_wrappers::get_handler,
+        )
+        .delete(
+            "/clients/:id/devices/:device_id",
+            handlers::single_tenant_wrappers::delete_device_handler,
+        )
+        .get(
+            "/notifications/:id",
+            handlers::single_tenant_wrappers::get_notification_handler,
+        );
+
+    let multi_tenant_routes = Routes::new()
+        .nest("/tenants", tenancy_routes)
+        .post("/:tenant_id/clients", handlers::register_client::handler)
+        .get("/:tenant_id/clients", handlers::list_clients::handler)
+        .delete("/:tenant_id/clients/:id", handlers::delete_client::handler)
+        .get("/:tenant_id/clients/:id", handlers::get_client::handler)
+        .post("/:tenant_id/clients/:id", handlers::push_message::handler)
+        .delete(
+            "/:tenant_id/clients/:id/devices/:device_id",
+            handlers::delete_device::handler,
+        )
+        .get(
+            "/:tenant_id/notifications/:id",
+            handlers::get_notification::handler,
+        );
+
+    // Hybrid mode serves the default tenant's routes alongside the hosted tenants'
+    match (is_multitenant, is_hybrid) {
+        (false, _) => single_tenant_routes,
+        (true, false) => multi_tenant_routes,
+        (true, true) => multi_tenant_routes.merge(single_tenant_routes),
+    }
+    .merge(resolved_tenant_routes)
+}
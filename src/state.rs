@@ -2,8 +2,15 @@ use {
     crate::{
         config::Config,
         metrics::Metrics,
+        rate_limit::{memory::InMemoryRateLimiter, RateLimiter},
         relay::RelayClient,
-        stores::{client::ClientStore, notification::NotificationStore, tenant::TenantStore},
+        stores::{
+            client::ClientStore,
+            notification::NotificationStore,
+            tenant::TenantStore,
+            usage::UsageStore,
+        },
+        webhooks,
     },
     build_info::BuildInfo,
     std::sync::Arc,
@@ -12,6 +19,8 @@ use {
 pub type ClientStoreArc = Arc<dyn ClientStore + Send + Sync + 'static>;
 pub type NotificationStoreArc = Arc<dyn NotificationStore + Send + Sync + 'static>;
 pub type TenantStoreArc = Arc<dyn TenantStore + Send + Sync + 'static>;
+pub type UsageStoreArc = Arc<dyn UsageStore + Send + Sync + 'static>;
+pub type RateLimiterArc = Arc<dyn RateLimiter + Send + Sync + 'static>;
 
 pub trait State {
     fn config(&self) -> Config;
@@ -19,9 +28,12 @@ pub trait State {
     fn client_store(&self) -> ClientStoreArc;
     fn notification_store(&self) -> NotificationStoreArc;
     fn tenant_store(&self) -> TenantStoreArc;
+    fn usage_store(&self) -> UsageStoreArc;
     fn relay_client(&self) -> RelayClient;
     fn is_multitenant(&self) -> bool;
+    fn is_hybrid(&self) -> bool;
     fn validate_signatures(&self) -> bool;
+    fn validate_client_signatures(&self) -> bool;
 }
 
 #[derive(Clone)]
@@ -32,8 +44,13 @@ pub struct AppState {
     pub client_store: ClientStoreArc,
     pub notification_store: NotificationStoreArc,
     pub tenant_store: TenantStoreArc,
+    pub usage_store: UsageStoreArc,
     pub relay_client: RelayClient,
+    pub rate_limiter: RateLimiterArc,
+    /// Used to deliver tenant webhooks
+    pub webhook_client: reqwest::Client,
     is_multitenant: bool,
+    is_hybrid: bool,
 }
 
 build_info::build_info!(fn build_info);
@@ -43,11 +60,14 @@ pub fn new_state(
     client_store: ClientStoreArc,
     notification_store: NotificationStoreArc,
     tenant_store: TenantStoreArc,
+    usage_store: UsageStoreArc,
 ) -> crate::error::Result<AppState> {
     let build_info: &BuildInfo = build_info();
 
     let is_multitenant = config.tenant_database_url.is_some();
+    let is_hybrid = config.is_hybrid();
     let relay_url = config.relay_url.to_string();
+    let webhook_client = webhooks::client(&config)?;
 
     Ok(AppState {
         config,
@@ -56,8 +76,12 @@ pub fn new_state(
         client_store,
         notification_store,
         tenant_store,
+        usage_store,
         relay_client: RelayClient::new(relay_url),
+        rate_limiter: Arc::new(InMemoryRateLimiter::new()),
+        webhook_client,
         is_multitenant,
+        is_hybrid,
     })
 }
 
@@ -65,6 +89,10 @@ impl AppState {
     pub fn set_metrics(&mut self, metrics: Metrics) {
         self.metrics = Some(metrics);
     }
+
+    pub fn set_rate_limiter(&mut self, rate_limiter: RateLimiterArc) {
+        self.rate_limiter = rate_limiter;
+    }
 }
 
 impl State for Arc<AppState> {
@@ -88,6 +116,10 @@ impl State for Arc<AppState> {
         self.tenant_store.clone()
     }
 
+    fn usage_store(&self) -> UsageStoreArc {
+        self.usage_store.clone()
+    }
+
     fn relay_client(&self) -> RelayClient {
         self.relay_client.clone()
     }
@@ -96,7 +128,15 @@ impl State for Arc<AppState> {
         self.is_multitenant
     }
 
+    fn is_hybrid(&self) -> bool {
+        self.is_hybrid
+    }
+
     fn validate_signatures(&self) -> bool {
         self.config.validate_signatures
     }
+
+    fn validate_client_signatures(&self) -> bool {
+        self.config.validate_client_signatures
+    }
 }
@@ -1,87 +1,515 @@
 use {
     crate::{
+        error::{self, Error::FieldTooLong},
         providers::ProviderKind,
         stores::{self, StoreError::NotFound},
     },
     async_trait::async_trait,
-    sqlx::Executor,
+    chrono::{DateTime, Utc},
+    schemars::JsonSchema,
+    serde::{Deserialize, Serialize},
+    sqlx::{Executor, PgPool},
+    std::collections::HashMap,
 };
 
-#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow)]
+/// Device ID used when a client registers without specifying one, this keeps
+/// the previous one-device-per-client behaviour for older SDKs
+pub const DEFAULT_DEVICE_ID: &str = "default";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Client {
+    pub id: String,
+    pub tenant_id: String,
+
+    pub devices: Vec<Device>,
+
+    pub created_at: DateTime<Utc>,
+    /// Updated whenever the client re-registers
+    pub last_seen_at: DateTime<Utc>,
+    /// Updated whenever a notification is delivered to one of its devices
+    pub last_push_success_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow)]
+pub struct Device {
+    pub id: String,
     pub push_type: ProviderKind,
     #[sqlx(rename = "device_token")]
     pub token: String,
+    #[sqlx(flatten)]
+    pub metadata: DeviceMetadata,
+}
+
+/// Optional details reported by the SDK when registering a device
+#[derive(
+    Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq, sqlx::FromRow, JsonSchema,
+)]
+pub struct DeviceMetadata {
+    /// Operating system and version e.g. `iOS 16.3`
+    pub os: Option<String>,
+    /// The wallet's bundle or package ID
+    pub bundle_id: Option<String>,
+    pub app_version: Option<String>,
+    /// e.g. `swift`, `kotlin`, `web`
+    pub sdk_name: Option<String>,
+    pub sdk_version: Option<String>,
+    /// BCP 47 language tag e.g. `en-GB`
+    pub locale: Option<String>,
+    /// IANA time zone e.g. `Europe/London`
+    pub timezone: Option<String>,
+}
+
+/// The longest value accepted for any of the `DeviceMetadata` fields
+pub const DEVICE_METADATA_MAX_LENGTH: usize = 128;
+
+/// SDK names reported as themselves in metrics, anything else is `other`
+const KNOWN_SDK_NAMES: [&str; 6] = ["swift", "kotlin", "web", "flutter", "react-native", "unity"];
+
+impl DeviceMetadata {
+    pub fn validate(&self) -> error::Result<()> {
+        let fields = [
+            ("os", &self.os),
+            ("bundle_id", &self.bundle_id),
+            ("app_version", &self.app_version),
+            ("sdk_name", &self.sdk_name),
+            ("sdk_version", &self.sdk_version),
+            ("locale", &self.locale),
+            ("timezone", &self.timezone),
+        ];
+
+        match fields
+            .into_iter()
+            .find(|(_, value)| value.as_ref().map(String::len) > Some(DEVICE_METADATA_MAX_LENGTH))
+        {
+            Some((field, _)) => Err(FieldTooLong(
+                format!("metadata.{field}"),
+                DEVICE_METADATA_MAX_LENGTH,
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// The SDK name as a metric label, unknown SDKs are grouped together to
+    /// keep the number of series bounded
+    pub fn sdk_label(&self) -> &'static str {
+        match self.sdk_name.as_deref().map(str::to_lowercase) {
+            None => "unknown",
+            Some(name) => KNOWN_SDK_NAMES
+                .into_iter()
+                .find(|known| *known == name)
+                .unwrap_or("other"),
+        }
+    }
+
+    /// The OS family without its version as a metric label, e.g. `ios` for
+    /// `iOS 16.3`
+    pub fn os_label(&self) -> &'static str {
+        let Some(os) = self.os.as_deref().map(str::to_lowercase) else {
+            return "unknown";
+        };
+
+        match os.split_whitespace().next().unwrap_or_default() {
+            "ios" | "ipados" => "ios",
+            "android" => "android",
+            "macos" => "macos",
+            "windows" => "windows",
+            "linux" => "linux",
+            _ => "other",
+        }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct DeviceRow {
+    client_id: String,
+    #[sqlx(flatten)]
+    device: Device,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClientListParams {
+    /// Only return clients with an ID after this one, used for pagination
+    pub after: Option<String>,
+    pub limit: i64,
+    /// Only return clients with at least one device using this provider
+    pub push_type: Option<ProviderKind>,
+    pub created_after: Option<DateTime<Utc>>,
+    pub created_before: Option<DateTime<Utc>>,
+    pub last_seen_after: Option<DateTime<Utc>>,
+    pub last_seen_before: Option<DateTime<Utc>>,
 }
 
 #[async_trait]
 pub trait ClientStore {
-    async fn create_client(&self, tenant_id: &str, id: &str, client: Client) -> stores::Result<()>;
+    /// Registers the device, creating the client if it doesn't exist. Returns
+    /// whether the client was created rather than already registered
+    async fn create_client(
+        &self,
+        tenant_id: &str,
+        id: &str,
+        device: Device,
+    ) -> stores::Result<bool>;
     async fn get_client(&self, tenant_id: &str, id: &str) -> stores::Result<Client>;
-    async fn delete_client(&self, tenant_id: &str, id: &str) -> stores::Result<()>;
+    async fn list_clients(
+        &self,
+        tenant_id: &str,
+        params: ClientListParams,
+    ) -> stores::Result<Vec<Client>>;
+    /// Returns whether the client existed and was deleted
+    async fn delete_client(&self, tenant_id: &str, id: &str) -> stores::Result<bool>;
+    async fn delete_device(&self, tenant_id: &str, id: &str, device_id: &str)
+        -> stores::Result<()>;
+    async fn update_last_push_success(&self, tenant_id: &str, id: &str) -> stores::Result<()>;
+    async fn count_clients(&self, tenant_id: &str) -> stores::Result<i64>;
+    /// Deletes all of a tenant's clients along with their devices and
+    /// notifications, returning how many clients were deleted
+    async fn delete_tenant_clients(&self, tenant_id: &str) -> stores::Result<u64>;
+    /// Moves all of a tenant's clients, along with their devices,
+    /// notifications and usage, to a new tenant ID in a single transaction,
+    /// returning how many clients were moved
+    async fn rename_tenant_clients(
+        &self,
+        tenant_id: &str,
+        new_tenant_id: &str,
+    ) -> stores::Result<u64>;
+    /// Counts the clients of each tenant, tenants without clients are omitted
+    async fn count_clients_by_tenant(
+        &self,
+        tenant_ids: &[String],
+    ) -> stores::Result<HashMap<String, i64>>;
+    /// Counts clients, across all tenants, that have not been seen or
+    /// successfully pushed to since `idle_since`
+    async fn count_stale_clients(&self, idle_since: DateTime<Utc>) -> stores::Result<i64>;
+    /// Deletes up to `limit` clients, across all tenants, that have not been
+    /// seen or successfully pushed to since `idle_since`, returning how many
+    /// were deleted
+    async fn delete_stale_clients(
+        &self,
+        idle_since: DateTime<Utc>,
+        limit: i64,
+    ) -> stores::Result<u64>;
 }
 
+/// Clients are stale when neither their last registration nor their last
+/// successful delivery is after the cutoff
+const STALE_CLIENT_CONDITION: &str =
+    "GREATEST(last_seen_at, COALESCE(last_push_success_at, last_seen_at)) < $1";
+
 #[async_trait]
 impl ClientStore for sqlx::PgPool {
-    async fn create_client(&self, tenant_id: &str, id: &str, client: Client) -> stores::Result<()> {
+    async fn create_client(
+        &self,
+        tenant_id: &str,
+        id: &str,
+        device: Device,
+    ) -> stores::Result<bool> {
+        let mut transaction = self.begin().await?;
+
+        let mut query_builder =
+            sqlx::QueryBuilder::new("INSERT INTO public.clients (id, tenant_id)");
+        query_builder.push_values(vec![(id, tenant_id)], |mut b, client| {
+            b.push_bind(client.0).push_bind(client.1);
+        });
+        // Clients are scoped to their tenant, the same ID registered by another tenant
+        // is a separate client
+        query_builder.push(" ON CONFLICT (tenant_id, id) DO UPDATE SET last_seen_at = now()");
+        // `xmax` is only set on rows that were updated rather than inserted
+        query_builder.push(" RETURNING (xmax = 0)");
+        let (created,) = query_builder
+            .build_query_as::<(bool,)>()
+            .fetch_one(&mut transaction)
+            .await?;
+
         let mut query_builder = sqlx::QueryBuilder::new(
-            "INSERT INTO public.clients (id, tenant_id, push_type, device_token)",
+            "INSERT INTO public.devices (id, client_id, tenant_id, push_type, device_token, os, \
+             bundle_id, app_version, sdk_name, sdk_version, locale, timezone)",
         );
         query_builder.push_values(
-            vec![(id, tenant_id, client.push_type, client.token)],
-            |mut b, client| {
-                b.push_bind(client.0)
-                    .push_bind(client.1)
-                    .push_bind(client.2)
-                    .push_bind(client.3);
+            vec![(id, tenant_id, device)],
+            |mut b, (id, tenant_id, d)| {
+                b.push_bind(d.id)
+                    .push_bind(id)
+                    .push_bind(tenant_id)
+                    .push_bind(d.push_type)
+                    .push_bind(d.token)
+                    .push_bind(d.metadata.os)
+                    .push_bind(d.metadata.bundle_id)
+                    .push_bind(d.metadata.app_version)
+                    .push_bind(d.metadata.sdk_name)
+                    .push_bind(d.metadata.sdk_version)
+                    .push_bind(d.metadata.locale)
+                    .push_bind(d.metadata.timezone);
             },
         );
         query_builder.push(
-            " ON CONFLICT (id) DO UPDATE SET device_token = EXCLUDED.device_token, tenant_id = \
-             EXCLUDED.tenant_id, push_type = EXCLUDED.push_type",
+            " ON CONFLICT (tenant_id, client_id, id) DO UPDATE SET device_token = \
+             EXCLUDED.device_token, push_type = EXCLUDED.push_type, os = EXCLUDED.os, bundle_id = \
+             EXCLUDED.bundle_id, app_version = EXCLUDED.app_version, sdk_name = \
+             EXCLUDED.sdk_name, sdk_version = EXCLUDED.sdk_version, locale = EXCLUDED.locale, \
+             timezone = EXCLUDED.timezone",
         );
         let query = query_builder.build();
 
-        self.execute(query).await?;
+        transaction.execute(query).await?;
 
-        Ok(())
+        transaction.commit().await?;
+
+        Ok(created)
     }
 
     async fn get_client(&self, tenant_id: &str, id: &str) -> stores::Result<Client> {
-        let res = sqlx::query_as::<sqlx::postgres::Postgres, Client>(
-            "SELECT push_type, device_token FROM public.clients WHERE id = $1 and tenant_id = $2",
+        let res = sqlx::query_as::<
+            sqlx::postgres::Postgres,
+            (DateTime<Utc>, DateTime<Utc>, Option<DateTime<Utc>>),
+        >(
+            "SELECT created_at, last_seen_at, last_push_success_at FROM public.clients WHERE id = \
+             $1 and tenant_id = $2",
         )
         .bind(id)
         .bind(tenant_id)
         .fetch_one(self)
         .await;
 
-        match res {
+        let (created_at, last_seen_at, last_push_success_at) = match res {
             Err(sqlx::Error::RowNotFound) => Err(NotFound("client".to_string(), id.to_string())),
             Err(e) => Err(e.into()),
             Ok(row) => Ok(row),
-        }
+        }?;
+
+        let mut devices = get_devices(self, tenant_id, &[id.to_string()]).await?;
+
+        Ok(Client {
+            id: id.to_string(),
+            tenant_id: tenant_id.to_string(),
+            devices: devices.remove(id).unwrap_or_default(),
+            created_at,
+            last_seen_at,
+            last_push_success_at,
+        })
     }
 
-    async fn delete_client(&self, tenant_id: &str, id: &str) -> stores::Result<()> {
-        let mut notification_query_builder =
-            sqlx::QueryBuilder::new("DELETE FROM public.notifications WHERE client_id = ");
-        notification_query_builder.push_bind(id);
-        notification_query_builder.push(" and tenant_id = ");
-        notification_query_builder.push_bind(tenant_id);
-        let notification_query = notification_query_builder.build();
+    async fn list_clients(
+        &self,
+        tenant_id: &str,
+        params: ClientListParams,
+    ) -> stores::Result<Vec<Client>> {
+        let mut query_builder = sqlx::QueryBuilder::new(
+            "SELECT c.id, c.created_at, c.last_seen_at, c.last_push_success_at FROM \
+             public.clients c WHERE c.tenant_id = ",
+        );
+        query_builder.push_bind(tenant_id);
+        if let Some(after) = params.after {
+            query_builder.push(" and c.id > ");
+            query_builder.push_bind(after);
+        }
+        if let Some(push_type) = params.push_type {
+            query_builder.push(
+                " and EXISTS (SELECT 1 FROM public.devices d WHERE d.tenant_id = c.tenant_id and \
+                 d.client_id = c.id and d.push_type = ",
+            );
+            query_builder.push_bind(push_type);
+            query_builder.push(")");
+        }
+        if let Some(created_after) = params.created_after {
+            query_builder.push(" and c.created_at >= ");
+            query_builder.push_bind(created_after);
+        }
+        if let Some(created_before) = params.created_before {
+            query_builder.push(" and c.created_at < ");
+            query_builder.push_bind(created_before);
+        }
+        if let Some(last_seen_after) = params.last_seen_after {
+            query_builder.push(" and c.last_seen_at >= ");
+            query_builder.push_bind(last_seen_after);
+        }
+        if let Some(last_seen_before) = params.last_seen_before {
+            query_builder.push(" and c.last_seen_at < ");
+            query_builder.push_bind(last_seen_before);
+        }
+        query_builder.push(" ORDER BY c.id LIMIT ");
+        query_builder.push_bind(params.limit);
+
+        let rows = query_builder
+            .build_query_as::<(String, DateTime<Utc>, DateTime<Utc>, Option<DateTime<Utc>>)>()
+            .fetch_all(self)
+            .await?;
 
-        self.execute(notification_query).await?;
+        let ids = rows.iter().map(|row| row.0.clone()).collect::<Vec<_>>();
+        let mut devices = get_devices(self, tenant_id, &ids).await?;
 
+        Ok(rows
+            .into_iter()
+            .map(
+                |(id, created_at, last_seen_at, last_push_success_at)| Client {
+                    devices: devices.remove(&id).unwrap_or_default(),
+                    id,
+                    tenant_id: tenant_id.to_string(),
+                    created_at,
+                    last_seen_at,
+                    last_push_success_at,
+                },
+            )
+            .collect())
+    }
+
+    async fn delete_client(&self, tenant_id: &str, id: &str) -> stores::Result<bool> {
+        // Devices and notifications are removed by their `ON DELETE CASCADE`
         let mut query_builder = sqlx::QueryBuilder::new("DELETE FROM public.clients WHERE id = ");
         query_builder.push_bind(id);
         query_builder.push(" and tenant_id = ");
         query_builder.push_bind(tenant_id);
         let query = query_builder.build();
 
-        match self.execute(query).await {
-            Ok(_) => Ok(()),
-            Err(e) => Err(e.into()),
+        Ok(self.execute(query).await?.rows_affected() > 0)
+    }
+
+    async fn delete_device(
+        &self,
+        tenant_id: &str,
+        id: &str,
+        device_id: &str,
+    ) -> stores::Result<()> {
+        let mut query_builder =
+            sqlx::QueryBuilder::new("DELETE FROM public.devices WHERE client_id = ");
+        query_builder.push_bind(id);
+        query_builder.push(" and tenant_id = ");
+        query_builder.push_bind(tenant_id);
+        query_builder.push(" and id = ");
+        query_builder.push_bind(device_id);
+        let query = query_builder.build();
+
+        if self.execute(query).await?.rows_affected() == 0 {
+            return Err(NotFound("device".to_string(), device_id.to_string()));
         }
+
+        Ok(())
+    }
+
+    async fn update_last_push_success(&self, tenant_id: &str, id: &str) -> stores::Result<()> {
+        sqlx::query(
+            "UPDATE public.clients SET last_push_success_at = now() WHERE id = $1 and tenant_id = \
+             $2",
+        )
+        .bind(id)
+        .bind(tenant_id)
+        .execute(self)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn count_clients(&self, tenant_id: &str) -> stores::Result<i64> {
+        let (count,) = sqlx::query_as::<sqlx::postgres::Postgres, (i64,)>(
+            "SELECT count(*) FROM public.clients WHERE tenant_id = $1",
+        )
+        .bind(tenant_id)
+        .fetch_one(self)
+        .await?;
+
+        Ok(count)
     }
+
+    async fn delete_tenant_clients(&self, tenant_id: &str) -> stores::Result<u64> {
+        // Devices and notifications are removed by their `ON DELETE CASCADE`
+        let res = sqlx::query("DELETE FROM public.clients WHERE tenant_id = $1")
+            .bind(tenant_id)
+            .execute(self)
+            .await?;
+
+        Ok(res.rows_affected())
+    }
+
+    async fn rename_tenant_clients(
+        &self,
+        tenant_id: &str,
+        new_tenant_id: &str,
+    ) -> stores::Result<u64> {
+        let mut transaction = self.begin().await?;
+
+        // Devices and notifications follow through the `ON UPDATE CASCADE` on
+        // their foreign keys
+        let res = sqlx::query("UPDATE public.clients SET tenant_id = $2 WHERE tenant_id = $1")
+            .bind(tenant_id)
+            .bind(new_tenant_id)
+            .execute(&mut transaction)
+            .await?;
+
+        sqlx::query("UPDATE public.tenant_usage SET tenant_id = $2 WHERE tenant_id = $1")
+            .bind(tenant_id)
+            .bind(new_tenant_id)
+            .execute(&mut transaction)
+            .await?;
+
+        transaction.commit().await?;
+
+        Ok(res.rows_affected())
+    }
+
+    async fn count_clients_by_tenant(
+        &self,
+        tenant_ids: &[String],
+    ) -> stores::Result<HashMap<String, i64>> {
+        let rows = sqlx::query_as::<sqlx::postgres::Postgres, (String, i64)>(
+            "SELECT tenant_id, count(*) FROM public.clients WHERE tenant_id = ANY($1) GROUP BY \
+             tenant_id",
+        )
+        .bind(tenant_ids)
+        .fetch_all(self)
+        .await?;
+
+        Ok(rows.into_iter().collect())
+    }
+
+    async fn count_stale_clients(&self, idle_since: DateTime<Utc>) -> stores::Result<i64> {
+        let (count,) = sqlx::query_as::<sqlx::postgres::Postgres, (i64,)>(&format!(
+            "SELECT count(*) FROM public.clients WHERE {STALE_CLIENT_CONDITION}"
+        ))
+        .bind(idle_since)
+        .fetch_one(self)
+        .await?;
+
+        Ok(count)
+    }
+
+    async fn delete_stale_clients(
+        &self,
+        idle_since: DateTime<Utc>,
+        limit: i64,
+    ) -> stores::Result<u64> {
+        // Devices and notifications are removed by their `ON DELETE CASCADE`
+        let res = sqlx::query(&format!(
+            "DELETE FROM public.clients WHERE (tenant_id, id) IN (SELECT tenant_id, id FROM \
+             public.clients WHERE {STALE_CLIENT_CONDITION} LIMIT $2)"
+        ))
+        .bind(idle_since)
+        .bind(limit)
+        .execute(self)
+        .await?;
+
+        Ok(res.rows_affected())
+    }
+}
+
+/// Fetches the devices for each of the clients, keyed by client ID
+async fn get_devices(
+    pool: &PgPool,
+    tenant_id: &str,
+    client_ids: &[String],
+) -> stores::Result<HashMap<String, Vec<Device>>> {
+    let rows = sqlx::query_as::<sqlx::postgres::Postgres, DeviceRow>(
+        "SELECT client_id, id, push_type, device_token, os, bundle_id, app_version, sdk_name, \
+         sdk_version, locale, timezone FROM public.devices WHERE tenant_id = $1 and client_id = \
+         ANY($2) ORDER BY created_at",
+    )
+    .bind(tenant_id)
+    .bind(client_ids)
+    .fetch_all(pool)
+    .await?;
+
+    let mut devices: HashMap<String, Vec<Device>> = HashMap::new();
+    for row in rows {
+        devices.entry(row.client_id).or_default().push(row.device);
+    }
+
+    Ok(devices)
 }
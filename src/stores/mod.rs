@@ -1,6 +1,9 @@
 pub mod client;
 pub mod notification;
 pub mod tenant;
+pub mod tenant_cache;
+pub mod tenant_hybrid;
+pub mod usage;
 
 type Result<T> = std::result::Result<T, StoreError>;
 
@@ -12,4 +15,9 @@ pub enum StoreError {
     /// Not found error, params are entity name and identifier
     #[error("Cannot find {0} with specified identifier {1}")]
     NotFound(String, String),
+
+    /// The identifier is already used by another entity, params are entity
+    /// name and identifier
+    #[error("{0} with identifier {1} already exists")]
+    AlreadyExists(String, String),
 }
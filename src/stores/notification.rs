@@ -1,20 +1,58 @@
 use {
     crate::{
         handlers::push_message::MessagePayload,
-        stores::{self, StoreError::NotFound},
+        stores::{
+            self,
+            StoreError::{AlreadyExists, NotFound},
+        },
     },
     async_trait::async_trait,
     chrono::{DateTime, Utc},
+    schemars::JsonSchema,
+    serde::{Deserialize, Serialize},
     sqlx::{types::Json, Executor},
 };
 
+/// The outcome of the last attempt to deliver a notification
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq, sqlx::Type, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "notification_status")]
+#[sqlx(rename_all = "lowercase")]
+pub enum NotificationStatus {
+    /// Delivery hasn't finished, or the server stopped part way through
+    Pending,
+    /// Delivered to at least one of the client's devices
+    Delivered,
+    /// Delivery to every device failed, a retry will attempt it again
+    Failed,
+}
+
+/// A payload received for a notification, retries from the relay are recorded
+/// individually
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, JsonSchema)]
+pub struct ReceivedPayload {
+    pub payload: MessagePayload,
+    pub received_at: DateTime<Utc>,
+}
+
+/// How many of a notification's payloads are kept, older ones are dropped
+pub const MAX_PAYLOAD_HISTORY: i32 = 20;
+
+/// How long a claim on a notification lasts, after this it can be claimed
+/// again in case the server stopped part way through delivering it
+pub const NOTIFICATION_CLAIM_TIMEOUT_SECS: i64 = 5 * 60;
+
 #[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow)]
 pub struct Notification {
     pub id: String,
     pub client_id: String,
 
     pub last_payload: Json<MessagePayload>,
-    pub previous_payloads: Vec<Json<MessagePayload>>,
+    /// The last `MAX_PAYLOAD_HISTORY` payloads received for this
+    /// notification, oldest first
+    pub previous_payloads: Vec<Json<ReceivedPayload>>,
+
+    pub status: NotificationStatus,
 
     pub last_received_at: DateTime<Utc>,
     pub created_at: DateTime<Utc>,
@@ -22,6 +60,8 @@ pub struct Notification {
 
 #[async_trait]
 pub trait NotificationStore {
+    /// Records a payload for the notification, IDs are scoped to the tenant
+    /// and can't be reused for another client
     async fn create_or_update_notification(
         &self,
         id: &str,
@@ -31,6 +71,25 @@ pub trait NotificationStore {
     ) -> stores::Result<Notification>;
     async fn get_notification(&self, tenant_id: &str, id: &str) -> stores::Result<Notification>;
     async fn delete_notification(&self, tenant_id: &str, id: &str) -> stores::Result<()>;
+    /// Claims an undelivered notification for delivery, returning false if it
+    /// has been delivered or another request has already claimed it
+    async fn claim_notification(&self, tenant_id: &str, id: &str) -> stores::Result<bool>;
+    /// Records the outcome of a delivery and releases the claim on it
+    async fn update_notification_status(
+        &self,
+        tenant_id: &str,
+        id: &str,
+        status: NotificationStatus,
+    ) -> stores::Result<()>;
+    /// Deletes up to `limit` notifications last received before
+    /// `received_before`, returning how many were deleted
+    async fn delete_notifications_received_before(
+        &self,
+        received_before: DateTime<Utc>,
+        limit: i64,
+    ) -> stores::Result<u64>;
+    /// The size of the notifications table, including indexes, in bytes
+    async fn get_notifications_table_size(&self) -> stores::Result<i64>;
 }
 
 #[async_trait]
@@ -43,26 +102,34 @@ impl NotificationStore for sqlx::PgPool {
         payload: &MessagePayload,
     ) -> stores::Result<Notification> {
         let res = sqlx::query_as::<sqlx::postgres::Postgres, Notification>(
-            "INSERT INTO public.notifications (id, tenant_id, client_id, last_payload)
-VALUES ($1, $2, $3, $4)
-ON CONFLICT (id)
-    DO UPDATE SET last_received_at  = now()
+            "INSERT INTO public.notifications AS n (id, tenant_id, client_id, last_payload, \
+             previous_payloads)
+VALUES ($1, $2, $3, $4, ARRAY [jsonb_build_object('payload', $4::jsonb, 'received_at', now())])
+ON CONFLICT (tenant_id, id)
+    DO UPDATE SET last_payload      = EXCLUDED.last_payload,
+                  last_received_at  = now(),
+                  previous_payloads = (n.previous_payloads || EXCLUDED.previous_payloads)
+                      [greatest(cardinality(n.previous_payloads) + 2 - $5, 1):]
+    WHERE n.client_id = EXCLUDED.client_id
 RETURNING *;",
         )
         .bind(id)
         .bind(tenant_id)
         .bind(client_id)
         .bind(Json(payload))
-        .fetch_one(self)
+        .bind(MAX_PAYLOAD_HISTORY)
+        .fetch_optional(self)
         .await;
 
         match res {
+            // The conflicting notification belongs to another client
+            Ok(None) => Err(AlreadyExists("notification".to_string(), id.to_string())),
             Err(e) => Err(e.into()),
-            Ok(row) => Ok(row),
+            Ok(Some(row)) => Ok(row),
         }
     }
 
-    async fn get_notification(&self, id: &str, tenant_id: &str) -> stores::Result<Notification> {
+    async fn get_notification(&self, tenant_id: &str, id: &str) -> stores::Result<Notification> {
         let res = sqlx::query_as::<sqlx::postgres::Postgres, Notification>(
             "SELECT * FROM public.notifications WHERE id = $1 and tenant_id = $2",
         )
@@ -80,11 +147,11 @@ RETURNING *;",
         }
     }
 
-    async fn delete_notification(&self, id: &str, tenant_id: &str) -> stores::Result<()> {
+    async fn delete_notification(&self, tenant_id: &str, id: &str) -> stores::Result<()> {
         let mut query_builder =
             sqlx::QueryBuilder::new("DELETE FROM public.notifications WHERE id = ");
         query_builder.push_bind(id);
-        query_builder.push("and tenant_id = ");
+        query_builder.push(" and tenant_id = ");
         query_builder.push_bind(tenant_id);
         let query = query_builder.build();
 
@@ -92,4 +159,65 @@ RETURNING *;",
 
         Ok(())
     }
+
+    async fn claim_notification(&self, tenant_id: &str, id: &str) -> stores::Result<bool> {
+        let res = sqlx::query(
+            "UPDATE public.notifications SET claimed_at = now() WHERE id = $1 and tenant_id = $2 \
+             and status <> 'delivered' and (claimed_at IS NULL or claimed_at < now() - \
+             make_interval(secs => $3))",
+        )
+        .bind(id)
+        .bind(tenant_id)
+        .bind(NOTIFICATION_CLAIM_TIMEOUT_SECS as f64)
+        .execute(self)
+        .await?;
+
+        Ok(res.rows_affected() > 0)
+    }
+
+    async fn update_notification_status(
+        &self,
+        tenant_id: &str,
+        id: &str,
+        status: NotificationStatus,
+    ) -> stores::Result<()> {
+        sqlx::query(
+            "UPDATE public.notifications SET status = $1, claimed_at = NULL WHERE id = $2 and \
+             tenant_id = $3",
+        )
+        .bind(status)
+        .bind(id)
+        .bind(tenant_id)
+        .execute(self)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn delete_notifications_received_before(
+        &self,
+        received_before: DateTime<Utc>,
+        limit: i64,
+    ) -> stores::Result<u64> {
+        let res = sqlx::query(
+            "DELETE FROM public.notifications WHERE (tenant_id, id) IN (SELECT tenant_id, id FROM \
+             public.notifications WHERE last_received_at < $1 LIMIT $2)",
+        )
+        .bind(received_before)
+        .bind(limit)
+        .execute(self)
+        .await?;
+
+        Ok(res.rows_affected())
+    }
+
+    async fn get_notifications_table_size(&self) -> stores::Result<i64> {
+        let (size,) = sqlx::query_as::<sqlx::postgres::Postgres, (i64,)>(
+            "SELECT pg_total_relation_size('public.notifications')",
+        )
+        .fetch_one(self)
+        .await?;
+
+        Ok(size)
+    }
 }
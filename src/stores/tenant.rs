@@ -3,7 +3,7 @@ use {
         config::Config,
         error::{
             self,
-            Error::{InvalidTenantId, ProviderNotAvailable},
+            Error::{InvalidTenantId, ProviderNotAvailable, TenantIdTaken},
             Result,
         },
         providers::{
@@ -16,8 +16,10 @@ use {
     },
     async_trait::async_trait,
     base64::Engine as _,
-    chrono::{DateTime, Utc},
+    chrono::{DateTime, NaiveDate, Utc},
+    schemars::JsonSchema,
     serde::{Deserialize, Serialize},
+    serde_json::{json, Value},
     sqlx::{Executor, PgPool},
     std::{io::BufReader, sync::Arc},
 };
@@ -25,7 +27,7 @@ use {
 const APNS_TYPE_CERTIFICATE: &str = "certificate";
 const APNS_TYPE_TOKEN: &str = "token";
 
-#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq, sqlx::Type)]
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq, sqlx::Type, JsonSchema)]
 #[sqlx(type_name = "apns_type")]
 #[sqlx(rename_all = "lowercase")]
 pub enum ApnsType {
@@ -90,6 +92,29 @@ pub struct Tenant {
     pub apns_key_id: Option<String>,
     pub apns_team_id: Option<String>,
 
+    pub api_key_hash: Option<String>,
+    /// Identifies the tenant on client requests, it's publishable so grants
+    /// nothing else
+    pub client_key: Option<String>,
+
+    /// Deliveries allowed per calendar month, unlimited when unset
+    pub monthly_notification_quota: Option<i64>,
+    /// Pushes allowed per second, replaces `TENANT_PUSH_RATE_LIMIT_PER_SECOND`
+    /// for this tenant when set
+    pub push_rate_limit_per_second: Option<i32>,
+
+    /// Set when the tenant is deleted, it's purged once the grace period has
+    /// passed and can be restored until then
+    pub deleted_at: Option<DateTime<Utc>>,
+
+    /// The previous ID while the tenant's clients are still being moved over
+    /// from it after a rename
+    pub renamed_from: Option<String>,
+
+    /// Delivery failures are sent here, signed with the secret
+    pub webhook_url: Option<String>,
+    pub webhook_secret: Option<String>,
+
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -101,11 +126,77 @@ pub struct TenantUpdateParams {
     pub id: String,
 }
 
+pub const TENANT_ID_MIN_LENGTH: usize = 3;
+pub const TENANT_ID_MAX_LENGTH: usize = 64;
+/// IDs that would be confused with the routes tenants are served alongside
+pub const RESERVED_TENANT_IDS: [&str; 6] = [
+    "tenants",
+    "health",
+    "clients",
+    "notifications",
+    "metrics",
+    "v1",
+];
+
+/// Version prefixes like `v1` or `v2` are reserved for the versioned route
+/// trees, including those that don't exist yet
+fn is_version_prefix(id: &str) -> bool {
+    id.strip_prefix(['v', 'V'])
+        .map(|version| !version.is_empty() && version.chars().all(|c| c.is_ascii_digit()))
+        .unwrap_or_default()
+}
+
+impl TenantUpdateParams {
+    /// IDs must be between 3 and 64 ASCII letters, digits, `-` or `_`,
+    /// starting with a letter or digit, and can't be a reserved word
+    pub fn validate(&self) -> Result<()> {
+        let id = self.id.as_str();
+        let valid = (TENANT_ID_MIN_LENGTH..=TENANT_ID_MAX_LENGTH).contains(&id.len())
+            && id.starts_with(|c: char| c.is_ascii_alphanumeric())
+            && id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            && !RESERVED_TENANT_IDS
+                .iter()
+                .any(|reserved| reserved.eq_ignore_ascii_case(id))
+            && !is_version_prefix(id);
+
+        match valid {
+            true => Ok(()),
+            false => Err(InvalidTenantId(self.id.clone())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TenantListParams {
+    /// Only return tenants with an ID after this one, used for pagination
+    pub after: Option<String>,
+    pub limit: i64,
+    /// Only return tenants with this provider configured
+    pub provider: Option<ProviderKind>,
+    pub created_after: Option<DateTime<Utc>>,
+    pub created_before: Option<DateTime<Utc>>,
+}
+
+/// Matches the checks in `Tenant::get_apns_type`
+const APNS_CONFIGURED_CONDITION: &str =
+    "apns_topic IS NOT NULL and ((apns_type = 'certificate'::apns_type and apns_certificate IS \
+     NOT NULL and apns_certificate_password IS NOT NULL) or (apns_type = 'token'::apns_type and \
+     apns_pkcs8_pem IS NOT NULL and apns_key_id IS NOT NULL and apns_team_id IS NOT NULL))";
+
 #[derive(Debug, Eq, PartialEq, Clone)]
 pub struct TenantFcmUpdateParams {
     pub fcm_api_key: String,
 }
 
+impl TenantFcmUpdateParams {
+    /// The fields changed for the audit log
+    pub fn audit_changes(&self) -> Value {
+        json!({ "fcm_api_key": AUDIT_REDACTED })
+    }
+}
+
 pub enum TenantApnsUpdateAuth {
     Certificate {
         apns_certificate: String,
@@ -118,11 +209,155 @@ pub enum TenantApnsUpdateAuth {
     },
 }
 
+impl TenantApnsUpdateAuth {
+    /// The fields changed for the audit log, credentials are redacted
+    pub fn audit_changes(&self) -> Value {
+        match self {
+            TenantApnsUpdateAuth::Certificate { .. } => json!({
+                "apns_type": ApnsType::Certificate,
+                "apns_certificate": AUDIT_REDACTED,
+                "apns_certificate_password": AUDIT_REDACTED,
+            }),
+            TenantApnsUpdateAuth::Token {
+                apns_key_id,
+                apns_team_id,
+                ..
+            } => json!({
+                "apns_type": ApnsType::Token,
+                "apns_pkcs8_pem": AUDIT_REDACTED,
+                "apns_key_id": apns_key_id,
+                "apns_team_id": apns_team_id,
+            }),
+        }
+    }
+}
+
 #[derive(Debug, Eq, PartialEq, Clone)]
 pub struct TenantApnsUpdateParams {
     pub apns_topic: String,
 }
 
+impl TenantApnsUpdateParams {
+    /// The fields changed for the audit log
+    pub fn audit_changes(&self) -> Value {
+        json!({ "apns_topic": self.apns_topic })
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct TenantWebhookUpdateParams {
+    pub webhook_url: String,
+    pub webhook_secret: String,
+}
+
+impl TenantWebhookUpdateParams {
+    /// The fields changed for the audit log
+    pub fn audit_changes(&self) -> Value {
+        json!({
+            "webhook_url": self.webhook_url,
+            "webhook_secret": AUDIT_REDACTED,
+        })
+    }
+}
+
+/// Replaces secrets in the audit log
+pub const AUDIT_REDACTED: &str = "[redacted]";
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq, sqlx::Type, JsonSchema)]
+#[sqlx(type_name = "tenant_audit_action")]
+#[sqlx(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum TenantAuditAction {
+    CreateTenant,
+    UpdateFcm,
+    UpdateApns,
+    UpdateApnsAuth,
+    RemoveFcm,
+    RemoveApns,
+    DeleteTenant,
+    UpdateWebhook,
+    RemoveWebhook,
+    RenameTenant,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TenantAuditParams {
+    pub tenant_id: String,
+    /// Who made the change, see `Actor`
+    pub actor: String,
+    pub action: TenantAuditAction,
+    /// The fields changed with any secrets redacted
+    pub changes: Value,
+    pub request_id: String,
+}
+
+#[derive(sqlx::FromRow, Serialize, Deserialize, Debug, Clone, PartialEq, Eq, JsonSchema)]
+pub struct TenantAuditEntry {
+    pub id: i64,
+    pub tenant_id: String,
+    pub actor: String,
+    pub action: TenantAuditAction,
+    pub changes: Value,
+    pub request_id: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq, sqlx::Type, JsonSchema)]
+#[sqlx(type_name = "webhook_event_type")]
+#[sqlx(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEventType {
+    /// The provider rejected the tenant's credentials
+    ProviderAuthFailed,
+    /// The provider rejected a device's token, the client needs to re-register
+    ClientTokenInvalid,
+    QuotaExceeded,
+}
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq, sqlx::Type, JsonSchema)]
+#[sqlx(type_name = "webhook_delivery_status")]
+#[sqlx(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookDeliveryStatus {
+    Pending,
+    Delivered,
+    Failed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebhookDeliveryParams {
+    pub tenant_id: String,
+    pub event_id: String,
+    pub event_type: WebhookEventType,
+    pub url: String,
+    pub payload: Value,
+}
+
+/// The outcome of the latest delivery attempt
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebhookDeliveryUpdate {
+    pub status: WebhookDeliveryStatus,
+    pub attempts: i32,
+    pub response_status: Option<i32>,
+    pub last_error: Option<String>,
+}
+
+#[derive(sqlx::FromRow, Serialize, Deserialize, Debug, Clone, PartialEq, Eq, JsonSchema)]
+pub struct WebhookDelivery {
+    pub id: i64,
+    pub tenant_id: String,
+    pub event_id: String,
+    pub event_type: WebhookEventType,
+    pub url: String,
+    pub payload: Value,
+    pub status: WebhookDeliveryStatus,
+    pub attempts: i32,
+    pub response_status: Option<i32>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
 impl Tenant {
     pub fn providers(&self) -> Vec<ProviderKind> {
         let mut supported = vec![];
@@ -245,9 +480,30 @@ impl Tenant {
 #[async_trait]
 pub trait TenantStore {
     async fn get_tenant(&self, id: &str) -> Result<Tenant>;
+    async fn list_tenants(&self, params: TenantListParams) -> Result<Vec<Tenant>>;
+    /// The tenant with the client key, if any
+    async fn find_tenant_by_client_key(&self, client_key: &str) -> Result<Option<Tenant>>;
+    /// Soft deletes the tenant, it can be restored until it's purged
     async fn delete_tenant(&self, id: &str) -> Result<()>;
-    async fn create_tenant(&self, params: TenantUpdateParams) -> Result<Tenant>;
+    async fn restore_tenant(&self, id: &str) -> Result<Tenant>;
+    /// Tenants deleted before `deleted_before` that are due to be purged
+    async fn list_deleted_tenants(&self, deleted_before: DateTime<Utc>) -> Result<Vec<Tenant>>;
+    /// Permanently removes a deleted tenant along with its audit log and
+    /// webhook deliveries, so nothing is left behind for a new tenant reusing
+    /// the ID
+    async fn purge_tenant(&self, id: &str) -> Result<()>;
+    /// Creates the tenant along with the hash of its API key, so a tenant
+    /// never exists without a key
+    async fn create_tenant(&self, params: TenantUpdateParams, api_key_hash: &str)
+        -> Result<Tenant>;
+    /// Changes the tenant's ID, along with the ID on its audit log and webhook
+    /// deliveries. The old ID is kept in `renamed_from`, and can't be taken by
+    /// another tenant, until `complete_tenant_rename` is called
     async fn update_tenant(&self, id: &str, params: TenantUpdateParams) -> Result<Tenant>;
+    /// Clears `renamed_from` once the tenant's clients have been moved over
+    async fn complete_tenant_rename(&self, id: &str) -> Result<()>;
+    /// Renamed tenants whose clients haven't been moved over yet
+    async fn list_pending_renames(&self) -> Result<Vec<Tenant>>;
     async fn update_tenant_fcm(&self, id: &str, params: TenantFcmUpdateParams) -> Result<Tenant>;
     async fn update_tenant_apns(&self, id: &str, params: TenantApnsUpdateParams) -> Result<Tenant>;
     async fn update_tenant_apns_auth(
@@ -255,13 +511,59 @@ pub trait TenantStore {
         id: &str,
         params: TenantApnsUpdateAuth,
     ) -> Result<Tenant>;
+    async fn remove_tenant_fcm(&self, id: &str) -> Result<Tenant>;
+    async fn remove_tenant_apns(&self, id: &str) -> Result<Tenant>;
+    async fn update_tenant_api_key(&self, id: &str, api_key_hash: &str) -> Result<Tenant>;
+    async fn update_tenant_quota(
+        &self,
+        id: &str,
+        monthly_notification_quota: Option<i64>,
+    ) -> Result<Tenant>;
+    async fn update_tenant_rate_limit(
+        &self,
+        id: &str,
+        push_rate_limit_per_second: Option<i32>,
+    ) -> Result<Tenant>;
+    /// Records that the tenant was sent a `quota_exceeded` event for the month
+    /// starting on `month`, false if it already was
+    async fn claim_quota_exceeded_event(&self, id: &str, month: NaiveDate) -> Result<bool>;
+    async fn update_tenant_webhook(
+        &self,
+        id: &str,
+        params: TenantWebhookUpdateParams,
+    ) -> Result<Tenant>;
+    async fn remove_tenant_webhook(&self, id: &str) -> Result<Tenant>;
+    async fn record_audit_entry(&self, params: TenantAuditParams) -> Result<()>;
+    /// Newest entries first, `before` is the ID of the last entry on the
+    /// previous page
+    async fn list_audit_entries(
+        &self,
+        tenant_id: &str,
+        before: Option<i64>,
+        limit: i64,
+    ) -> Result<Vec<TenantAuditEntry>>;
+    /// Returns the ID of the new delivery
+    async fn create_webhook_delivery(&self, params: WebhookDeliveryParams) -> Result<i64>;
+    async fn update_webhook_delivery(&self, id: i64, update: WebhookDeliveryUpdate) -> Result<()>;
+    /// Newest deliveries first, `before` is the ID of the last delivery on the
+    /// previous page
+    async fn list_webhook_deliveries(
+        &self,
+        tenant_id: &str,
+        before: Option<i64>,
+        limit: i64,
+    ) -> Result<Vec<WebhookDelivery>>;
+}
+
+fn is_unique_violation(e: &sqlx::Error) -> bool {
+    matches!(e, sqlx::Error::Database(e) if e.code().as_deref() == Some("23505"))
 }
 
 #[async_trait]
 impl TenantStore for PgPool {
     async fn get_tenant(&self, id: &str) -> Result<Tenant> {
         let res = sqlx::query_as::<sqlx::postgres::Postgres, Tenant>(
-            "SELECT * FROM public.tenants WHERE id = $1",
+            "SELECT * FROM public.tenants WHERE id = $1 and deleted_at IS NULL",
         )
         .bind(id)
         .fetch_one(self)
@@ -274,9 +576,59 @@ impl TenantStore for PgPool {
         }
     }
 
+    async fn list_tenants(&self, params: TenantListParams) -> Result<Vec<Tenant>> {
+        let mut query_builder =
+            sqlx::QueryBuilder::new("SELECT * FROM public.tenants WHERE deleted_at IS NULL");
+        if let Some(after) = params.after {
+            query_builder.push(" and id > ");
+            query_builder.push_bind(after);
+        }
+        match params.provider {
+            Some(ProviderKind::Fcm) => {
+                query_builder.push(" and fcm_api_key IS NOT NULL");
+            }
+            Some(ProviderKind::Apns | ProviderKind::ApnsSandbox) => {
+                query_builder.push(" and ");
+                query_builder.push(APNS_CONFIGURED_CONDITION);
+            }
+            // Noop is always available when compiled in
+            _ => {}
+        }
+        if let Some(created_after) = params.created_after {
+            query_builder.push(" and created_at >= ");
+            query_builder.push_bind(created_after);
+        }
+        if let Some(created_before) = params.created_before {
+            query_builder.push(" and created_at < ");
+            query_builder.push_bind(created_before);
+        }
+        query_builder.push(" ORDER BY id LIMIT ");
+        query_builder.push_bind(params.limit);
+
+        let res = query_builder
+            .build_query_as::<Tenant>()
+            .fetch_all(self)
+            .await?;
+
+        Ok(res)
+    }
+
+    async fn find_tenant_by_client_key(&self, client_key: &str) -> Result<Option<Tenant>> {
+        let res = sqlx::query_as::<sqlx::postgres::Postgres, Tenant>(
+            "SELECT * FROM public.tenants WHERE client_key = $1 AND deleted_at IS NULL",
+        )
+        .bind(client_key)
+        .fetch_optional(self)
+        .await?;
+
+        Ok(res)
+    }
+
     async fn delete_tenant(&self, id: &str) -> Result<()> {
-        let mut query_builder = sqlx::QueryBuilder::new("DELETE FROM public.tenants WHERE id = ");
+        let mut query_builder =
+            sqlx::QueryBuilder::new("UPDATE public.tenants SET deleted_at = now() WHERE id = ");
         query_builder.push_bind(id);
+        query_builder.push(" and deleted_at IS NULL");
         let query = query_builder.build();
 
         self.execute(query).await?;
@@ -284,24 +636,143 @@ impl TenantStore for PgPool {
         Ok(())
     }
 
-    async fn create_tenant(&self, params: TenantUpdateParams) -> Result<Tenant> {
+    async fn restore_tenant(&self, id: &str) -> Result<Tenant> {
         let res = sqlx::query_as::<sqlx::postgres::Postgres, Tenant>(
-            "INSERT INTO public.tenants (id) VALUES ($1) RETURNING *;",
+            "UPDATE public.tenants SET deleted_at = null WHERE id = $1 and deleted_at IS NOT NULL \
+             RETURNING *;",
         )
-        .bind(params.id)
+        .bind(id)
         .fetch_one(self)
+        .await;
+
+        match res {
+            Err(sqlx::Error::RowNotFound) => Err(InvalidTenantId(id.into())),
+            Err(e) => Err(e.into()),
+            Ok(row) => Ok(row),
+        }
+    }
+
+    async fn list_deleted_tenants(&self, deleted_before: DateTime<Utc>) -> Result<Vec<Tenant>> {
+        let res = sqlx::query_as::<sqlx::postgres::Postgres, Tenant>(
+            "SELECT * FROM public.tenants WHERE deleted_at < $1",
+        )
+        .bind(deleted_before)
+        .fetch_all(self)
         .await?;
 
         Ok(res)
     }
 
+    async fn purge_tenant(&self, id: &str) -> Result<()> {
+        let mut transaction = self.begin().await?;
+
+        let res =
+            sqlx::query("DELETE FROM public.tenants WHERE id = $1 and deleted_at IS NOT NULL")
+                .bind(id)
+                .execute(&mut transaction)
+                .await?;
+
+        // Only the history of a tenant that was actually purged is removed
+        if res.rows_affected() > 0 {
+            sqlx::query("DELETE FROM public.tenant_audit_log WHERE tenant_id = $1")
+                .bind(id)
+                .execute(&mut transaction)
+                .await?;
+
+            sqlx::query("DELETE FROM public.tenant_webhook_deliveries WHERE tenant_id = $1")
+                .bind(id)
+                .execute(&mut transaction)
+                .await?;
+        }
+
+        transaction.commit().await?;
+
+        Ok(())
+    }
+
+    async fn create_tenant(
+        &self,
+        params: TenantUpdateParams,
+        api_key_hash: &str,
+    ) -> Result<Tenant> {
+        // The previous ID of a pending rename still has clients under it
+        let res = sqlx::query_as::<sqlx::postgres::Postgres, Tenant>(
+            "INSERT INTO public.tenants (id, api_key_hash) SELECT $1, $2 WHERE NOT EXISTS (SELECT \
+             1 FROM public.tenants WHERE renamed_from = $1) RETURNING *;",
+        )
+        .bind(&params.id)
+        .bind(api_key_hash)
+        .fetch_one(self)
+        .await;
+
+        match res {
+            Err(sqlx::Error::RowNotFound) => Err(TenantIdTaken(params.id)),
+            Err(e) if is_unique_violation(&e) => Err(TenantIdTaken(params.id)),
+            Err(e) => Err(e.into()),
+            Ok(row) => Ok(row),
+        }
+    }
+
     async fn update_tenant(&self, id: &str, params: TenantUpdateParams) -> Result<Tenant> {
+        let mut transaction = self.begin().await?;
+
+        // The previous ID of a pending rename still has clients under it
+        let pending = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS (SELECT 1 FROM public.tenants WHERE renamed_from = $1)",
+        )
+        .bind(&params.id)
+        .fetch_one(&mut transaction)
+        .await?;
+        if pending {
+            return Err(TenantIdTaken(params.id));
+        }
+
+        // A tenant with a pending rename must have it completed first, otherwise
+        // the clients under its previous ID would be lost track of
         let res = sqlx::query_as::<sqlx::postgres::Postgres, Tenant>(
-            "UPDATE public.tenants SET id = $2 WHERE id = $1 RETURNING *;",
+            "UPDATE public.tenants SET id = $2, renamed_from = $1 WHERE id = $1 AND deleted_at IS \
+             NULL AND renamed_from IS NULL RETURNING *;",
         )
         .bind(id)
-        .bind(params.id)
-        .fetch_one(self)
+        .bind(&params.id)
+        .fetch_one(&mut transaction)
+        .await;
+        let tenant = match res {
+            Err(sqlx::Error::RowNotFound) => return Err(InvalidTenantId(id.into())),
+            Err(e) if is_unique_violation(&e) => return Err(TenantIdTaken(params.id)),
+            Err(e) => return Err(e.into()),
+            Ok(row) => row,
+        };
+
+        for table in ["tenant_audit_log", "tenant_webhook_deliveries"] {
+            sqlx::query(&format!(
+                "UPDATE public.{table} SET tenant_id = $2 WHERE tenant_id = $1"
+            ))
+            .bind(id)
+            .bind(&params.id)
+            .execute(&mut transaction)
+            .await?;
+        }
+
+        transaction.commit().await?;
+
+        Ok(tenant)
+    }
+
+    async fn complete_tenant_rename(&self, id: &str) -> Result<()> {
+        sqlx::query("UPDATE public.tenants SET renamed_from = NULL WHERE id = $1")
+            .bind(id)
+            .execute(self)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn list_pending_renames(&self) -> Result<Vec<Tenant>> {
+        let res = sqlx::query_as::<sqlx::postgres::Postgres, Tenant>(
+            "SELECT * FROM public.tenants WHERE renamed_from IS NOT NULL",
+        )
+        .fetch_all(self)
         .await?;
 
         Ok(res)
@@ -367,6 +838,205 @@ impl TenantStore for PgPool {
 
         Ok(res)
     }
+
+    async fn remove_tenant_fcm(&self, id: &str) -> Result<Tenant> {
+        let res = sqlx::query_as::<sqlx::postgres::Postgres, Tenant>(
+            "UPDATE public.tenants SET fcm_api_key = null WHERE id = $1 AND deleted_at IS NULL \
+             RETURNING *;",
+        )
+        .bind(id)
+        .fetch_one(self)
+        .await?;
+
+        Ok(res)
+    }
+
+    async fn remove_tenant_apns(&self, id: &str) -> Result<Tenant> {
+        let res = sqlx::query_as::<sqlx::postgres::Postgres, Tenant>(
+            "UPDATE public.tenants SET apns_type = null, apns_topic = null, apns_certificate = \
+             null, apns_certificate_password = null, apns_pkcs8_pem = null, apns_team_id = null, \
+             apns_key_id = null WHERE id = $1 AND deleted_at IS NULL RETURNING *;",
+        )
+        .bind(id)
+        .fetch_one(self)
+        .await?;
+
+        Ok(res)
+    }
+
+    async fn update_tenant_api_key(&self, id: &str, api_key_hash: &str) -> Result<Tenant> {
+        let res = sqlx::query_as::<sqlx::postgres::Postgres, Tenant>(
+            "UPDATE public.tenants SET api_key_hash = $2 WHERE id = $1 RETURNING *;",
+        )
+        .bind(id)
+        .bind(api_key_hash)
+        .fetch_one(self)
+        .await?;
+
+        Ok(res)
+    }
+
+    async fn update_tenant_quota(
+        &self,
+        id: &str,
+        monthly_notification_quota: Option<i64>,
+    ) -> Result<Tenant> {
+        let res = sqlx::query_as::<sqlx::postgres::Postgres, Tenant>(
+            "UPDATE public.tenants SET monthly_notification_quota = $2, \
+             quota_exceeded_notified_for = NULL WHERE id = $1 RETURNING *;",
+        )
+        .bind(id)
+        .bind(monthly_notification_quota)
+        .fetch_one(self)
+        .await?;
+
+        Ok(res)
+    }
+
+    async fn update_tenant_rate_limit(
+        &self,
+        id: &str,
+        push_rate_limit_per_second: Option<i32>,
+    ) -> Result<Tenant> {
+        let res = sqlx::query_as::<sqlx::postgres::Postgres, Tenant>(
+            "UPDATE public.tenants SET push_rate_limit_per_second = $2 WHERE id = $1 RETURNING *;",
+        )
+        .bind(id)
+        .bind(push_rate_limit_per_second)
+        .fetch_one(self)
+        .await?;
+
+        Ok(res)
+    }
+
+    async fn claim_quota_exceeded_event(&self, id: &str, month: NaiveDate) -> Result<bool> {
+        let res = sqlx::query(
+            "UPDATE public.tenants SET quota_exceeded_notified_for = $2 WHERE id = $1 AND \
+             quota_exceeded_notified_for IS DISTINCT FROM $2 RETURNING id;",
+        )
+        .bind(id)
+        .bind(month)
+        .fetch_optional(self)
+        .await?;
+
+        Ok(res.is_some())
+    }
+
+    async fn update_tenant_webhook(
+        &self,
+        id: &str,
+        params: TenantWebhookUpdateParams,
+    ) -> Result<Tenant> {
+        let res = sqlx::query_as::<sqlx::postgres::Postgres, Tenant>(
+            "UPDATE public.tenants SET webhook_url = $2, webhook_secret = $3 WHERE id = $1 AND \
+             deleted_at IS NULL RETURNING *;",
+        )
+        .bind(id)
+        .bind(params.webhook_url)
+        .bind(params.webhook_secret)
+        .fetch_one(self)
+        .await?;
+
+        Ok(res)
+    }
+
+    async fn remove_tenant_webhook(&self, id: &str) -> Result<Tenant> {
+        let res = sqlx::query_as::<sqlx::postgres::Postgres, Tenant>(
+            "UPDATE public.tenants SET webhook_url = null, webhook_secret = null WHERE id = $1 \
+             AND deleted_at IS NULL RETURNING *;",
+        )
+        .bind(id)
+        .fetch_one(self)
+        .await?;
+
+        Ok(res)
+    }
+
+    async fn record_audit_entry(&self, params: TenantAuditParams) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO public.tenant_audit_log (tenant_id, actor, action, changes, request_id) \
+             VALUES ($1, $2, $3, $4, $5);",
+        )
+        .bind(params.tenant_id)
+        .bind(params.actor)
+        .bind(params.action)
+        .bind(params.changes)
+        .bind(params.request_id)
+        .execute(self)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn list_audit_entries(
+        &self,
+        tenant_id: &str,
+        before: Option<i64>,
+        limit: i64,
+    ) -> Result<Vec<TenantAuditEntry>> {
+        let res = sqlx::query_as::<sqlx::postgres::Postgres, TenantAuditEntry>(
+            "SELECT * FROM public.tenant_audit_log WHERE tenant_id = $1 AND ($2::bigint IS NULL \
+             OR id < $2) ORDER BY id DESC LIMIT $3;",
+        )
+        .bind(tenant_id)
+        .bind(before)
+        .bind(limit)
+        .fetch_all(self)
+        .await?;
+
+        Ok(res)
+    }
+
+    async fn create_webhook_delivery(&self, params: WebhookDeliveryParams) -> Result<i64> {
+        let (id,) = sqlx::query_as::<sqlx::postgres::Postgres, (i64,)>(
+            "INSERT INTO public.tenant_webhook_deliveries (tenant_id, event_id, event_type, url, \
+             payload) VALUES ($1, $2, $3, $4, $5) RETURNING id;",
+        )
+        .bind(params.tenant_id)
+        .bind(params.event_id)
+        .bind(params.event_type)
+        .bind(params.url)
+        .bind(params.payload)
+        .fetch_one(self)
+        .await?;
+
+        Ok(id)
+    }
+
+    async fn update_webhook_delivery(&self, id: i64, update: WebhookDeliveryUpdate) -> Result<()> {
+        sqlx::query(
+            "UPDATE public.tenant_webhook_deliveries SET status = $2, attempts = $3, \
+             response_status = $4, last_error = $5, updated_at = now() WHERE id = $1;",
+        )
+        .bind(id)
+        .bind(update.status)
+        .bind(update.attempts)
+        .bind(update.response_status)
+        .bind(update.last_error)
+        .execute(self)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn list_webhook_deliveries(
+        &self,
+        tenant_id: &str,
+        before: Option<i64>,
+        limit: i64,
+    ) -> Result<Vec<WebhookDelivery>> {
+        let res = sqlx::query_as::<sqlx::postgres::Postgres, WebhookDelivery>(
+            "SELECT * FROM public.tenant_webhook_deliveries WHERE tenant_id = $1 AND ($2::bigint \
+             IS NULL OR id < $2) ORDER BY id DESC LIMIT $3;",
+        )
+        .bind(tenant_id)
+        .bind(before)
+        .bind(limit)
+        .fetch_all(self)
+        .await?;
+
+        Ok(res)
+    }
 }
 
 pub struct DefaultTenantStore(Tenant);
@@ -383,6 +1053,14 @@ impl DefaultTenantStore {
             apns_pkcs8_pem: config.apns_pkcs8_pem.clone(),
             apns_key_id: config.apns_key_id.clone(),
             apns_team_id: config.apns_team_id.clone(),
+            api_key_hash: None,
+            client_key: None,
+            monthly_notification_quota: None,
+            push_rate_limit_per_second: None,
+            deleted_at: None,
+            renamed_from: None,
+            webhook_url: None,
+            webhook_secret: None,
             created_at: Default::default(),
             updated_at: Default::default(),
         }))
@@ -390,16 +1068,43 @@ impl DefaultTenantStore {
 }
 
 #[async_trait]
+// Note: `async_trait` wraps the `panic!`s below which trips this lint
+#[allow(clippy::diverging_sub_expression)]
 impl TenantStore for DefaultTenantStore {
     async fn get_tenant(&self, _id: &str) -> Result<Tenant> {
         Ok(self.0.clone())
     }
 
+    async fn list_tenants(&self, _params: TenantListParams) -> Result<Vec<Tenant>> {
+        panic!("Shouldn't have run in single tenant mode")
+    }
+
+    async fn find_tenant_by_client_key(&self, _client_key: &str) -> Result<Option<Tenant>> {
+        // The default tenant doesn't have a client key
+        Ok(None)
+    }
+
     async fn delete_tenant(&self, _id: &str) -> Result<()> {
         panic!("Shouldn't have run in single tenant mode")
     }
 
-    async fn create_tenant(&self, _params: TenantUpdateParams) -> Result<Tenant> {
+    async fn restore_tenant(&self, _id: &str) -> Result<Tenant> {
+        panic!("Shouldn't have run in single tenant mode")
+    }
+
+    async fn list_deleted_tenants(&self, _deleted_before: DateTime<Utc>) -> Result<Vec<Tenant>> {
+        panic!("Shouldn't have run in single tenant mode")
+    }
+
+    async fn purge_tenant(&self, _id: &str) -> Result<()> {
+        panic!("Shouldn't have run in single tenant mode")
+    }
+
+    async fn create_tenant(
+        &self,
+        _params: TenantUpdateParams,
+        _api_key_hash: &str,
+    ) -> Result<Tenant> {
         panic!("Shouldn't have run in single tenant mode")
     }
 
@@ -407,6 +1112,14 @@ impl TenantStore for DefaultTenantStore {
         panic!("Shouldn't have run in single tenant mode")
     }
 
+    async fn complete_tenant_rename(&self, _id: &str) -> Result<()> {
+        panic!("Shouldn't have run in single tenant mode")
+    }
+
+    async fn list_pending_renames(&self) -> Result<Vec<Tenant>> {
+        panic!("Shouldn't have run in single tenant mode")
+    }
+
     async fn update_tenant_fcm(&self, _id: &str, _params: TenantFcmUpdateParams) -> Result<Tenant> {
         panic!("Shouldn't have run in single tenant mode")
     }
@@ -426,4 +1139,82 @@ impl TenantStore for DefaultTenantStore {
     ) -> Result<Tenant> {
         panic!("Shouldn't have run in single tenant mode")
     }
+
+    async fn remove_tenant_fcm(&self, _id: &str) -> Result<Tenant> {
+        panic!("Shouldn't have run in single tenant mode")
+    }
+
+    async fn remove_tenant_apns(&self, _id: &str) -> Result<Tenant> {
+        panic!("Shouldn't have run in single tenant mode")
+    }
+
+    async fn update_tenant_api_key(&self, _id: &str, _api_key_hash: &str) -> Result<Tenant> {
+        panic!("Shouldn't have run in single tenant mode")
+    }
+
+    async fn update_tenant_quota(
+        &self,
+        _id: &str,
+        _monthly_notification_quota: Option<i64>,
+    ) -> Result<Tenant> {
+        panic!("Shouldn't have run in single tenant mode")
+    }
+
+    async fn update_tenant_rate_limit(
+        &self,
+        _id: &str,
+        _push_rate_limit_per_second: Option<i32>,
+    ) -> Result<Tenant> {
+        panic!("Shouldn't have run in single tenant mode")
+    }
+
+    async fn claim_quota_exceeded_event(&self, _id: &str, _month: NaiveDate) -> Result<bool> {
+        panic!("Shouldn't have run in single tenant mode")
+    }
+
+    async fn update_tenant_webhook(
+        &self,
+        _id: &str,
+        _params: TenantWebhookUpdateParams,
+    ) -> Result<Tenant> {
+        panic!("Shouldn't have run in single tenant mode")
+    }
+
+    async fn remove_tenant_webhook(&self, _id: &str) -> Result<Tenant> {
+        panic!("Shouldn't have run in single tenant mode")
+    }
+
+    async fn record_audit_entry(&self, _params: TenantAuditParams) -> Result<()> {
+        panic!("Shouldn't have run in single tenant mode")
+    }
+
+    async fn list_audit_entries(
+        &self,
+        _tenant_id: &str,
+        _before: Option<i64>,
+        _limit: i64,
+    ) -> Result<Vec<TenantAuditEntry>> {
+        panic!("Shouldn't have run in single tenant mode")
+    }
+
+    async fn create_webhook_delivery(&self, _params: WebhookDeliveryParams) -> Result<i64> {
+        panic!("Shouldn't have run in single tenant mode")
+    }
+
+    async fn update_webhook_delivery(
+        &self,
+        _id: i64,
+        _update: WebhookDeliveryUpdate,
+    ) -> Result<()> {
+        panic!("Shouldn't have run in single tenant mode")
+    }
+
+    async fn list_webhook_deliveries(
+        &self,
+        _tenant_id: &str,
+        _before: Option<i64>,
+        _limit: i64,
+    ) -> Result<Vec<WebhookDelivery>> {
+        panic!("Shouldn't have run in single tenant mode")
+    }
 }
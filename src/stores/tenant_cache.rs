@@ -0,0 +1,344 @@
+use {
+    crate::{
+        error::Result,
+        increment_counter,
+        metrics::Metrics,
+        state::TenantStoreArc,
+        stores::tenant::{
+            Tenant,
+            TenantApnsUpdateAuth,
+            TenantApnsUpdateParams,
+            TenantAuditEntry,
+            TenantAuditParams,
+            TenantFcmUpdateParams,
+            TenantListParams,
+            TenantStore,
+            TenantUpdateParams,
+            TenantWebhookUpdateParams,
+            WebhookDelivery,
+            WebhookDeliveryParams,
+            WebhookDeliveryUpdate,
+        },
+    },
+    async_trait::async_trait,
+    chrono::{DateTime, NaiveDate, Utc},
+    std::{
+        collections::{BTreeMap, HashMap},
+        sync::Mutex,
+        time::{Duration, Instant},
+    },
+};
+
+struct Entry {
+    tenant: Tenant,
+    cached_at: Instant,
+    /// Position in the LRU order
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct Cache {
+    entries: HashMap<String, Entry>,
+    /// Tenant IDs by when they were last used, the first is evicted when full
+    lru: BTreeMap<u64, String>,
+    tick: u64,
+    /// Incremented by every invalidation, a tenant loaded while it changed
+    /// may be stale so isn't cached
+    generation: u64,
+}
+
+impl Cache {
+    fn get(&mut self, id: &str, ttl: Duration) -> Option<Tenant> {
+        let tick = self.next_tick();
+        let entry = self.entries.get_mut(id)?;
+        if entry.cached_at.elapsed() >= ttl {
+            self.remove(id);
+            return None;
+        }
+
+        self.lru.remove(&entry.last_used);
+        entry.last_used = tick;
+        self.lru.insert(tick, id.to_string());
+
+        Some(entry.tenant.clone())
+    }
+
+    fn insert(&mut self, tenant: Tenant, capacity: usize) {
+        self.remove(&tenant.id);
+        while self.entries.len() >= capacity {
+            match self.lru.pop_first() {
+                Some((_, id)) => self.entries.remove(&id),
+                None => break,
+            };
+        }
+
+        let tick = self.next_tick();
+        self.lru.insert(tick, tenant.id.clone());
+        self.entries.insert(tenant.id.clone(), Entry {
+            tenant,
+            cached_at: Instant::now(),
+            last_used: tick,
+        });
+    }
+
+    /// Inserts a tenant loaded when the cache was at `generation`, unless it
+    /// has been invalidated since
+    fn insert_if_current(&mut self, tenant: Tenant, capacity: usize, generation: u64) {
+        if self.generation == generation {
+            self.insert(tenant, capacity);
+        }
+    }
+
+    fn invalidate(&mut self, id: &str) {
+        self.generation += 1;
+        self.remove(id);
+    }
+
+    fn remove(&mut self, id: &str) {
+        if let Some(entry) = self.entries.remove(id) {
+            self.lru.remove(&entry.last_used);
+        }
+    }
+
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+}
+
+/// Caches `get_tenant` in memory in front of another `TenantStore`. Tenants
+/// are invalidated when they're changed through this store, changes made by
+/// other instances are picked up once the TTL expires
+pub struct CachedTenantStore {
+    inner: TenantStoreArc,
+    ttl: Duration,
+    capacity: usize,
+    cache: Mutex<Cache>,
+    metrics: Option<Metrics>,
+}
+
+impl CachedTenantStore {
+    pub fn new(
+        inner: TenantStoreArc,
+        ttl: Duration,
+        capacity: usize,
+        metrics: Option<Metrics>,
+    ) -> Self {
+        CachedTenantStore {
+            inner,
+            ttl,
+            capacity: capacity.max(1),
+            cache: Default::default(),
+            metrics,
+        }
+    }
+
+    fn invalidate(&self, id: &str) {
+        self.cache.lock().unwrap().invalidate(id);
+    }
+}
+
+#[async_trait]
+impl TenantStore for CachedTenantStore {
+    async fn get_tenant(&self, id: &str) -> Result<Tenant> {
+        let (cached, generation) = {
+            let mut cache = self.cache.lock().unwrap();
+            (cache.get(id, self.ttl), cache.generation)
+        };
+        if let Some(tenant) = cached {
+            increment_counter!(self.metrics, tenant_cache_hits);
+            return Ok(tenant);
+        }
+        increment_counter!(self.metrics, tenant_cache_misses);
+
+        // A write that commits while this is loading invalidates the tenant
+        // before this inserts it, so it's only cached if nothing was
+        // invalidated in the meantime
+        let tenant = self.inner.get_tenant(id).await?;
+        self.cache
+            .lock()
+            .unwrap()
+            .insert_if_current(tenant.clone(), self.capacity, generation);
+
+        Ok(tenant)
+    }
+
+    async fn list_tenants(&self, params: TenantListParams) -> Result<Vec<Tenant>> {
+        self.inner.list_tenants(params).await
+    }
+
+    async fn find_tenant_by_client_key(&self, client_key: &str) -> Result<Option<Tenant>> {
+        self.inner.find_tenant_by_client_key(client_key).await
+    }
+
+    async fn delete_tenant(&self, id: &str) -> Result<()> {
+        let res = self.inner.delete_tenant(id).await;
+        self.invalidate(id);
+        res
+    }
+
+    async fn restore_tenant(&self, id: &str) -> Result<Tenant> {
+        let res = self.inner.restore_tenant(id).await;
+        self.invalidate(id);
+        res
+    }
+
+    async fn list_deleted_tenants(&self, deleted_before: DateTime<Utc>) -> Result<Vec<Tenant>> {
+        self.inner.list_deleted_tenants(deleted_before).await
+    }
+
+    async fn purge_tenant(&self, id: &str) -> Result<()> {
+        let res = self.inner.purge_tenant(id).await;
+        self.invalidate(id);
+        res
+    }
+
+    async fn create_tenant(
+        &self,
+        params: TenantUpdateParams,
+        api_key_hash: &str,
+    ) -> Result<Tenant> {
+        let id = params.id.clone();
+        let res = self.inner.create_tenant(params, api_key_hash).await;
+        self.invalidate(&id);
+        res
+    }
+
+    async fn update_tenant(&self, id: &str, params: TenantUpdateParams) -> Result<Tenant> {
+        let new_id = params.id.clone();
+        let res = self.inner.update_tenant(id, params).await;
+        self.invalidate(id);
+        self.invalidate(&new_id);
+        res
+    }
+
+    async fn complete_tenant_rename(&self, id: &str) -> Result<()> {
+        let res = self.inner.complete_tenant_rename(id).await;
+        self.invalidate(id);
+        res
+    }
+
+    async fn list_pending_renames(&self) -> Result<Vec<Tenant>> {
+        self.inner.list_pending_renames().await
+    }
+
+    async fn update_tenant_fcm(&self, id: &str, params: TenantFcmUpdateParams) -> Result<Tenant> {
+        let res = self.inner.update_tenant_fcm(id, params).await;
+        self.invalidate(id);
+        res
+    }
+
+    async fn update_tenant_apns(&self, id: &str, params: TenantApnsUpdateParams) -> Result<Tenant> {
+        let res = self.inner.update_tenant_apns(id, params).await;
+        self.invalidate(id);
+        res
+    }
+
+    async fn update_tenant_apns_auth(
+        &self,
+        id: &str,
+        params: TenantApnsUpdateAuth,
+    ) -> Result<Tenant> {
+        let res = self.inner.update_tenant_apns_auth(id, params).await;
+        self.invalidate(id);
+        res
+    }
+
+    async fn remove_tenant_fcm(&self, id: &str) -> Result<Tenant> {
+        let res = self.inner.remove_tenant_fcm(id).await;
+        self.invalidate(id);
+        res
+    }
+
+    async fn remove_tenant_apns(&self, id: &str) -> Result<Tenant> {
+        let res = self.inner.remove_tenant_apns(id).await;
+        self.invalidate(id);
+        res
+    }
+
+    async fn update_tenant_api_key(&self, id: &str, api_key_hash: &str) -> Result<Tenant> {
+        let res = self.inner.update_tenant_api_key(id, api_key_hash).await;
+        self.invalidate(id);
+        res
+    }
+
+    async fn update_tenant_quota(
+        &self,
+        id: &str,
+        monthly_notification_quota: Option<i64>,
+    ) -> Result<Tenant> {
+        let res = self
+            .inner
+            .update_tenant_quota(id, monthly_notification_quota)
+            .await;
+        self.invalidate(id);
+        res
+    }
+
+    async fn update_tenant_rate_limit(
+        &self,
+        id: &str,
+        push_rate_limit_per_second: Option<i32>,
+    ) -> Result<Tenant> {
+        let res = self
+            .inner
+            .update_tenant_rate_limit(id, push_rate_limit_per_second)
+            .await;
+        self.invalidate(id);
+        res
+    }
+
+    async fn claim_quota_exceeded_event(&self, id: &str, month: NaiveDate) -> Result<bool> {
+        self.inner.claim_quota_exceeded_event(id, month).await
+    }
+
+    async fn update_tenant_webhook(
+        &self,
+        id: &str,
+        params: TenantWebhookUpdateParams,
+    ) -> Result<Tenant> {
+        let res = self.inner.update_tenant_webhook(id, params).await;
+        self.invalidate(id);
+        res
+    }
+
+    async fn remove_tenant_webhook(&self, id: &str) -> Result<Tenant> {
+        let res = self.inner.remove_tenant_webhook(id).await;
+        self.invalidate(id);
+        res
+    }
+
+    async fn record_audit_entry(&self, params: TenantAuditParams) -> Result<()> {
+        self.inner.record_audit_entry(params).await
+    }
+
+    async fn list_audit_entries(
+        &self,
+        tenant_id: &str,
+        before: Option<i64>,
+        limit: i64,
+    ) -> Result<Vec<TenantAuditEntry>> {
+        self.inner
+            .list_audit_entries(tenant_id, before, limit)
+            .await
+    }
+
+    async fn create_webhook_delivery(&self, params: WebhookDeliveryParams) -> Result<i64> {
+        self.inner.create_webhook_delivery(params).await
+    }
+
+    async fn update_webhook_delivery(&self, id: i64, update: WebhookDeliveryUpdate) -> Result<()> {
+        self.inner.update_webhook_delivery(id, update).await
+    }
+
+    async fn list_webhook_deliveries(
+        &self,
+        tenant_id: &str,
+        before: Option<i64>,
+        limit: i64,
+    ) -> Result<Vec<WebhookDelivery>> {
+        self.inner
+            .list_webhook_deliveries(tenant_id, before, limit)
+            .await
+    }
+}
@@ -0,0 +1,220 @@
+use {
+    crate::{
+        error::{
+            Error::{DefaultTenantImmutable, TenantIdTaken},
+            Result,
+        },
+        state::TenantStoreArc,
+        stores::tenant::{
+            Tenant,
+            TenantApnsUpdateAuth,
+            TenantApnsUpdateParams,
+            TenantAuditEntry,
+            TenantAuditParams,
+            TenantFcmUpdateParams,
+            TenantListParams,
+            TenantStore,
+            TenantUpdateParams,
+            TenantWebhookUpdateParams,
+            WebhookDelivery,
+            WebhookDeliveryParams,
+            WebhookDeliveryUpdate,
+        },
+    },
+    async_trait::async_trait,
+    chrono::{DateTime, NaiveDate, Utc},
+};
+
+/// Serves the environment configured default tenant alongside tenants hosted
+/// in the tenant database. The default tenant can be read but not changed
+pub struct HybridTenantStore {
+    default_tenant_id: String,
+    default_store: TenantStoreArc,
+    hosted_store: TenantStoreArc,
+}
+
+impl HybridTenantStore {
+    pub fn new(
+        default_tenant_id: String,
+        default_store: TenantStoreArc,
+        hosted_store: TenantStoreArc,
+    ) -> Self {
+        HybridTenantStore {
+            default_tenant_id,
+            default_store,
+            hosted_store,
+        }
+    }
+
+    /// The hosted store, as long as `id` isn't the default tenant
+    fn hosted(&self, id: &str) -> Result<&TenantStoreArc> {
+        match id == self.default_tenant_id {
+            true => Err(DefaultTenantImmutable),
+            false => Ok(&self.hosted_store),
+        }
+    }
+}
+
+#[async_trait]
+impl TenantStore for HybridTenantStore {
+    async fn get_tenant(&self, id: &str) -> Result<Tenant> {
+        match id == self.default_tenant_id {
+            true => self.default_store.get_tenant(id).await,
+            false => self.hosted_store.get_tenant(id).await,
+        }
+    }
+
+    async fn list_tenants(&self, params: TenantListParams) -> Result<Vec<Tenant>> {
+        self.hosted_store.list_tenants(params).await
+    }
+
+    async fn find_tenant_by_client_key(&self, client_key: &str) -> Result<Option<Tenant>> {
+        self.hosted_store
+            .find_tenant_by_client_key(client_key)
+            .await
+    }
+
+    async fn delete_tenant(&self, id: &str) -> Result<()> {
+        self.hosted(id)?.delete_tenant(id).await
+    }
+
+    async fn restore_tenant(&self, id: &str) -> Result<Tenant> {
+        self.hosted(id)?.restore_tenant(id).await
+    }
+
+    async fn list_deleted_tenants(&self, deleted_before: DateTime<Utc>) -> Result<Vec<Tenant>> {
+        self.hosted_store.list_deleted_tenants(deleted_before).await
+    }
+
+    async fn purge_tenant(&self, id: &str) -> Result<()> {
+        self.hosted(id)?.purge_tenant(id).await
+    }
+
+    async fn create_tenant(
+        &self,
+        params: TenantUpdateParams,
+        api_key_hash: &str,
+    ) -> Result<Tenant> {
+        if params.id == self.default_tenant_id {
+            return Err(TenantIdTaken(params.id));
+        }
+
+        self.hosted_store.create_tenant(params, api_key_hash).await
+    }
+
+    async fn update_tenant(&self, id: &str, params: TenantUpdateParams) -> Result<Tenant> {
+        if params.id == self.default_tenant_id {
+            return Err(TenantIdTaken(params.id));
+        }
+
+        self.hosted(id)?.update_tenant(id, params).await
+    }
+
+    async fn complete_tenant_rename(&self, id: &str) -> Result<()> {
+        self.hosted(id)?.complete_tenant_rename(id).await
+    }
+
+    async fn list_pending_renames(&self) -> Result<Vec<Tenant>> {
+        self.hosted_store.list_pending_renames().await
+    }
+
+    async fn update_tenant_fcm(&self, id: &str, params: TenantFcmUpdateParams) -> Result<Tenant> {
+        self.hosted(id)?.update_tenant_fcm(id, params).await
+    }
+
+    async fn update_tenant_apns(&self, id: &str, params: TenantApnsUpdateParams) -> Result<Tenant> {
+        self.hosted(id)?.update_tenant_apns(id, params).await
+    }
+
+    async fn update_tenant_apns_auth(
+        &self,
+        id: &str,
+        params: TenantApnsUpdateAuth,
+    ) -> Result<Tenant> {
+        self.hosted(id)?.update_tenant_apns_auth(id, params).await
+    }
+
+    async fn remove_tenant_fcm(&self, id: &str) -> Result<Tenant> {
+        self.hosted(id)?.remove_tenant_fcm(id).await
+    }
+
+    async fn remove_tenant_apns(&self, id: &str) -> Result<Tenant> {
+        self.hosted(id)?.remove_tenant_apns(id).await
+    }
+
+    async fn update_tenant_api_key(&self, id: &str, api_key_hash: &str) -> Result<Tenant> {
+        self.hosted(id)?
+            .update_tenant_api_key(id, api_key_hash)
+            .await
+    }
+
+    async fn update_tenant_quota(
+        &self,
+        id: &str,
+        monthly_notification_quota: Option<i64>,
+    ) -> Result<Tenant> {
+        self.hosted(id)?
+            .update_tenant_quota(id, monthly_notification_quota)
+            .await
+    }
+
+    async fn update_tenant_rate_limit(
+        &self,
+        id: &str,
+        push_rate_limit_per_second: Option<i32>,
+    ) -> Result<Tenant> {
+        self.hosted(id)?
+            .update_tenant_rate_limit(id, push_rate_limit_per_second)
+            .await
+    }
+
+    async fn claim_quota_exceeded_event(&self, id: &str, month: NaiveDate) -> Result<bool> {
+        self.hosted(id)?.claim_quota_exceeded_event(id, month).await
+    }
+
+    async fn update_tenant_webhook(
+        &self,
+        id: &str,
+        params: TenantWebhookUpdateParams,
+    ) -> Result<Tenant> {
+        self.hosted(id)?.update_tenant_webhook(id, params).await
+    }
+
+    async fn remove_tenant_webhook(&self, id: &str) -> Result<Tenant> {
+        self.hosted(id)?.remove_tenant_webhook(id).await
+    }
+
+    async fn record_audit_entry(&self, params: TenantAuditParams) -> Result<()> {
+        self.hosted_store.record_audit_entry(params).await
+    }
+
+    async fn list_audit_entries(
+        &self,
+        tenant_id: &str,
+        before: Option<i64>,
+        limit: i64,
+    ) -> Result<Vec<TenantAuditEntry>> {
+        self.hosted_store
+            .list_audit_entries(tenant_id, before, limit)
+            .await
+    }
+
+    async fn create_webhook_delivery(&self, params: WebhookDeliveryParams) -> Result<i64> {
+        self.hosted_store.create_webhook_delivery(params).await
+    }
+
+    async fn update_webhook_delivery(&self, id: i64, update: WebhookDeliveryUpdate) -> Result<()> {
+        self.hosted_store.update_webhook_delivery(id, update).await
+    }
+
+    async fn list_webhook_deliveries(
+        &self,
+        tenant_id: &str,
+        before: Option<i64>,
+        limit: i64,
+    ) -> Result<Vec<WebhookDelivery>> {
+        self.hosted_store
+            .list_webhook_deliveries(tenant_id, before, limit)
+            .await
+    }
+}
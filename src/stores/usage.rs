@@ -0,0 +1,203 @@
+use {
+    crate::stores,
+    async_trait::async_trait,
+    chrono::NaiveDate,
+    schemars::JsonSchema,
+    serde::{Deserialize, Serialize},
+};
+
+/// Usage counters for a tenant, used both as the increments to record and as
+/// the totals for a period
+#[derive(
+    Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, sqlx::FromRow, JsonSchema,
+)]
+pub struct UsageCounts {
+    pub received_notifications: i64,
+    pub sent_fcm_notifications: i64,
+    pub sent_apns_notifications: i64,
+    /// Notifications delivered to at least one of the client's devices, each
+    /// notification counts once however many devices it went to. This is
+    /// what quotas apply to
+    pub delivered_notifications: i64,
+    /// Notifications that couldn't be delivered to any of the client's devices
+    pub failed_notifications: i64,
+    pub registrations: i64,
+}
+
+impl UsageCounts {
+    /// Deliveries to a provider, one for each of a client's devices
+    pub fn sent_notifications(&self) -> i64 {
+        self.sent_fcm_notifications + self.sent_apns_notifications
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, sqlx::FromRow, JsonSchema)]
+pub struct DailyUsage {
+    pub day: NaiveDate,
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    pub counts: UsageCounts,
+    /// Clients registered at the day's last snapshot, unset for days before
+    /// snapshots were taken
+    pub registered_clients: Option<i64>,
+}
+
+#[async_trait]
+pub trait UsageStore {
+    /// Adds the counts to the tenant's usage on `day`, which is the current
+    /// UTC date so days line up with the quota's calendar months
+    async fn record_usage(
+        &self,
+        tenant_id: &str,
+        day: NaiveDate,
+        counts: UsageCounts,
+    ) -> stores::Result<()>;
+    /// Daily usage between `from` and `to`, inclusive, oldest first
+    async fn get_usage(
+        &self,
+        tenant_id: &str,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> stores::Result<Vec<DailyUsage>>;
+    /// Total usage between `from` and `to`, inclusive
+    async fn get_usage_totals(
+        &self,
+        tenant_id: &str,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> stores::Result<UsageCounts>;
+    /// Deletes all of the tenant's usage, returning how many days were deleted
+    async fn delete_usage(&self, tenant_id: &str) -> stores::Result<u64>;
+    /// Records the number of clients each tenant has as their count for
+    /// `day`, replacing any earlier snapshot of that day. Tenants without
+    /// clients are only recorded if they had some earlier in the day. Returns
+    /// how many tenants with clients were recorded
+    async fn snapshot_registered_clients(&self, day: NaiveDate) -> stores::Result<u64>;
+}
+
+#[async_trait]
+impl UsageStore for sqlx::PgPool {
+    async fn record_usage(
+        &self,
+        tenant_id: &str,
+        day: NaiveDate,
+        counts: UsageCounts,
+    ) -> stores::Result<()> {
+        sqlx::query(
+            "INSERT INTO public.tenant_usage (tenant_id, day, received_notifications,
+                                 sent_fcm_notifications, sent_apns_notifications,
+                                 delivered_notifications, failed_notifications, registrations)
+VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+ON CONFLICT (tenant_id, day)
+    DO UPDATE SET received_notifications  = tenant_usage.received_notifications + \
+             EXCLUDED.received_notifications,
+                  sent_fcm_notifications  = tenant_usage.sent_fcm_notifications + \
+             EXCLUDED.sent_fcm_notifications,
+                  sent_apns_notifications = tenant_usage.sent_apns_notifications + \
+             EXCLUDED.sent_apns_notifications,
+                  delivered_notifications = tenant_usage.delivered_notifications + \
+             EXCLUDED.delivered_notifications,
+                  failed_notifications    = tenant_usage.failed_notifications + \
+             EXCLUDED.failed_notifications,
+                  registrations           = tenant_usage.registrations + EXCLUDED.registrations;",
+        )
+        .bind(tenant_id)
+        .bind(day)
+        .bind(counts.received_notifications)
+        .bind(counts.sent_fcm_notifications)
+        .bind(counts.sent_apns_notifications)
+        .bind(counts.delivered_notifications)
+        .bind(counts.failed_notifications)
+        .bind(counts.registrations)
+        .execute(self)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_usage(
+        &self,
+        tenant_id: &str,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> stores::Result<Vec<DailyUsage>> {
+        let res = sqlx::query_as::<sqlx::postgres::Postgres, DailyUsage>(
+            "SELECT day, received_notifications, sent_fcm_notifications, sent_apns_notifications, \
+             delivered_notifications, failed_notifications, registrations, registered_clients \
+             FROM public.tenant_usage WHERE tenant_id = $1 and day >= $2 and day <= $3 ORDER BY \
+             day",
+        )
+        .bind(tenant_id)
+        .bind(from)
+        .bind(to)
+        .fetch_all(self)
+        .await?;
+
+        Ok(res)
+    }
+
+    async fn get_usage_totals(
+        &self,
+        tenant_id: &str,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> stores::Result<UsageCounts> {
+        let res = sqlx::query_as::<sqlx::postgres::Postgres, UsageCounts>(
+            "SELECT COALESCE(SUM(received_notifications), 0)::bigint as received_notifications,
+       COALESCE(SUM(sent_fcm_notifications), 0)::bigint  as sent_fcm_notifications,
+       COALESCE(SUM(sent_apns_notifications), 0)::bigint as sent_apns_notifications,
+       COALESCE(SUM(delivered_notifications), 0)::bigint as delivered_notifications,
+       COALESCE(SUM(failed_notifications), 0)::bigint    as failed_notifications,
+       COALESCE(SUM(registrations), 0)::bigint           as registrations
+FROM public.tenant_usage
+WHERE tenant_id = $1
+  and day >= $2
+  and day <= $3",
+        )
+        .bind(tenant_id)
+        .bind(from)
+        .bind(to)
+        .fetch_one(self)
+        .await?;
+
+        Ok(res)
+    }
+
+    async fn delete_usage(&self, tenant_id: &str) -> stores::Result<u64> {
+        let res = sqlx::query("DELETE FROM public.tenant_usage WHERE tenant_id = $1")
+            .bind(tenant_id)
+            .execute(self)
+            .await?;
+
+        Ok(res.rows_affected())
+    }
+
+    async fn snapshot_registered_clients(&self, day: NaiveDate) -> stores::Result<u64> {
+        let mut transaction = self.begin().await?;
+
+        let res = sqlx::query(
+            "INSERT INTO public.tenant_usage (tenant_id, day, registered_clients)
+SELECT tenant_id, $1, count(*)
+FROM public.clients
+GROUP BY tenant_id
+ON CONFLICT (tenant_id, day) DO UPDATE SET registered_clients = EXCLUDED.registered_clients;",
+        )
+        .bind(day)
+        .execute(&mut transaction)
+        .await?;
+
+        // Tenants whose last clients were deleted since an earlier snapshot
+        sqlx::query(
+            "UPDATE public.tenant_usage SET registered_clients = 0 WHERE day = $1 and \
+             registered_clients > 0 and tenant_id NOT IN (SELECT DISTINCT tenant_id FROM \
+             public.clients)",
+        )
+        .bind(day)
+        .execute(&mut transaction)
+        .await?;
+
+        transaction.commit().await?;
+
+        Ok(res.rows_affected())
+    }
+}
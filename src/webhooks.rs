@@ -0,0 +1,351 @@
+use {
+    crate::{
+        config::Config,
+        error::Error,
+        log::prelude::*,
+        state::AppState,
+        stores::tenant::{
+            Tenant,
+            WebhookDeliveryParams,
+            WebhookDeliveryStatus,
+            WebhookDeliveryUpdate,
+            WebhookEventType,
+        },
+    },
+    chrono::{DateTime, Utc},
+    hmac::{Hmac, Mac},
+    hyper::client::connect::dns::Name,
+    reqwest::{
+        dns::{Addrs, Resolve, Resolving},
+        header::CONTENT_TYPE,
+        redirect,
+        Url,
+    },
+    serde::{Deserialize, Serialize},
+    serde_json::Value,
+    sha2::Sha256,
+    std::{
+        net::{IpAddr, SocketAddr},
+        sync::Arc,
+        time::Duration,
+    },
+    tokio::net::lookup_host,
+    uuid::Uuid,
+};
+
+pub const SIGNATURE_HEADER_NAME: &str = "X-Echo-Signature";
+pub const TIMESTAMP_HEADER_NAME: &str = "X-Echo-Timestamp";
+pub const EVENT_ID_HEADER_NAME: &str = "X-Echo-Event-Id";
+
+const SIGNATURE_PREFIX: &str = "sha256=";
+
+/// The body POSTed to a tenant's webhook URL
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WebhookEvent {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub event_type: WebhookEventType,
+    pub tenant_id: String,
+    pub created_at: DateTime<Utc>,
+    pub data: Value,
+}
+
+/// The event a tenant should be sent for a push failure, if any
+pub fn event_type_for(error: &Error) -> Option<WebhookEventType> {
+    match error {
+        Error::Apns(a2::Error::ResponseError(response)) => {
+            match response.error.as_ref().map(|body| &body.reason) {
+                Some(
+                    a2::ErrorReason::BadDeviceToken
+                    | a2::ErrorReason::Unregistered
+                    | a2::ErrorReason::DeviceTokenNotForTopic,
+                ) => Some(WebhookEventType::ClientTokenInvalid),
+                Some(
+                    a2::ErrorReason::BadCertificate
+                    | a2::ErrorReason::BadCertificateEnvironment
+                    | a2::ErrorReason::ExpiredProviderToken
+                    | a2::ErrorReason::InvalidProviderToken
+                    | a2::ErrorReason::MissingProviderToken,
+                ) => Some(WebhookEventType::ProviderAuthFailed),
+                _ => None,
+            }
+        }
+        // The certificate or key couldn't be loaded
+        Error::Apns(a2::Error::SignerError(_) | a2::Error::ReadError(_)) => {
+            Some(WebhookEventType::ProviderAuthFailed)
+        }
+        Error::Fcm(fcm::FcmError::Unauthorized) => Some(WebhookEventType::ProviderAuthFailed),
+        Error::FcmMessage(
+            fcm::ErrorReason::InvalidRegistration
+            | fcm::ErrorReason::NotRegistered
+            | fcm::ErrorReason::MissingRegistration,
+        ) => Some(WebhookEventType::ClientTokenInvalid),
+        Error::FcmMessage(
+            fcm::ErrorReason::MismatchSenderId | fcm::ErrorReason::InvalidApnsCredential,
+        ) => Some(WebhookEventType::ProviderAuthFailed),
+        Error::QuotaExceeded(_) => Some(WebhookEventType::QuotaExceeded),
+        _ => None,
+    }
+}
+
+/// Whether webhooks may be delivered to the address, only public addresses are
+/// allowed so a webhook can't be used to reach services on our network
+pub fn is_public_address(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, c, _] = ip.octets();
+            !(ip.is_unspecified()
+                || ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || ip.is_multicast()
+                // 0.0.0.0/8, "this network"
+                || a == 0
+                // 100.64.0.0/10, shared address space used by carrier-grade NAT
+                || (a == 100 && (b & 0xc0) == 64)
+                // 192.0.0.0/24, IETF protocol assignments
+                || (a == 192 && b == 0 && c == 0)
+                // 198.18.0.0/15, benchmarking
+                || (a == 198 && (b & 0xfe) == 18)
+                // 240.0.0.0/4, reserved
+                || a >= 240)
+        }
+        IpAddr::V6(ip) => {
+            if let Some(ip) = ip.to_ipv4_mapped() {
+                return is_public_address(IpAddr::V4(ip));
+            }
+            let [first, second, ..] = ip.segments();
+            !(ip.is_unspecified()
+                || ip.is_loopback()
+                || ip.is_multicast()
+                // fc00::/7, unique local
+                || (first & 0xfe00) == 0xfc00
+                // fe80::/10, link local
+                || (first & 0xffc0) == 0xfe80
+                // 2001:db8::/32, documentation
+                || (first == 0x2001 && second == 0x0db8))
+        }
+    }
+}
+
+/// Checks every address the webhook URL's host resolves to is public, the
+/// error describes why it isn't
+pub async fn check_destination(url: &str) -> Result<(), String> {
+    let url = Url::parse(url).map_err(|e| e.to_string())?;
+    let port = url.port_or_known_default().unwrap_or_default();
+    // IPv6 hosts are bracketed in URLs, IP addresses are returned as they are
+    // without a lookup
+    let host = url
+        .host_str()
+        .ok_or_else(|| "Must have a host".to_string())?
+        .trim_start_matches('[')
+        .trim_end_matches(']');
+    let addresses = lookup_host((host, port))
+        .await
+        .map_err(|e| format!("Couldn't resolve {host}: {e}"))?
+        .map(|address| address.ip())
+        .collect::<Vec<_>>();
+
+    if addresses.is_empty() {
+        return Err("Must resolve to an address".to_string());
+    }
+    match addresses.into_iter().find(|ip| !is_public_address(*ip)) {
+        Some(ip) => Err(format!("Must resolve to a public address, {ip} isn't")),
+        None => Ok(()),
+    }
+}
+
+/// Only resolves hosts to public addresses, so a host that passed
+/// `check_destination` can't be pointed somewhere else before delivery
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let addresses = lookup_host((name.as_str(), 0))
+                .await?
+                .filter(|address| is_public_address(address.ip()))
+                .collect::<Vec<SocketAddr>>();
+            if addresses.is_empty() {
+                return Err(
+                    format!("{} doesn't resolve to a public address", name.as_str()).into(),
+                );
+            }
+
+            Ok(Box::new(addresses.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// The client used to deliver webhooks. Redirects aren't followed as they
+/// could lead anywhere, and unless `WEBHOOK_ALLOW_PRIVATE_ADDRESSES` is set
+/// only public addresses are connected to
+pub fn client(config: &Config) -> reqwest::Result<reqwest::Client> {
+    let builder = reqwest::Client::builder()
+        .timeout(Duration::from_secs(config.webhook_timeout_secs))
+        .redirect(redirect::Policy::none());
+
+    match config.webhook_allow_private_addresses {
+        true => builder.build(),
+        false => builder.dns_resolver(Arc::new(PublicResolver)).build(),
+    }
+}
+
+/// Hex encoded HMAC-SHA256 of `{timestamp}.{body}` keyed with the tenant's
+/// webhook secret
+pub fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC can take a key of any size");
+    mac.update(format!("{timestamp}.{body}").as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Sends the event to the tenant's webhook in the background, it's a no-op for
+/// tenants without a webhook
+pub async fn dispatch(
+    state: &Arc<AppState>,
+    tenant: &Tenant,
+    event_type: WebhookEventType,
+    data: Value,
+) {
+    let (url, secret) = match (&tenant.webhook_url, &tenant.webhook_secret) {
+        (Some(url), Some(secret)) => (url.clone(), secret.clone()),
+        _ => return,
+    };
+
+    let event = WebhookEvent {
+        id: Uuid::new_v4().to_string(),
+        event_type,
+        tenant_id: tenant.id.clone(),
+        created_at: Utc::now(),
+        data,
+    };
+    let payload = match serde_json::to_value(&event) {
+        Ok(payload) => payload,
+        Err(e) => {
+            error!("failed to serialize webhook event ({}): {}", &event.id, e);
+            return;
+        }
+    };
+
+    let delivery_id = match state
+        .tenant_store
+        .create_webhook_delivery(WebhookDeliveryParams {
+            tenant_id: tenant.id.clone(),
+            event_id: event.id.clone(),
+            event_type,
+            url: url.clone(),
+            payload: payload.clone(),
+        })
+        .await
+    {
+        Ok(id) => id,
+        Err(e) => {
+            error!(
+                "failed to record webhook event ({}) for tenant ({}): {}",
+                &event.id, &tenant.id, e
+            );
+            return;
+        }
+    };
+
+    tokio::spawn(deliver(
+        state.clone(),
+        delivery_id,
+        url,
+        secret,
+        event.id,
+        payload.to_string(),
+    ));
+}
+
+/// Retries with exponential backoff until the webhook responds with a success
+/// status or the attempts run out
+async fn deliver(
+    state: Arc<AppState>,
+    delivery_id: i64,
+    url: String,
+    secret: String,
+    event_id: String,
+    body: String,
+) {
+    let max_attempts = state.config.webhook_max_attempts.max(1);
+    let mut delay = Duration::from_millis(state.config.webhook_retry_delay_ms);
+
+    for attempt in 1..=max_attempts {
+        let timestamp = Utc::now().timestamp();
+        let signature = sign(&secret, timestamp, &body);
+
+        // The resolver isn't used for URLs with an IP address, and webhooks
+        // set before private addresses were rejected are still stored, so the
+        // destination is checked before every attempt
+        let destination = match state.config.webhook_allow_private_addresses {
+            true => Ok(()),
+            false => check_destination(&url).await,
+        };
+
+        let (response_status, last_error) = match destination {
+            Err(e) => (None, Some(format!("destination isn't allowed: {e}"))),
+            Ok(()) => match state
+                .webhook_client
+                .post(&url)
+                .header(CONTENT_TYPE, "application/json")
+                .header(EVENT_ID_HEADER_NAME, &event_id)
+                .header(TIMESTAMP_HEADER_NAME, timestamp)
+                .header(
+                    SIGNATURE_HEADER_NAME,
+                    format!("{SIGNATURE_PREFIX}{signature}"),
+                )
+                .body(body.clone())
+                .send()
+                .await
+            {
+                Ok(response) if response.status().is_success() => {
+                    (Some(response.status().as_u16() as i32), None)
+                }
+                Ok(response) => (
+                    Some(response.status().as_u16() as i32),
+                    Some(format!("unexpected response status {}", response.status())),
+                ),
+                Err(e) => (None, Some(e.to_string())),
+            },
+        };
+
+        let status = match (&last_error, attempt == max_attempts) {
+            (None, _) => WebhookDeliveryStatus::Delivered,
+            (Some(_), true) => WebhookDeliveryStatus::Failed,
+            (Some(_), false) => WebhookDeliveryStatus::Pending,
+        };
+        if let Some(e) = &last_error {
+            warn!(
+                "webhook event ({}) delivery attempt {} of {} failed: {}",
+                &event_id, attempt, max_attempts, e
+            );
+        }
+
+        if let Err(e) = state
+            .tenant_store
+            .update_webhook_delivery(delivery_id, WebhookDeliveryUpdate {
+                status,
+                attempts: attempt as i32,
+                response_status,
+                last_error,
+            })
+            .await
+        {
+            warn!(
+                "failed to record webhook event ({}) delivery attempt: {}",
+                &event_id, e
+            );
+        }
+
+        if status != WebhookDeliveryStatus::Pending {
+            return;
+        }
+
+        tokio::time::sleep(delay).await;
+        delay *= 2;
+    }
+}
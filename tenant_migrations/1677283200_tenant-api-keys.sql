@@ -0,0 +1,2 @@
+ALTER TABLE public.tenants
+    ADD COLUMN api_key_hash text;
@@ -0,0 +1 @@
+ALTER TABLE public.tenants ADD COLUMN monthly_notification_quota bigint;
@@ -0,0 +1 @@
+ALTER TABLE public.tenants ADD COLUMN deleted_at timestamptz;
@@ -0,0 +1,24 @@
+CREATE TYPE public.tenant_audit_action AS ENUM (
+    'create_tenant',
+    'update_fcm',
+    'update_apns',
+    'update_apns_auth',
+    'remove_fcm',
+    'remove_apns',
+    'delete_tenant'
+);
+
+-- Entries are deleted by `purge_tenant` along with the tenant, rather than by a
+-- foreign key
+CREATE TABLE IF NOT EXISTS public.tenant_audit_log
+(
+    id         bigserial                primary key,
+    tenant_id  varchar(255)             not null,
+    actor      varchar(255)             not null,
+    action     tenant_audit_action      not null,
+    changes    jsonb                    not null default '{}'::jsonb,
+    request_id varchar(255)             not null,
+    created_at timestamptz              not null default now()
+);
+
+CREATE INDEX tenant_audit_log_tenant_id_idx ON public.tenant_audit_log (tenant_id, id);
@@ -0,0 +1,38 @@
+ALTER TABLE public.tenants
+    ADD COLUMN webhook_url text,
+    ADD COLUMN webhook_secret text;
+
+ALTER TYPE public.tenant_audit_action ADD VALUE 'update_webhook';
+ALTER TYPE public.tenant_audit_action ADD VALUE 'remove_webhook';
+
+CREATE TYPE public.webhook_event_type AS ENUM (
+    'provider_auth_failed',
+    'client_token_invalid',
+    'quota_exceeded'
+);
+
+CREATE TYPE public.webhook_delivery_status AS ENUM (
+    'pending',
+    'delivered',
+    'failed'
+);
+
+-- Entries are deleted by `purge_tenant` along with the tenant, rather than by a
+-- foreign key
+CREATE TABLE IF NOT EXISTS public.tenant_webhook_deliveries
+(
+    id              bigserial                primary key,
+    tenant_id       varchar(255)             not null,
+    event_id        varchar(255)             not null,
+    event_type      webhook_event_type       not null,
+    url             text                     not null,
+    payload         jsonb                    not null,
+    status          webhook_delivery_status  not null default 'pending',
+    attempts        integer                  not null default 0,
+    response_status integer,
+    last_error      text,
+    created_at      timestamptz              not null default now(),
+    updated_at      timestamptz              not null default now()
+);
+
+CREATE INDEX tenant_webhook_deliveries_tenant_id_idx ON public.tenant_webhook_deliveries (tenant_id, id);
@@ -0,0 +1 @@
+ALTER TYPE public.tenant_audit_action ADD VALUE 'rename_tenant';
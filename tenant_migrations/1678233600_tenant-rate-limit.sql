@@ -0,0 +1 @@
+ALTER TABLE public.tenants ADD COLUMN push_rate_limit_per_second integer;
@@ -0,0 +1,3 @@
+-- The first day of the month the tenant was last sent a `quota_exceeded` event for, so
+-- it's sent once a month rather than for every rejected push
+ALTER TABLE public.tenants ADD COLUMN quota_exceeded_notified_for date;
@@ -0,0 +1,6 @@
+-- A publishable key identifying the tenant on client requests, so the API key
+-- never has to be shipped in apps. Existing tenants get one from the default
+ALTER TABLE public.tenants
+    ADD COLUMN client_key text NOT NULL DEFAULT 'pk_' || replace(gen_random_uuid()::text, '-', '');
+
+CREATE UNIQUE INDEX tenants_client_key_idx ON public.tenants (client_key);
@@ -0,0 +1,3 @@
+-- The previous ID of a renamed tenant, set until its clients in the main
+-- database have been moved over to the new ID
+ALTER TABLE public.tenants ADD COLUMN renamed_from varchar(255);
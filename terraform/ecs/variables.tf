@@ -78,4 +78,8 @@ variable "aws_otel_collector_ecr_repository_url" {
 
 variable "allowed_origins" {
   type = string
+}
+
+variable "unversioned_routes_sunset" {
+  type = string
 }
\ No newline at end of file
@@ -139,6 +139,8 @@ module "ecs" {
   telemetry_sample_ratio = terraform.workspace == "prod" ? 0.25 : 1.0
   allowed_origins        = terraform.workspace == "prod" ? "https://cloud.walletconnect.com" : "*"
 
+  unversioned_routes_sunset = "Fri, 01 Oct 2027 00:00:00 GMT"
+
   aws_otel_collector_ecr_repository_url = data.aws_ecr_repository.aws_otel_collector.repository_url
 }
 
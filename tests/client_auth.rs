@@ -0,0 +1,139 @@
+use {
+    base64::Engine as _,
+    chrono::Utc,
+    echo_server::middleware::validate_client_jwt::{
+        did_key_to_public_key,
+        public_key_to_did_key,
+        verify_client_jwt,
+        ClientJwtClaims,
+        JwtHeader,
+        RequireClientAuth,
+        JWT_MAX_LIFETIME_SECONDS,
+    },
+    ed25519_dalek::{Keypair, PublicKey, SecretKey, Signer},
+};
+
+const EXAMPLE_AUDIENCE: &str = "https://echo.walletconnect.com";
+
+fn keypair_from_seed(seed: u8) -> Keypair {
+    let secret = SecretKey::from_bytes(&[seed; 32]).expect("invalid secret key");
+    let public = PublicKey::from(&secret);
+    Keypair { secret, public }
+}
+
+fn encode<T: serde::Serialize>(value: &T) -> String {
+    base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .encode(serde_json::to_vec(value).expect("failed to serialize"))
+}
+
+fn sign_jwt(keypair: &Keypair, claims: &ClientJwtClaims) -> String {
+    let message = format!("{}.{}", encode(&JwtHeader::default()), encode(claims));
+    let signature = keypair.sign(message.as_bytes());
+
+    format!(
+        "{}.{}",
+        message,
+        base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(signature.to_bytes())
+    )
+}
+
+fn claims(keypair: &Keypair, audience: &str, exp: i64) -> ClientJwtClaims {
+    ClientJwtClaims {
+        iss: public_key_to_did_key(&keypair.public),
+        aud: audience.to_string(),
+        iat: Utc::now().timestamp(),
+        exp,
+    }
+}
+
+#[test]
+pub fn did_key_round_trip() {
+    let keypair = keypair_from_seed(1);
+    let did_key = public_key_to_did_key(&keypair.public);
+
+    assert!(did_key.starts_with("did:key:z6Mk"));
+    assert_eq!(
+        did_key_to_public_key(&did_key).expect("failed to decode did:key"),
+        keypair.public
+    );
+}
+
+#[test]
+pub fn valid_client_jwt() {
+    let keypair = keypair_from_seed(1);
+    let jwt = sign_jwt(
+        &keypair,
+        &claims(&keypair, EXAMPLE_AUDIENCE, Utc::now().timestamp() + 3600),
+    );
+
+    let client_id = verify_client_jwt(&jwt, EXAMPLE_AUDIENCE).expect("JWT should be valid");
+
+    assert_eq!(
+        format!("did:key:{client_id}"),
+        public_key_to_did_key(&keypair.public)
+    );
+}
+
+#[test]
+pub fn client_jwt_wrong_audience() {
+    let keypair = keypair_from_seed(1);
+    let jwt = sign_jwt(
+        &keypair,
+        &claims(
+            &keypair,
+            "https://evil.example.com",
+            Utc::now().timestamp() + 3600,
+        ),
+    );
+
+    assert!(verify_client_jwt(&jwt, EXAMPLE_AUDIENCE).is_err());
+}
+
+#[test]
+pub fn client_jwt_expired() {
+    let keypair = keypair_from_seed(1);
+    let jwt = sign_jwt(
+        &keypair,
+        &claims(&keypair, EXAMPLE_AUDIENCE, Utc::now().timestamp() - 3600),
+    );
+
+    assert!(verify_client_jwt(&jwt, EXAMPLE_AUDIENCE).is_err());
+}
+
+#[test]
+pub fn client_jwt_valid_for_too_long() {
+    let keypair = keypair_from_seed(1);
+    let jwt = sign_jwt(
+        &keypair,
+        &claims(
+            &keypair,
+            EXAMPLE_AUDIENCE,
+            Utc::now().timestamp() + JWT_MAX_LIFETIME_SECONDS + 60,
+        ),
+    );
+
+    assert!(verify_client_jwt(&jwt, EXAMPLE_AUDIENCE).is_err());
+}
+
+#[test]
+pub fn client_jwt_signed_by_other_key() {
+    let keypair = keypair_from_seed(1);
+    let other = keypair_from_seed(2);
+
+    // Claims to be `keypair` but is signed by `other`
+    let jwt = sign_jwt(
+        &other,
+        &claims(&keypair, EXAMPLE_AUDIENCE, Utc::now().timestamp() + 3600),
+    );
+
+    assert!(verify_client_jwt(&jwt, EXAMPLE_AUDIENCE).is_err());
+}
+
+#[test]
+pub fn client_auth_must_match_client() {
+    let auth = RequireClientAuth(Some("z6MkClient".to_string()));
+
+    assert!(auth.check("z6MkClient").is_ok());
+    assert!(auth.check("z6MkOtherClient").is_err());
+    assert!(RequireClientAuth(None).check("z6MkOtherClient").is_ok());
+}
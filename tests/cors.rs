@@ -0,0 +1,30 @@
+use echo_server::middleware::cors::cors_layer;
+
+#[test]
+fn test_cors_allowed_origins() {
+    let valid = [
+        vec!["*"],
+        vec!["https://example.com"],
+        vec!["https://example.com", "http://localhost:3000"],
+        vec!["https://*.example.com", "https://example.com"],
+    ];
+    for origins in valid {
+        let origins = origins.into_iter().map(String::from).collect::<Vec<_>>();
+        assert!(cors_layer(&origins).is_ok(), "{origins:?} should be valid");
+    }
+
+    let invalid = [
+        "example.com",
+        "https://",
+        "https://example.com/path",
+        "https://*",
+        "https://*example.com",
+        "https://app.*.example.com",
+    ];
+    for origin in invalid {
+        assert!(
+            cors_layer(&[origin.to_string()]).is_err(),
+            "{origin} should be invalid"
+        );
+    }
+}
@@ -0,0 +1,60 @@
+use {
+    axum::{http::StatusCode, response::IntoResponse},
+    echo_server::error::Error,
+};
+
+/// Values sent by clients that fail to decode are the client's fault
+#[test]
+fn test_decode_errors_are_client_errors() {
+    let errors = [
+        Error::Hex(hex::FromHexError::OddLength),
+        Error::Ed25519(ed25519_dalek::ed25519::Error::new()),
+        Error::Base64Decode(base64::DecodeError::InvalidLength),
+        Error::EmptyField("token".to_string()),
+    ];
+
+    for error in errors {
+        assert_eq!(error.into_response().status(), StatusCode::BAD_REQUEST);
+    }
+}
+
+/// Errors keep the `name` they had before `code` was added, so existing clients
+/// that match on it still work
+#[tokio::test]
+async fn test_errors_keep_legacy_name() {
+    let errors = [
+        (
+            Error::Database(sqlx::Error::RowNotFound),
+            "sqlx",
+            "database",
+        ),
+        (Error::MissingTenantId, "tenancy-mode", "tenancy_mode"),
+        (
+            Error::ProviderNotFound("noop".to_string()),
+            "provider_not_available",
+            "provider_not_found",
+        ),
+    ];
+
+    for (error, name, code) in errors {
+        let body = hyper::body::to_bytes(error.into_response().into_body())
+            .await
+            .expect("Failed to read body");
+        let body: serde_json::Value = serde_json::from_slice(&body).expect("Invalid JSON");
+        assert_eq!(body["errors"][0]["name"], name);
+        assert_eq!(body["errors"][0]["code"], code);
+    }
+}
+
+/// FCM refusing a message, e.g. for a stale token, isn't an internal error
+#[tokio::test]
+async fn test_fcm_rejections_are_bad_gateway() {
+    let response = Error::FcmMessage(fcm::ErrorReason::NotRegistered).into_response();
+    assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+
+    let body = hyper::body::to_bytes(response.into_body())
+        .await
+        .expect("Failed to read body");
+    let body: serde_json::Value = serde_json::from_slice(&body).expect("Invalid JSON");
+    assert_eq!(body["errors"][0]["code"], "fcm");
+}
@@ -0,0 +1,184 @@
+use {
+    crate::context::{MultiTenantServerContext, SingleTenantServerContext, ADMIN_API_KEY},
+    echo_server::{
+        handlers::{
+            create_tenant::{TenantRegisterBody, TenantRegisterResponse},
+            get_client::ClientResponse,
+            list_clients::ListClientsResponse,
+            register_client::RegisterBody,
+        },
+        stores::client::DeviceMetadata,
+    },
+    random_string::generate,
+    test_context::test_context,
+};
+
+#[test_context(SingleTenantServerContext)]
+#[tokio::test]
+async fn test_get_client(ctx: &mut SingleTenantServerContext) {
+    let charset = "1234567890";
+    let random_client_id = generate(12, charset);
+    let payload = RegisterBody {
+        client_id: random_client_id.clone(),
+        push_type: "noop".to_string(),
+        token: "0123456789abcdef".to_string(),
+        device_id: None,
+        metadata: Some(DeviceMetadata {
+            sdk_name: Some("kotlin".to_string()),
+            sdk_version: Some("0.9.1".to_string()),
+            timezone: Some("Europe/London".to_string()),
+            ..Default::default()
+        }),
+    };
+
+    let client = reqwest::Client::new();
+    let response = client
+        .post(format!("http://{}/clients", ctx.server.public_addr))
+        .json(&payload)
+        .send()
+        .await
+        .expect("Call failed");
+
+    assert!(response.status().is_success(), "Failed to register client");
+
+    // Requires authentication
+    let response = client
+        .get(format!(
+            "http://{}/clients/{}",
+            ctx.server.public_addr, random_client_id
+        ))
+        .send()
+        .await
+        .expect("Call failed");
+
+    assert_eq!(response.status().as_u16(), 401);
+
+    let response = client
+        .get(format!(
+            "http://{}/clients/{}",
+            ctx.server.public_addr, random_client_id
+        ))
+        .bearer_auth("not-the-admin-key")
+        .send()
+        .await
+        .expect("Call failed");
+
+    assert_eq!(response.status().as_u16(), 403);
+
+    let response = client
+        .get(format!(
+            "http://{}/clients/{}",
+            ctx.server.public_addr, random_client_id
+        ))
+        .bearer_auth(ADMIN_API_KEY)
+        .send()
+        .await
+        .expect("Call failed");
+
+    assert!(response.status().is_success(), "Failed to fetch client");
+
+    let body: ClientResponse = response.json().await.expect("Invalid response body");
+    assert_eq!(body.id, random_client_id);
+    assert_eq!(body.devices.len(), 1);
+    assert_eq!(body.devices[0].push_type, "noop");
+    assert_eq!(body.devices[0].token, "0123********cdef");
+    assert_eq!(body.devices[0].metadata.sdk_name.as_deref(), Some("kotlin"));
+    assert_eq!(
+        body.devices[0].metadata.timezone.as_deref(),
+        Some("Europe/London")
+    );
+}
+
+#[test_context(MultiTenantServerContext)]
+#[tokio::test]
+async fn test_list_clients(ctx: &mut MultiTenantServerContext) {
+    let charset = "1234567890";
+    let random_tenant_id = generate(12, charset);
+    let client = reqwest::Client::new();
+
+    let response = client
+        .post(format!("http://{}/tenants", ctx.server.public_addr))
+        .bearer_auth(ADMIN_API_KEY)
+        .json(&TenantRegisterBody {
+            id: random_tenant_id.clone(),
+        })
+        .send()
+        .await
+        .expect("Call failed");
+
+    assert!(response.status().is_success(), "Failed to create tenant");
+    let tenant: TenantRegisterResponse = response.json().await.expect("Invalid response body");
+
+    let mut client_ids = vec![];
+    for _ in 0..3 {
+        let random_client_id = generate(12, charset);
+        let payload = RegisterBody {
+            client_id: random_client_id.clone(),
+            push_type: "noop".to_string(),
+            token: "test".to_string(),
+            device_id: None,
+            metadata: None,
+        };
+
+        let response = client
+            .post(format!(
+                "http://{}/{}/clients",
+                ctx.server.public_addr, random_tenant_id
+            ))
+            .json(&payload)
+            .send()
+            .await
+            .expect("Call failed");
+
+        assert!(response.status().is_success(), "Failed to register client");
+
+        client_ids.push(random_client_id);
+    }
+    client_ids.sort();
+
+    // Page through the clients with the tenant's own API key
+    let mut listed = vec![];
+    let mut cursor: Option<String> = None;
+    loop {
+        let mut request = client
+            .get(format!(
+                "http://{}/{}/clients",
+                ctx.server.public_addr, random_tenant_id
+            ))
+            .bearer_auth(&tenant.api_key)
+            .query(&[("limit", "2"), ("type", "noop")]);
+        if let Some(cursor) = &cursor {
+            request = request.query(&[("cursor", cursor)]);
+        }
+
+        let response = request.send().await.expect("Call failed");
+        assert!(response.status().is_success(), "Failed to list clients");
+
+        let body: ListClientsResponse = response.json().await.expect("Invalid response body");
+        assert!(body.clients.len() <= 2);
+        listed.extend(body.clients.into_iter().map(|c| c.id));
+
+        match body.next_cursor {
+            Some(next) => cursor = Some(next),
+            None => break,
+        }
+    }
+
+    assert_eq!(listed, client_ids);
+
+    // Filtering by a provider no device uses returns nothing
+    let response = client
+        .get(format!(
+            "http://{}/{}/clients",
+            ctx.server.public_addr, random_tenant_id
+        ))
+        .bearer_auth(&tenant.api_key)
+        .query(&[("type", "fcm")])
+        .send()
+        .await
+        .expect("Call failed");
+
+    let body: ListClientsResponse = response.json().await.expect("Invalid response body");
+    assert!(body.clients.is_empty());
+    assert!(body.next_cursor.is_none());
+}
@@ -1,8 +1,19 @@
-use {crate::context::SingleTenantServerContext, test_context::test_context};
+use {
+    crate::context::{SingleTenantServerContext, CORS_ALLOWED_ORIGIN, UNVERSIONED_ROUTES_SUNSET},
+    echo_server::{
+        handlers::register_client::RegisterBody,
+        middleware::deprecation::{DEPRECATION_HEADER_NAME, SUNSET_HEADER_NAME},
+    },
+    random_string::generate,
+    test_context::test_context,
+};
 
+mod clients;
 mod push;
 mod registration;
 mod tenancy;
+mod usage;
+mod webhooks;
 
 #[test_context(SingleTenantServerContext)]
 #[tokio::test]
@@ -13,3 +24,168 @@ async fn test_health(ctx: &mut SingleTenantServerContext) {
         .status();
     assert!(body.is_success());
 }
+
+#[test_context(SingleTenantServerContext)]
+#[tokio::test]
+async fn test_openapi(ctx: &mut SingleTenantServerContext) {
+    let response = reqwest::get(format!("http://{}/openapi.json", ctx.server.public_addr))
+        .await
+        .expect("Failed to call /openapi.json");
+    assert!(response.status().is_success());
+
+    let spec: serde_json::Value = response.json().await.expect("Invalid JSON");
+    assert_eq!(spec["openapi"], "3.0.3");
+    assert!(spec["paths"]["/v1/clients"]["post"].is_object());
+}
+
+#[test_context(SingleTenantServerContext)]
+#[tokio::test]
+async fn test_versioned_routes(ctx: &mut SingleTenantServerContext) {
+    let client = reqwest::Client::new();
+    let register = || RegisterBody {
+        client_id: generate(12, "1234567890"),
+        push_type: "noop".to_string(),
+        token: "token".to_string(),
+        device_id: None,
+        metadata: None,
+    };
+
+    let response = client
+        .post(format!("http://{}/v1/clients", ctx.server.public_addr))
+        .json(&register())
+        .send()
+        .await
+        .expect("Call failed");
+    assert!(response.status().is_success(), "Failed to register client");
+    assert!(response.headers().get(DEPRECATION_HEADER_NAME).is_none());
+    assert!(response.headers().get(SUNSET_HEADER_NAME).is_none());
+
+    // The unversioned routes still work but are marked as deprecated
+    let response = client
+        .post(format!("http://{}/clients", ctx.server.public_addr))
+        .json(&register())
+        .send()
+        .await
+        .expect("Call failed");
+    assert!(response.status().is_success(), "Failed to register client");
+    assert_eq!(
+        response.headers().get(DEPRECATION_HEADER_NAME).unwrap(),
+        "true"
+    );
+    assert_eq!(
+        response.headers().get(SUNSET_HEADER_NAME).unwrap(),
+        UNVERSIONED_ROUTES_SUNSET
+    );
+
+    // Errors from the unversioned routes are marked too
+    let response = client
+        .get(format!("http://{}/clients/missing", ctx.server.public_addr))
+        .send()
+        .await
+        .expect("Call failed");
+    assert!(!response.status().is_success());
+    assert!(response.headers().get(DEPRECATION_HEADER_NAME).is_some());
+}
+
+#[test_context(SingleTenantServerContext)]
+#[tokio::test]
+async fn test_rejections_use_response_envelope(ctx: &mut SingleTenantServerContext) {
+    let client = reqwest::Client::new();
+
+    let response = client
+        .post(format!("http://{}/v1/clients", ctx.server.public_addr))
+        .header("Content-Type", "application/json")
+        .body("{")
+        .send()
+        .await
+        .expect("Call failed");
+    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+    let body: serde_json::Value = response.json().await.expect("Invalid JSON");
+    assert_eq!(body["status"], "FAILURE");
+    assert_eq!(body["errors"][0]["code"], "invalid_request");
+
+    let response = client
+        .get(format!("http://{}/v1/missing", ctx.server.public_addr))
+        .send()
+        .await
+        .expect("Call failed");
+    assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+    assert!(response.headers().get("x-request-id").is_some());
+    let body: serde_json::Value = response.json().await.expect("Invalid JSON");
+    assert_eq!(body["errors"][0]["code"], "not_found");
+}
+
+#[test_context(SingleTenantServerContext)]
+#[tokio::test]
+async fn test_cors(ctx: &mut SingleTenantServerContext) {
+    let client = reqwest::Client::new();
+    let allowed_origin = |origin: &str, path: &str| {
+        let request = client
+            .get(format!("http://{}{}", ctx.server.public_addr, path))
+            .header("Origin", origin);
+        async move {
+            let response = request.send().await.expect("Call failed");
+            response
+                .headers()
+                .get("Access-Control-Allow-Origin")
+                .map(|header| header.to_str().unwrap().to_string())
+        }
+    };
+
+    // Exact origins and subdomains of wildcard origins are allowed
+    assert_eq!(
+        allowed_origin(CORS_ALLOWED_ORIGIN, "/health").await,
+        Some(CORS_ALLOWED_ORIGIN.to_string())
+    );
+    assert_eq!(
+        allowed_origin("https://wallet.example.org", "/health").await,
+        Some("https://wallet.example.org".to_string())
+    );
+    assert_eq!(allowed_origin("https://example.org", "/health").await, None);
+    assert_eq!(
+        allowed_origin("http://wallet.example.org", "/health").await,
+        None
+    );
+    assert_eq!(allowed_origin("https://example.com", "/health").await, None);
+
+    // Errors from client routes have the headers too
+    assert_eq!(
+        allowed_origin(CORS_ALLOWED_ORIGIN, "/v1/clients/missing").await,
+        Some(CORS_ALLOWED_ORIGIN.to_string())
+    );
+
+    // Preflight requests are answered for routes that don't handle `OPTIONS`
+    let response = client
+        .request(
+            reqwest::Method::OPTIONS,
+            format!("http://{}/v1/clients", ctx.server.public_addr),
+        )
+        .header("Origin", "https://wallet.example.org")
+        .header("Access-Control-Request-Method", "POST")
+        .header(
+            "Access-Control-Request-Headers",
+            "authorization,content-type",
+        )
+        .send()
+        .await
+        .expect("Call failed");
+    assert!(response.status().is_success());
+    let headers = response.headers();
+    assert_eq!(
+        headers.get("Access-Control-Allow-Origin").unwrap(),
+        "https://wallet.example.org"
+    );
+    assert!(headers
+        .get("Access-Control-Allow-Methods")
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .contains("POST"));
+    assert!(headers
+        .get("Access-Control-Allow-Headers")
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .contains("authorization"));
+    assert_eq!(headers.get("Access-Control-Max-Age").unwrap(), "3600");
+}
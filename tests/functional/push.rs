@@ -1,10 +1,15 @@
 use {
-    crate::context::SingleTenantServerContext,
-    echo_server::handlers::{
-        push_message::{MessagePayload, PushMessageBody},
-        register_client::RegisterBody,
+    crate::context::{SingleTenantServerContext, ADMIN_API_KEY, DATABASE_URL},
+    echo_server::{
+        handlers::{
+            get_notification::NotificationHistoryResponse,
+            push_message::{MessagePayload, PushMessageBody},
+            register_client::RegisterBody,
+        },
+        stores::notification::NotificationStatus,
     },
     random_string::generate,
+    serde_json::Value,
     test_context::test_context,
     uuid::Uuid,
 };
@@ -18,6 +23,8 @@ async fn test_push(ctx: &mut SingleTenantServerContext) {
         client_id: random_client_id.clone(),
         push_type: "noop".to_string(),
         token: "test".to_string(),
+        device_id: None,
+        metadata: None,
     };
 
     // Register client
@@ -39,7 +46,7 @@ async fn test_push(ctx: &mut SingleTenantServerContext) {
     let topic = Uuid::new_v4().to_string();
     let blob = Uuid::new_v4().to_string();
     let push_message_payload = MessagePayload {
-        topic: Some(topic.into()),
+        topic: Some(topic),
         blob: blob.to_string(),
         flags: 0,
     };
@@ -86,3 +93,156 @@ async fn test_push(ctx: &mut SingleTenantServerContext) {
         "Response was not successful"
     );
 }
+
+#[test_context(SingleTenantServerContext)]
+#[tokio::test]
+async fn test_push_retried_after_failure(ctx: &mut SingleTenantServerContext) {
+    let charset = "1234567890";
+    let random_client_id = generate(12, charset);
+    let client = reqwest::Client::new();
+    let response = client
+        .post(format!("http://{}/clients", ctx.server.public_addr))
+        .json(&RegisterBody {
+            client_id: random_client_id.clone(),
+            push_type: "noop".to_string(),
+            token: "test".to_string(),
+            device_id: None,
+            metadata: None,
+        })
+        .send()
+        .await
+        .expect("Call failed");
+    assert!(response.status().is_success(), "Failed to register client");
+
+    let payload = PushMessageBody {
+        id: Uuid::new_v4().to_string(),
+        payload: MessagePayload {
+            topic: None,
+            blob: Uuid::new_v4().to_string(),
+            flags: 0,
+        },
+    };
+    let push_url = format!(
+        "http://{}/clients/{}",
+        ctx.server.public_addr, random_client_id
+    );
+
+    let response = client
+        .post(&push_url)
+        .json(&payload)
+        .send()
+        .await
+        .expect("Call failed");
+    assert_eq!(response.status().as_u16(), 202);
+
+    // Mark the delivery as failed so the retry is attempted again rather than
+    // being treated as a duplicate
+    let pool = sqlx::PgPool::connect(DATABASE_URL)
+        .await
+        .expect("failed to connect to postgres");
+    sqlx::query("UPDATE public.notifications SET status = 'failed' WHERE id = $1")
+        .bind(&payload.id)
+        .execute(&pool)
+        .await
+        .expect("update failed");
+
+    let response = client
+        .post(&push_url)
+        .json(&payload)
+        .send()
+        .await
+        .expect("Call failed");
+    assert_eq!(response.status().as_u16(), 202);
+
+    let (status,) = sqlx::query_as::<_, (String,)>(
+        "SELECT status::text FROM public.notifications WHERE id = $1",
+    )
+    .bind(&payload.id)
+    .fetch_one(&pool)
+    .await
+    .expect("fetch failed");
+    assert_eq!(status, "delivered");
+
+    // Now it has been delivered the retry is deduplicated
+    let response = client
+        .post(&push_url)
+        .json(&payload)
+        .send()
+        .await
+        .expect("Call failed");
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[test_context(SingleTenantServerContext)]
+#[tokio::test]
+async fn test_notification_history(ctx: &mut SingleTenantServerContext) {
+    let charset = "1234567890";
+    let random_client_id = generate(12, charset);
+    let client = reqwest::Client::new();
+    let response = client
+        .post(format!("http://{}/clients", ctx.server.public_addr))
+        .json(&RegisterBody {
+            client_id: random_client_id.clone(),
+            push_type: "noop".to_string(),
+            token: "test".to_string(),
+            device_id: None,
+            metadata: None,
+        })
+        .send()
+        .await
+        .expect("Call failed");
+    assert!(response.status().is_success(), "Failed to register client");
+
+    let notification_id = Uuid::new_v4().to_string();
+    let blobs = [Uuid::new_v4().to_string(), Uuid::new_v4().to_string()];
+    for blob in &blobs {
+        let response = client
+            .post(format!(
+                "http://{}/clients/{}",
+                ctx.server.public_addr, random_client_id
+            ))
+            .json(&PushMessageBody {
+                id: notification_id.clone(),
+                payload: MessagePayload {
+                    topic: None,
+                    blob: blob.clone(),
+                    flags: 0,
+                },
+            })
+            .send()
+            .await
+            .expect("Call failed");
+        assert!(response.status().is_success(), "Failed to push");
+    }
+
+    let history_url = format!(
+        "http://{}/notifications/{}",
+        ctx.server.public_addr, notification_id
+    );
+
+    let response = client.get(&history_url).send().await.expect("Call failed");
+    assert_eq!(response.status().as_u16(), 401);
+
+    let response = client
+        .get(&history_url)
+        .bearer_auth(ADMIN_API_KEY)
+        .send()
+        .await
+        .expect("Call failed");
+    assert!(response.status().is_success(), "Failed to fetch history");
+
+    let body: Value = response.json().await.expect("Invalid response body");
+    assert_eq!(body["status"], "SUCCESS");
+    let body: NotificationHistoryResponse =
+        serde_json::from_value(body).expect("Invalid response body");
+    assert_eq!(body.client_id, random_client_id);
+    assert_eq!(body.delivery_status, NotificationStatus::Delivered);
+    assert_eq!(
+        body.history
+            .iter()
+            .map(|received| received.payload.blob.clone())
+            .collect::<Vec<_>>(),
+        blobs.to_vec()
+    );
+    assert!(body.history[0].received_at <= body.history[1].received_at);
+}
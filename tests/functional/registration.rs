@@ -1,8 +1,23 @@
 use {
-    crate::context::SingleTenantServerContext,
-    echo_server::handlers::register_client::RegisterBody,
+    crate::context::{
+        MultiTenantServerContext,
+        SingleTenantServerContext,
+        ADMIN_API_KEY,
+        DATABASE_URL,
+        TENANT_SUBDOMAIN_HOST,
+    },
+    echo_server::{
+        handlers::{
+            create_tenant::{TenantRegisterBody, TenantRegisterResponse},
+            push_message::{MessagePayload, PushMessageBody},
+            register_client::RegisterBody,
+        },
+        middleware::resolve_tenant::{TENANT_CLIENT_KEY_HEADER_NAME, TENANT_ID_HEADER_NAME},
+        stores::client::{ClientStore, DeviceMetadata, DEVICE_METADATA_MAX_LENGTH},
+    },
     random_string::generate,
     test_context::test_context,
+    uuid::Uuid,
 };
 
 #[test_context(SingleTenantServerContext)]
@@ -14,6 +29,8 @@ async fn test_registration(ctx: &mut SingleTenantServerContext) {
         client_id: random_client_id.clone(),
         push_type: "noop".to_string(),
         token: "test".to_string(),
+        device_id: None,
+        metadata: None,
     };
 
     // Register client
@@ -35,6 +52,8 @@ async fn test_registration(ctx: &mut SingleTenantServerContext) {
         client_id: random_client_id,
         push_type: "noop".to_string(),
         token: "new_token".to_string(),
+        device_id: None,
+        metadata: None,
     };
     let response = client
         .post(format!("http://{}/clients", ctx.server.public_addr))
@@ -49,6 +68,37 @@ async fn test_registration(ctx: &mut SingleTenantServerContext) {
     );
 }
 
+#[test_context(SingleTenantServerContext)]
+#[tokio::test]
+async fn test_registration_metadata_is_limited(ctx: &mut SingleTenantServerContext) {
+    let charset = "1234567890";
+    let payload = RegisterBody {
+        client_id: generate(12, charset),
+        push_type: "noop".to_string(),
+        token: "test".to_string(),
+        device_id: None,
+        metadata: Some(DeviceMetadata {
+            sdk_name: Some("a".repeat(DEVICE_METADATA_MAX_LENGTH + 1)),
+            ..Default::default()
+        }),
+    };
+
+    let response = reqwest::Client::new()
+        .post(format!("http://{}/clients", ctx.server.public_addr))
+        .json(&payload)
+        .send()
+        .await
+        .expect("Call failed");
+
+    assert_eq!(response.status().as_u16(), 400);
+    let body = response
+        .json::<serde_json::Value>()
+        .await
+        .expect("Failed to parse body");
+    assert_eq!(body["errors"][0]["code"], "field_too_long");
+    assert_eq!(body["fields"][0]["field"], "metadata.sdk_name");
+}
+
 #[test_context(SingleTenantServerContext)]
 #[tokio::test]
 async fn test_deregistration(ctx: &mut SingleTenantServerContext) {
@@ -58,6 +108,8 @@ async fn test_deregistration(ctx: &mut SingleTenantServerContext) {
         client_id: random_client_id.clone(),
         push_type: "noop".to_string(),
         token: "test".to_string(),
+        device_id: None,
+        metadata: None,
     };
 
     let client = reqwest::Client::new();
@@ -88,3 +140,321 @@ async fn test_deregistration(ctx: &mut SingleTenantServerContext) {
         "Failed to unregister client"
     );
 }
+
+#[test_context(SingleTenantServerContext)]
+#[tokio::test]
+async fn test_multiple_devices(ctx: &mut SingleTenantServerContext) {
+    let charset = "1234567890";
+    let random_client_id = generate(12, charset);
+    let client = reqwest::Client::new();
+
+    // Register two devices for the same client
+    for device_id in ["phone", "tablet"] {
+        let payload = RegisterBody {
+            client_id: random_client_id.clone(),
+            push_type: "noop".to_string(),
+            token: format!("{device_id}-token"),
+            device_id: Some(device_id.to_string()),
+            metadata: None,
+        };
+
+        let response = client
+            .post(format!("http://{}/clients", ctx.server.public_addr))
+            .json(&payload)
+            .send()
+            .await
+            .expect("Call failed");
+
+        assert!(
+            response.status().is_success(),
+            "Failed to register device {device_id}"
+        );
+    }
+
+    // Push to every device
+    let payload = PushMessageBody {
+        id: Uuid::new_v4().to_string(),
+        payload: MessagePayload {
+            topic: Some(Uuid::new_v4().to_string()),
+            blob: Uuid::new_v4().to_string(),
+            flags: 0,
+        },
+    };
+    let response = client
+        .post(format!(
+            "http://{}/clients/{}",
+            ctx.server.public_addr, random_client_id
+        ))
+        .json(&payload)
+        .send()
+        .await
+        .expect("Call failed");
+
+    assert_eq!(
+        response.status().as_u16(),
+        202,
+        "Response was not successful"
+    );
+
+    // Remove a single device
+    let delete_response = client
+        .delete(format!(
+            "http://{}/clients/{}/devices/{}",
+            ctx.server.public_addr, random_client_id, "tablet"
+        ))
+        .send()
+        .await
+        .expect("Call failed");
+
+    assert!(
+        delete_response.status().is_success(),
+        "Failed to delete device"
+    );
+
+    // The device is gone, so removing it again is a not found
+    let delete_response = client
+        .delete(format!(
+            "http://{}/clients/{}/devices/{}",
+            ctx.server.public_addr, random_client_id, "tablet"
+        ))
+        .send()
+        .await
+        .expect("Call failed");
+
+    assert_eq!(
+        delete_response.status().as_u16(),
+        404,
+        "Removing a missing device should be a not found"
+    );
+
+    // Remove the client and all remaining devices
+    let delete_response = client
+        .delete(format!(
+            "http://{}/clients/{}",
+            ctx.server.public_addr, random_client_id
+        ))
+        .send()
+        .await
+        .expect("Call failed");
+
+    assert!(
+        delete_response.status().is_success(),
+        "Failed to unregister client"
+    );
+}
+
+#[test_context(MultiTenantServerContext)]
+#[tokio::test]
+async fn test_registration_is_tenant_scoped(ctx: &mut MultiTenantServerContext) {
+    let charset = "1234567890";
+    let client = reqwest::Client::new();
+
+    let tenant_ids = [generate(12, charset), generate(12, charset)];
+    for tenant_id in &tenant_ids {
+        let response = client
+            .post(format!("http://{}/tenants", ctx.server.public_addr))
+            .bearer_auth(ADMIN_API_KEY)
+            .json(&TenantRegisterBody {
+                id: tenant_id.clone(),
+            })
+            .send()
+            .await
+            .expect("Call failed");
+
+        assert!(
+            response.status().is_success(),
+            "Failed to create tenant {tenant_id}"
+        );
+    }
+
+    // Both tenants register the same client id with their own tokens
+    let random_client_id = generate(12, charset);
+    for tenant_id in &tenant_ids {
+        let payload = RegisterBody {
+            client_id: random_client_id.clone(),
+            push_type: "noop".to_string(),
+            token: format!("{tenant_id}-token"),
+            device_id: None,
+            metadata: None,
+        };
+
+        let response = client
+            .post(format!(
+                "http://{}/{}/clients",
+                ctx.server.public_addr, tenant_id
+            ))
+            .json(&payload)
+            .send()
+            .await
+            .expect("Call failed");
+
+        assert!(
+            response.status().is_success(),
+            "Failed to register client for tenant {tenant_id}"
+        );
+    }
+
+    let store = sqlx::PgPool::connect(DATABASE_URL)
+        .await
+        .expect("failed to connect to postgres");
+
+    // The second registration must not take over the first tenant's client
+    for tenant_id in &tenant_ids {
+        let registered = store
+            .get_client(tenant_id, &random_client_id)
+            .await
+            .expect("client should exist for both tenants");
+        assert_eq!(registered.devices.len(), 1);
+        assert_eq!(registered.devices[0].token, format!("{tenant_id}-token"));
+    }
+
+    // Deleting the client for one tenant leaves the other untouched
+    let response = client
+        .delete(format!(
+            "http://{}/{}/clients/{}",
+            ctx.server.public_addr, tenant_ids[1], random_client_id
+        ))
+        .send()
+        .await
+        .expect("Call failed");
+
+    assert!(
+        response.status().is_success(),
+        "Failed to unregister client"
+    );
+
+    assert!(store
+        .get_client(&tenant_ids[0], &random_client_id)
+        .await
+        .is_ok());
+    assert!(store
+        .get_client(&tenant_ids[1], &random_client_id)
+        .await
+        .is_err());
+
+    store.close().await;
+}
+
+#[test_context(MultiTenantServerContext)]
+#[tokio::test]
+async fn test_tenant_resolvers(ctx: &mut MultiTenantServerContext) {
+    let charset = "1234567890";
+    let client = reqwest::Client::new();
+    let clients_url = format!("http://{}/clients", ctx.server.public_addr);
+
+    let tenant_id = generate(12, charset);
+    let response = client
+        .post(format!("http://{}/tenants", ctx.server.public_addr))
+        .bearer_auth(ADMIN_API_KEY)
+        .json(&TenantRegisterBody {
+            id: tenant_id.clone(),
+        })
+        .send()
+        .await
+        .expect("Call failed");
+    assert!(response.status().is_success(), "Failed to create tenant");
+    let tenant: TenantRegisterResponse = response.json().await.expect("Invalid response body");
+
+    let register = |client_id: &str| RegisterBody {
+        client_id: client_id.to_string(),
+        push_type: "noop".to_string(),
+        token: "token".to_string(),
+        device_id: None,
+        metadata: None,
+    };
+
+    // Without a tenant ID in the path one of the resolvers must match
+    let response = client
+        .post(&clients_url)
+        .json(&register("unresolved"))
+        .send()
+        .await
+        .expect("Call failed");
+    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+
+    let header_client_id = generate(12, charset);
+    let response = client
+        .post(&clients_url)
+        .header(TENANT_ID_HEADER_NAME, &tenant_id)
+        .json(&register(&header_client_id))
+        .send()
+        .await
+        .expect("Call failed");
+    assert!(response.status().is_success(), "Header resolver failed");
+
+    let subdomain_client_id = generate(12, charset);
+    let response = client
+        .post(&clients_url)
+        .header(
+            reqwest::header::HOST,
+            format!("{tenant_id}.{TENANT_SUBDOMAIN_HOST}"),
+        )
+        .json(&register(&subdomain_client_id))
+        .send()
+        .await
+        .expect("Call failed");
+    assert!(response.status().is_success(), "Subdomain resolver failed");
+
+    let client_key_client_id = generate(12, charset);
+    let response = client
+        .post(&clients_url)
+        .header(TENANT_CLIENT_KEY_HEADER_NAME, &tenant.client_key)
+        .json(&register(&client_key_client_id))
+        .send()
+        .await
+        .expect("Call failed");
+    assert!(response.status().is_success(), "Client key resolver failed");
+
+    // Unknown keys, including the tenant's API key, are rejected
+    for unknown_key in [tenant.api_key.as_str(), "pk_unknown"] {
+        let response = client
+            .post(&clients_url)
+            .header(TENANT_CLIENT_KEY_HEADER_NAME, unknown_key)
+            .json(&register(&generate(12, charset)))
+            .send()
+            .await
+            .expect("Call failed");
+        assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+    }
+
+    let store = sqlx::PgPool::connect(DATABASE_URL)
+        .await
+        .expect("failed to connect to postgres");
+    for client_id in [
+        &header_client_id,
+        &subdomain_client_id,
+        &client_key_client_id,
+    ] {
+        assert!(store.get_client(&tenant_id, client_id).await.is_ok());
+    }
+
+    let response = client
+        .post(format!("{clients_url}/{header_client_id}"))
+        .header(TENANT_ID_HEADER_NAME, &tenant_id)
+        .json(&PushMessageBody {
+            id: Uuid::new_v4().to_string(),
+            payload: MessagePayload {
+                topic: None,
+                blob: Uuid::new_v4().to_string(),
+                flags: 0,
+            },
+        })
+        .send()
+        .await
+        .expect("Call failed");
+    assert!(response.status().is_success(), "Failed to push");
+
+    let response = client
+        .delete(format!("{clients_url}/{header_client_id}"))
+        .header(TENANT_ID_HEADER_NAME, &tenant_id)
+        .send()
+        .await
+        .expect("Call failed");
+    assert!(response.status().is_success(), "Failed to delete client");
+    assert!(store
+        .get_client(&tenant_id, &header_client_id)
+        .await
+        .is_err());
+
+    store.close().await;
+}
@@ -1,8 +1,26 @@
 use {
-    crate::context::MultiTenantServerContext,
-    echo_server::handlers::create_tenant::TenantRegisterBody,
+    crate::context::{
+        HybridServerContext,
+        MultiTenantServerContext,
+        ADMIN_API_KEY,
+        HYBRID_DEFAULT_TENANT_ID,
+        TENANT_DATABASE_URL,
+    },
+    chrono::{Duration, Utc},
+    echo_server::{
+        handlers::{
+            create_tenant::{TenantRegisterBody, TenantRegisterResponse},
+            get_audit_log::AuditLogResponse,
+            list_tenants::ListTenantsResponse,
+            push_message::{MessagePayload, PushMessageBody},
+            register_client::RegisterBody,
+        },
+        stores::tenant::{TenantAuditAction, AUDIT_REDACTED},
+    },
     random_string::generate,
+    serde_json::{json, Value},
     test_context::test_context,
+    uuid::Uuid,
 };
 
 #[test_context(MultiTenantServerContext)]
@@ -14,7 +32,7 @@ async fn test_tenant(ctx: &mut MultiTenantServerContext) {
         id: random_tenant_id.clone(),
     };
 
-    // Register tenant
+    // Register tenant, which only operators can do
     let client = reqwest::Client::new();
     let response = client
         .post(format!("http://{}/tenants", ctx.server.public_addr))
@@ -22,6 +40,15 @@ async fn test_tenant(ctx: &mut MultiTenantServerContext) {
         .send()
         .await
         .expect("Call failed");
+    assert_eq!(response.status().as_u16(), 401);
+
+    let response = client
+        .post(format!("http://{}/tenants", ctx.server.public_addr))
+        .bearer_auth(ADMIN_API_KEY)
+        .json(&payload)
+        .send()
+        .await
+        .expect("Call failed");
 
     assert!(
         response.status().is_success(),
@@ -54,12 +81,20 @@ async fn test_tenant(ctx: &mut MultiTenantServerContext) {
     assert_eq!(allowed_origins.to_str().unwrap(), "*");
 
     // Delete tenant
+    let tenant_url = format!(
+        "http://{}/tenants/{}",
+        ctx.server.public_addr, random_tenant_id
+    );
     let response = client
-        .delete(format!(
-            "http://{}/tenants/{}",
-            ctx.server.public_addr,
-            random_tenant_id.clone()
-        ))
+        .delete(&tenant_url)
+        .send()
+        .await
+        .expect("Call failed");
+    assert_eq!(response.status().as_u16(), 401);
+
+    let response = client
+        .delete(&tenant_url)
+        .bearer_auth(ADMIN_API_KEY)
         .send()
         .await
         .expect("Call failed");
@@ -86,4 +121,842 @@ async fn test_tenant(ctx: &mut MultiTenantServerContext) {
         400,
         "Response was not successful"
     );
+
+    // Deleted tenants can be restored by operators during the grace period
+    let restore_url = format!(
+        "http://{}/tenants/{}/restore",
+        ctx.server.public_addr, random_tenant_id
+    );
+    let response = client.post(&restore_url).send().await.expect("Call failed");
+    assert_eq!(response.status().as_u16(), 401);
+
+    let response = client
+        .post(&restore_url)
+        .bearer_auth(ADMIN_API_KEY)
+        .send()
+        .await
+        .expect("Call failed");
+    assert!(response.status().is_success(), "Failed to restore tenant");
+
+    let response = client
+        .get(format!(
+            "http://{}/tenants/{}",
+            ctx.server.public_addr, random_tenant_id
+        ))
+        .send()
+        .await
+        .expect("Call failed");
+    assert!(response.status().is_success(), "Tenant wasn't restored");
+}
+
+#[test_context(MultiTenantServerContext)]
+#[tokio::test]
+async fn test_list_tenants(ctx: &mut MultiTenantServerContext) {
+    let charset = "1234567890";
+    let started_at = Utc::now() - Duration::seconds(1);
+    let client = reqwest::Client::new();
+
+    let mut tenant_ids = vec![];
+    let mut api_key = String::new();
+    for _ in 0..2 {
+        let random_tenant_id = generate(12, charset);
+        let response = client
+            .post(format!("http://{}/tenants", ctx.server.public_addr))
+            .bearer_auth(ADMIN_API_KEY)
+            .json(&TenantRegisterBody {
+                id: random_tenant_id.clone(),
+            })
+            .send()
+            .await
+            .expect("Call failed");
+        assert!(response.status().is_success(), "Failed to create tenant");
+        let tenant: TenantRegisterResponse = response.json().await.expect("Invalid response body");
+        api_key = tenant.api_key;
+        tenant_ids.push(random_tenant_id);
+    }
+
+    let response = client
+        .post(format!(
+            "http://{}/{}/clients",
+            ctx.server.public_addr, tenant_ids[0]
+        ))
+        .json(&RegisterBody {
+            client_id: generate(12, charset),
+            push_type: "noop".to_string(),
+            token: "test".to_string(),
+            device_id: None,
+            metadata: None,
+        })
+        .send()
+        .await
+        .expect("Call failed");
+    assert!(response.status().is_success(), "Failed to register client");
+
+    // Only operators can list tenants
+    let response = client
+        .get(format!("http://{}/tenants", ctx.server.public_addr))
+        .bearer_auth(&api_key)
+        .send()
+        .await
+        .expect("Call failed");
+    assert_eq!(response.status().as_u16(), 403);
+
+    let mut tenants = vec![];
+    let mut cursor: Option<String> = None;
+    loop {
+        let mut query = vec![
+            ("limit", "1".to_string()),
+            ("created_after", started_at.to_rfc3339()),
+        ];
+        if let Some(cursor) = &cursor {
+            query.push(("cursor", cursor.clone()));
+        }
+
+        let response = client
+            .get(format!("http://{}/tenants", ctx.server.public_addr))
+            .bearer_auth(ADMIN_API_KEY)
+            .query(&query)
+            .send()
+            .await
+            .expect("Call failed");
+        assert!(response.status().is_success(), "Failed to list tenants");
+
+        let page: ListTenantsResponse = response.json().await.expect("Invalid response body");
+        assert!(page.tenants.len() <= 1);
+        tenants.extend(page.tenants);
+
+        cursor = page.next_cursor;
+        if cursor.is_none() {
+            break;
+        }
+    }
+
+    let first = tenants
+        .iter()
+        .find(|tenant| tenant.id == tenant_ids[0])
+        .expect("tenant missing from list");
+    assert_eq!(first.client_count, 1);
+    let second = tenants
+        .iter()
+        .find(|tenant| tenant.id == tenant_ids[1])
+        .expect("tenant missing from list");
+    assert_eq!(second.client_count, 0);
+
+    // Neither tenant has FCM configured
+    let response = client
+        .get(format!("http://{}/tenants", ctx.server.public_addr))
+        .bearer_auth(ADMIN_API_KEY)
+        .query(&[
+            ("provider", "fcm".to_string()),
+            ("created_after", started_at.to_rfc3339()),
+        ])
+        .send()
+        .await
+        .expect("Call failed");
+    assert!(response.status().is_success(), "Failed to list tenants");
+    let page: ListTenantsResponse = response.json().await.expect("Invalid response body");
+    assert!(page
+        .tenants
+        .iter()
+        .all(|tenant| !tenant_ids.contains(&tenant.id)));
+}
+
+#[test_context(MultiTenantServerContext)]
+#[tokio::test]
+async fn test_remove_tenant_provider(ctx: &mut MultiTenantServerContext) {
+    let charset = "1234567890";
+    let random_tenant_id = generate(12, charset);
+    let random_client_id = generate(12, charset);
+    let client = reqwest::Client::new();
+
+    let response = client
+        .post(format!("http://{}/tenants", ctx.server.public_addr))
+        .bearer_auth(ADMIN_API_KEY)
+        .json(&TenantRegisterBody {
+            id: random_tenant_id.clone(),
+        })
+        .send()
+        .await
+        .expect("Call failed");
+    assert!(response.status().is_success(), "Failed to create tenant");
+    let tenant: TenantRegisterResponse = response.json().await.expect("Invalid response body");
+
+    let pool = sqlx::PgPool::connect(TENANT_DATABASE_URL)
+        .await
+        .expect("failed to connect to postgres");
+    sqlx::query("UPDATE public.tenants SET fcm_api_key = 'test-api-key' WHERE id = $1")
+        .bind(&random_tenant_id)
+        .execute(&pool)
+        .await
+        .expect("update failed");
+
+    let response = client
+        .post(format!(
+            "http://{}/{}/clients",
+            ctx.server.public_addr, random_tenant_id
+        ))
+        .json(&RegisterBody {
+            client_id: random_client_id.clone(),
+            push_type: "fcm".to_string(),
+            token: "test".to_string(),
+            device_id: None,
+            metadata: None,
+        })
+        .send()
+        .await
+        .expect("Call failed");
+    assert!(response.status().is_success(), "Failed to register client");
+
+    // Removing a provider requires the tenant's API key
+    let fcm_url = format!(
+        "http://{}/tenants/{}/fcm",
+        ctx.server.public_addr, random_tenant_id
+    );
+    let response = client.delete(&fcm_url).send().await.expect("Call failed");
+    assert_eq!(response.status().as_u16(), 401);
+
+    let response = client
+        .delete(&fcm_url)
+        .bearer_auth("not-the-api-key")
+        .send()
+        .await
+        .expect("Call failed");
+    assert_eq!(response.status().as_u16(), 403);
+
+    let response = client
+        .delete(&fcm_url)
+        .bearer_auth(&tenant.api_key)
+        .send()
+        .await
+        .expect("Call failed");
+    assert!(response.status().is_success(), "Failed to remove FCM");
+
+    // Pushes to clients registered for the removed provider are rejected
+    let response = client
+        .post(format!(
+            "http://{}/{}/clients/{}",
+            ctx.server.public_addr, random_tenant_id, random_client_id
+        ))
+        .json(&PushMessageBody {
+            id: Uuid::new_v4().to_string(),
+            payload: MessagePayload {
+                topic: None,
+                blob: Uuid::new_v4().to_string(),
+                flags: 0,
+            },
+        })
+        .send()
+        .await
+        .expect("Call failed");
+    assert_eq!(response.status().as_u16(), 400);
+    let body = response.text().await.expect("Invalid response body");
+    assert!(body.contains("provider_not_available"));
+
+    // Removing APNs when it was never configured is a no-op
+    let response = client
+        .delete(format!(
+            "http://{}/tenants/{}/apns",
+            ctx.server.public_addr, random_tenant_id
+        ))
+        .bearer_auth(&tenant.api_key)
+        .send()
+        .await
+        .expect("Call failed");
+    assert!(response.status().is_success(), "Failed to remove APNs");
+}
+
+#[test_context(MultiTenantServerContext)]
+#[tokio::test]
+async fn test_update_tenant_providers_json(ctx: &mut MultiTenantServerContext) {
+    let charset = "1234567890";
+    let random_tenant_id = generate(12, charset);
+    let client = reqwest::Client::new();
+
+    let response = client
+        .post(format!("http://{}/tenants", ctx.server.public_addr))
+        .bearer_auth(ADMIN_API_KEY)
+        .json(&TenantRegisterBody {
+            id: random_tenant_id.clone(),
+        })
+        .send()
+        .await
+        .expect("Call failed");
+    assert!(response.status().is_success(), "Failed to create tenant");
+    let tenant: TenantRegisterResponse = response.json().await.expect("Invalid response body");
+
+    let tenant_url = format!(
+        "http://{}/tenants/{}",
+        ctx.server.public_addr, random_tenant_id
+    );
+    let invalid_fields = |body: Value| -> Vec<String> {
+        body["fields"]
+            .as_array()
+            .expect("missing fields")
+            .iter()
+            .map(|field| field["field"].as_str().unwrap().to_string())
+            .collect()
+    };
+
+    // Providers can only be set with the tenant's API key
+    let response = client
+        .post(format!("{tenant_url}/fcm"))
+        .json(&json!({ "api_key": "test-api-key" }))
+        .send()
+        .await
+        .expect("Call failed");
+    assert_eq!(response.status().as_u16(), 401);
+
+    // FCM
+    let response = client
+        .post(format!("{tenant_url}/fcm"))
+        .bearer_auth(&tenant.api_key)
+        .json(&json!({}))
+        .send()
+        .await
+        .expect("Call failed");
+    assert_eq!(response.status().as_u16(), 400);
+    let body: Value = response.json().await.expect("Invalid response body");
+    assert_eq!(invalid_fields(body), vec!["api_key"]);
+
+    let response = client
+        .post(format!("{tenant_url}/fcm"))
+        .bearer_auth(&tenant.api_key)
+        .json(&json!({ "api_key": "test-api-key" }))
+        .send()
+        .await
+        .expect("Call failed");
+    assert!(response.status().is_success(), "Failed to update FCM");
+
+    // APNs
+    let response = client
+        .post(format!("{tenant_url}/apns"))
+        .bearer_auth(&tenant.api_key)
+        .json(&json!({
+            "apns_topic": "com.walletconnect.exampleapp",
+            "apns_certificate": "Y2VydGlmaWNhdGU=",
+        }))
+        .send()
+        .await
+        .expect("Call failed");
+    assert_eq!(response.status().as_u16(), 400);
+    let body: Value = response.json().await.expect("Invalid response body");
+    assert_eq!(invalid_fields(body), vec!["apns_certificate_password"]);
+
+    let response = client
+        .post(format!("{tenant_url}/apns"))
+        .bearer_auth(&tenant.api_key)
+        .json(&json!({
+            "apns_certificate": "not base64!",
+            "apns_certificate_password": "password123",
+        }))
+        .send()
+        .await
+        .expect("Call failed");
+    assert_eq!(response.status().as_u16(), 400);
+    let body: Value = response.json().await.expect("Invalid response body");
+    assert_eq!(invalid_fields(body), vec!["apns_certificate"]);
+
+    let response = client
+        .post(format!("{tenant_url}/apns"))
+        .bearer_auth(&tenant.api_key)
+        .json(&json!({
+            "apns_topic": "com.walletconnect.exampleapp",
+            "apns_certificate": "Y2VydGlmaWNhdGU=",
+            "apns_certificate_password": "password123",
+        }))
+        .send()
+        .await
+        .expect("Call failed");
+    assert!(response.status().is_success(), "Failed to update APNs");
+
+    let response = client
+        .post(format!("{tenant_url}/apns"))
+        .bearer_auth(&tenant.api_key)
+        .header("Content-Type", "text/plain")
+        .body("apns_topic=com.walletconnect.exampleapp")
+        .send()
+        .await
+        .expect("Call failed");
+    assert_eq!(response.status().as_u16(), 415);
+
+    let response = client.get(&tenant_url).send().await.expect("Call failed");
+    let body: Value = response.json().await.expect("Invalid response body");
+    let providers = body["enabled_providers"]
+        .as_array()
+        .expect("missing providers");
+    assert!(providers.contains(&json!("fcm")));
+    assert!(providers.contains(&json!("apns")));
+    assert_eq!(body["apns_type"], json!("Certificate"));
+}
+
+#[test_context(MultiTenantServerContext)]
+#[tokio::test]
+async fn test_tenant_audit_log(ctx: &mut MultiTenantServerContext) {
+    let charset = "1234567890";
+    let random_tenant_id = generate(12, charset);
+    let client = reqwest::Client::new();
+
+    let response = client
+        .post(format!("http://{}/tenants", ctx.server.public_addr))
+        .bearer_auth(ADMIN_API_KEY)
+        .json(&TenantRegisterBody {
+            id: random_tenant_id.clone(),
+        })
+        .send()
+        .await
+        .expect("Call failed");
+    assert!(response.status().is_success(), "Failed to create tenant");
+    let tenant: TenantRegisterResponse = response.json().await.expect("Invalid response body");
+
+    let tenant_url = format!(
+        "http://{}/tenants/{}",
+        ctx.server.public_addr, random_tenant_id
+    );
+    let response = client
+        .post(format!("{tenant_url}/fcm"))
+        .bearer_auth(&tenant.api_key)
+        .header("x-request-id", "audit-test-request")
+        .json(&json!({ "api_key": "secret-fcm-api-key" }))
+        .send()
+        .await
+        .expect("Call failed");
+    assert!(response.status().is_success(), "Failed to update FCM");
+    assert_eq!(
+        response.headers().get("x-request-id").unwrap(),
+        "audit-test-request"
+    );
+
+    let response = client
+        .post(format!("{tenant_url}/apns"))
+        .bearer_auth(ADMIN_API_KEY)
+        .json(&json!({
+            "apns_topic": "com.walletconnect.exampleapp",
+            "apns_certificate": "Y2VydGlmaWNhdGU=",
+            "apns_certificate_password": "secret-password",
+        }))
+        .send()
+        .await
+        .expect("Call failed");
+    assert!(response.status().is_success(), "Failed to update APNs");
+
+    // The log requires auth
+    let response = client
+        .get(format!("{tenant_url}/audit"))
+        .send()
+        .await
+        .expect("Call failed");
+    assert_eq!(response.status().as_u16(), 401);
+
+    let mut entries = vec![];
+    let mut cursor: Option<String> = None;
+    loop {
+        let mut query = vec![("limit", "1".to_string())];
+        if let Some(cursor) = &cursor {
+            query.push(("cursor", cursor.clone()));
+        }
+
+        let response = client
+            .get(format!("{tenant_url}/audit"))
+            .bearer_auth(&tenant.api_key)
+            .query(&query)
+            .send()
+            .await
+            .expect("Call failed");
+        assert!(response.status().is_success(), "Failed to get audit log");
+        let text = response.text().await.expect("Invalid response body");
+        assert!(!text.contains("secret"), "Secrets weren't redacted");
+
+        let page: AuditLogResponse = serde_json::from_str(&text).expect("Invalid response body");
+        entries.extend(page.entries);
+
+        cursor = page.next_cursor;
+        if cursor.is_none() {
+            break;
+        }
+    }
+
+    let actions = entries.iter().map(|entry| entry.action).collect::<Vec<_>>();
+    assert_eq!(actions, vec![
+        TenantAuditAction::UpdateApnsAuth,
+        TenantAuditAction::UpdateApns,
+        TenantAuditAction::UpdateFcm,
+        TenantAuditAction::CreateTenant,
+    ]);
+    assert_eq!(entries[0].actor, "admin");
+    assert_eq!(entries[0].changes["apns_certificate"], AUDIT_REDACTED);
+    assert_eq!(
+        entries[1].changes["apns_topic"],
+        "com.walletconnect.exampleapp"
+    );
+    assert_eq!(entries[2].actor, format!("tenant:{random_tenant_id}"));
+    assert_eq!(entries[2].request_id, "audit-test-request");
+    assert_eq!(entries[2].changes["fcm_api_key"], AUDIT_REDACTED);
+    assert_eq!(entries[3].actor, "admin");
+
+    // Operators can still read the log once the tenant is deleted
+    let response = client
+        .delete(&tenant_url)
+        .bearer_auth(ADMIN_API_KEY)
+        .send()
+        .await
+        .expect("Call failed");
+    assert!(response.status().is_success(), "Failed to delete tenant");
+
+    let response = client
+        .get(format!("{tenant_url}/audit"))
+        .bearer_auth(ADMIN_API_KEY)
+        .send()
+        .await
+        .expect("Call failed");
+    assert!(response.status().is_success(), "Failed to get audit log");
+    let page: AuditLogResponse = response.json().await.expect("Invalid response body");
+    assert_eq!(page.entries.len(), 5);
+    assert_eq!(page.entries[0].action, TenantAuditAction::DeleteTenant);
+    assert_eq!(page.entries[0].actor, "admin");
+}
+
+#[test_context(MultiTenantServerContext)]
+#[tokio::test]
+async fn test_tenant_id_validation(ctx: &mut MultiTenantServerContext) {
+    let client = reqwest::Client::new();
+
+    for invalid_id in [
+        "ab",
+        "health",
+        "Tenants",
+        "-sdk",
+        "swift sdk",
+        "v12",
+        "V300",
+        &"a".repeat(65),
+    ] {
+        let response = client
+            .post(format!("http://{}/tenants", ctx.server.public_addr))
+            .bearer_auth(ADMIN_API_KEY)
+            .json(&TenantRegisterBody {
+                id: invalid_id.to_string(),
+            })
+            .send()
+            .await
+            .expect("Call failed");
+        assert_eq!(
+            response.status(),
+            reqwest::StatusCode::BAD_REQUEST,
+            "{invalid_id} should be rejected"
+        );
+    }
+
+    let vanity_id = format!("swift-sdk_{}", generate(8, "1234567890"));
+    for expected_status in [reqwest::StatusCode::OK, reqwest::StatusCode::CONFLICT] {
+        let response = client
+            .post(format!("http://{}/tenants", ctx.server.public_addr))
+            .bearer_auth(ADMIN_API_KEY)
+            .json(&TenantRegisterBody {
+                id: vanity_id.clone(),
+            })
+            .send()
+            .await
+            .expect("Call failed");
+        assert_eq!(response.status(), expected_status);
+    }
+}
+
+#[test_context(MultiTenantServerContext)]
+#[tokio::test]
+async fn test_rename_tenant(ctx: &mut MultiTenantServerContext) {
+    let charset = "1234567890";
+    let tenant_id = generate(12, charset);
+    let new_tenant_id = format!("renamed-{}", generate(12, charset));
+    let client_id = generate(12, charset);
+    let client = reqwest::Client::new();
+
+    let response = client
+        .post(format!("http://{}/tenants", ctx.server.public_addr))
+        .bearer_auth(ADMIN_API_KEY)
+        .json(&TenantRegisterBody {
+            id: tenant_id.clone(),
+        })
+        .send()
+        .await
+        .expect("Call failed");
+    assert!(response.status().is_success(), "Failed to create tenant");
+    let tenant: TenantRegisterResponse = response.json().await.expect("Invalid response body");
+
+    let response = client
+        .post(format!(
+            "http://{}/{}/clients",
+            ctx.server.public_addr, tenant_id
+        ))
+        .json(&RegisterBody {
+            client_id: client_id.clone(),
+            push_type: "noop".to_string(),
+            token: "token".to_string(),
+            device_id: None,
+            metadata: None,
+        })
+        .send()
+        .await
+        .expect("Call failed");
+    assert!(response.status().is_success(), "Failed to register client");
+
+    let rename_url = format!(
+        "http://{}/tenants/{}/rename",
+        ctx.server.public_addr, tenant_id
+    );
+
+    // Requires the tenant's API key
+    let response = client
+        .post(&rename_url)
+        .json(&json!({ "id": new_tenant_id }))
+        .send()
+        .await
+        .expect("Call failed");
+    assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+    let response = client
+        .post(&rename_url)
+        .bearer_auth(&tenant.api_key)
+        .json(&json!({ "id": "clients" }))
+        .send()
+        .await
+        .expect("Call failed");
+    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+
+    let response = client
+        .post(&rename_url)
+        .bearer_auth(&tenant.api_key)
+        .json(&json!({ "id": new_tenant_id }))
+        .send()
+        .await
+        .expect("Call failed");
+    assert!(response.status().is_success(), "Failed to rename tenant");
+
+    let response = client
+        .get(format!(
+            "http://{}/tenants/{}",
+            ctx.server.public_addr, tenant_id
+        ))
+        .send()
+        .await
+        .expect("Call failed");
+    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+
+    // The client moved with the tenant
+    let response = client
+        .get(format!(
+            "http://{}/{}/clients/{}",
+            ctx.server.public_addr, new_tenant_id, client_id
+        ))
+        .bearer_auth(&tenant.api_key)
+        .send()
+        .await
+        .expect("Call failed");
+    assert!(response.status().is_success(), "Client was not moved");
+
+    let response = client
+        .get(format!(
+            "http://{}/tenants/{}/audit",
+            ctx.server.public_addr, new_tenant_id
+        ))
+        .bearer_auth(&tenant.api_key)
+        .send()
+        .await
+        .expect("Call failed");
+    let audit: AuditLogResponse = response.json().await.expect("Invalid response body");
+    assert_eq!(audit.entries[0].action, TenantAuditAction::RenameTenant);
+    assert_eq!(
+        audit.entries.last().unwrap().action,
+        TenantAuditAction::CreateTenant
+    );
+}
+
+#[test_context(HybridServerContext)]
+#[tokio::test]
+async fn test_hybrid_mode(ctx: &mut HybridServerContext) {
+    let charset = "1234567890";
+    let client = reqwest::Client::new();
+
+    let health = reqwest::get(format!("http://{}/health", ctx.server.public_addr))
+        .await
+        .expect("Call failed")
+        .text()
+        .await
+        .expect("Invalid response body");
+    assert!(health.contains("hybrid: true"));
+
+    // The default tenant is served without a tenant ID in the path
+    let default_client_id = generate(12, charset);
+    let response = client
+        .post(format!("http://{}/clients", ctx.server.public_addr))
+        .json(&RegisterBody {
+            client_id: default_client_id.clone(),
+            push_type: "fcm".to_string(),
+            token: "default-token".to_string(),
+            device_id: None,
+            metadata: None,
+        })
+        .send()
+        .await
+        .expect("Call failed");
+    assert!(
+        response.status().is_success(),
+        "Failed to register client for the default tenant"
+    );
+
+    let response = client
+        .get(format!(
+            "http://{}/clients/{}",
+            ctx.server.public_addr, default_client_id
+        ))
+        .bearer_auth(ADMIN_API_KEY)
+        .send()
+        .await
+        .expect("Call failed");
+    assert!(response.status().is_success(), "Failed to get client");
+
+    // Hosted tenants are served alongside it
+    let tenant_id = generate(12, charset);
+    let response = client
+        .post(format!("http://{}/tenants", ctx.server.public_addr))
+        .bearer_auth(ADMIN_API_KEY)
+        .json(&TenantRegisterBody {
+            id: tenant_id.clone(),
+        })
+        .send()
+        .await
+        .expect("Call failed");
+    assert!(response.status().is_success(), "Failed to create tenant");
+
+    let response = client
+        .post(format!(
+            "http://{}/{}/clients",
+            ctx.server.public_addr, tenant_id
+        ))
+        .json(&RegisterBody {
+            client_id: generate(12, charset),
+            push_type: "noop".to_string(),
+            token: "hosted-token".to_string(),
+            device_id: None,
+            metadata: None,
+        })
+        .send()
+        .await
+        .expect("Call failed");
+    assert!(
+        response.status().is_success(),
+        "Failed to register client for the hosted tenant"
+    );
+
+    // The default tenant can't be taken or changed through the API
+    let response = client
+        .post(format!("http://{}/tenants", ctx.server.public_addr))
+        .bearer_auth(ADMIN_API_KEY)
+        .json(&TenantRegisterBody {
+            id: HYBRID_DEFAULT_TENANT_ID.to_string(),
+        })
+        .send()
+        .await
+        .expect("Call failed");
+    assert_eq!(response.status(), reqwest::StatusCode::CONFLICT);
+
+    let response = client
+        .delete(format!(
+            "http://{}/tenants/{}",
+            ctx.server.public_addr, HYBRID_DEFAULT_TENANT_ID
+        ))
+        .bearer_auth(ADMIN_API_KEY)
+        .send()
+        .await
+        .expect("Call failed");
+    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+    let body: Value = response.json().await.expect("Invalid response body");
+    assert_eq!(body["errors"][0]["name"], "default_tenant_immutable");
+    assert_eq!(body["errors"][0]["code"], "default_tenant_immutable");
+}
+
+#[test_context(MultiTenantServerContext)]
+#[tokio::test]
+async fn test_tenant_response_envelope(ctx: &mut MultiTenantServerContext) {
+    let tenant_id = generate(12, "1234567890");
+    let client = reqwest::Client::new();
+
+    // Payloads are returned alongside the envelope's status
+    let body: Value = client
+        .post(format!("http://{}/v1/tenants", ctx.server.public_addr))
+        .bearer_auth(ADMIN_API_KEY)
+        .json(&TenantRegisterBody {
+            id: tenant_id.clone(),
+        })
+        .send()
+        .await
+        .expect("Call failed")
+        .json()
+        .await
+        .expect("Invalid JSON");
+    assert_eq!(body["status"], "SUCCESS");
+    assert!(body["api_key"].is_string());
+    assert!(body["client_key"].is_string());
+    assert!(body["errors"].is_null());
+
+    // Endpoints without a payload return just the envelope
+    let response = client
+        .delete(format!(
+            "http://{}/v1/tenants/{}",
+            ctx.server.public_addr, tenant_id
+        ))
+        .bearer_auth(ADMIN_API_KEY)
+        .send()
+        .await
+        .expect("Call failed");
+    assert!(response.status().is_success());
+    let body: Value = response.json().await.expect("Invalid JSON");
+    assert_eq!(
+        body,
+        json!({ "status": "SUCCESS", "errors": null, "fields": null })
+    );
+
+    // Errors have a stable code
+    let response = client
+        .get(format!(
+            "http://{}/v1/tenants/{}/usage",
+            ctx.server.public_addr, tenant_id
+        ))
+        .send()
+        .await
+        .expect("Call failed");
+    assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+    let body: Value = response.json().await.expect("Invalid JSON");
+    assert_eq!(body["status"], "FAILURE");
+    assert_eq!(body["errors"][0]["code"], "tenant_auth_failed");
+}
+
+#[test_context(MultiTenantServerContext)]
+#[tokio::test]
+async fn test_cors_any_origin(ctx: &mut MultiTenantServerContext) {
+    let client = reqwest::Client::new();
+
+    // `*` allows any origin on both tenant and client routes
+    for path in ["/v1/tenants", "/v1/tenant-id/clients", "/tenants"] {
+        let response = client
+            .request(
+                reqwest::Method::OPTIONS,
+                format!("http://{}{}", ctx.server.public_addr, path),
+            )
+            .header("Origin", "https://example.com")
+            .header("Access-Control-Request-Method", "POST")
+            .send()
+            .await
+            .expect("Call failed");
+        assert!(
+            response.status().is_success(),
+            "Preflight failed for {path}"
+        );
+        assert_eq!(
+            response
+                .headers()
+                .get("Access-Control-Allow-Origin")
+                .unwrap(),
+            "*"
+        );
+    }
 }
@@ -0,0 +1,263 @@
+use {
+    crate::context::{MultiTenantServerContext, ADMIN_API_KEY},
+    echo_server::{
+        handlers::{
+            create_tenant::{TenantRegisterBody, TenantRegisterResponse},
+            get_notification::NotificationHistoryResponse,
+            get_usage::UsageResponse,
+            push_message::{MessagePayload, PushMessageBody},
+            register_client::RegisterBody,
+            update_quota::QuotaUpdateBody,
+            update_rate_limit::RateLimitUpdateBody,
+        },
+        stores::notification::NotificationStatus,
+    },
+    random_string::generate,
+    std::num::NonZeroU32,
+    test_context::test_context,
+    uuid::Uuid,
+};
+
+#[test_context(MultiTenantServerContext)]
+#[tokio::test]
+async fn test_usage_and_quota(ctx: &mut MultiTenantServerContext) {
+    let charset = "1234567890";
+    let random_tenant_id = generate(12, charset);
+    let random_client_id = generate(12, charset);
+    let client = reqwest::Client::new();
+
+    let response = client
+        .post(format!("http://{}/tenants", ctx.server.public_addr))
+        .bearer_auth(ADMIN_API_KEY)
+        .json(&TenantRegisterBody {
+            id: random_tenant_id.clone(),
+        })
+        .send()
+        .await
+        .expect("Call failed");
+    assert!(response.status().is_success(), "Failed to create tenant");
+    let tenant: TenantRegisterResponse = response.json().await.expect("Invalid response body");
+
+    let response = client
+        .post(format!(
+            "http://{}/{}/clients",
+            ctx.server.public_addr, random_tenant_id
+        ))
+        .json(&RegisterBody {
+            client_id: random_client_id.clone(),
+            push_type: "noop".to_string(),
+            token: "test".to_string(),
+            device_id: None,
+            metadata: None,
+        })
+        .send()
+        .await
+        .expect("Call failed");
+    assert!(response.status().is_success(), "Failed to register client");
+
+    let push_url = format!(
+        "http://{}/{}/clients/{}",
+        ctx.server.public_addr, random_tenant_id, random_client_id
+    );
+    let push = || PushMessageBody {
+        id: Uuid::new_v4().to_string(),
+        payload: MessagePayload {
+            topic: None,
+            blob: Uuid::new_v4().to_string(),
+            flags: 0,
+        },
+    };
+
+    let response = client
+        .post(&push_url)
+        .json(&push())
+        .send()
+        .await
+        .expect("Call failed");
+    assert_eq!(response.status().as_u16(), 202);
+
+    let usage_url = format!(
+        "http://{}/tenants/{}/usage",
+        ctx.server.public_addr, random_tenant_id
+    );
+    let response = client
+        .get(&usage_url)
+        .bearer_auth(&tenant.api_key)
+        .send()
+        .await
+        .expect("Call failed");
+    assert!(response.status().is_success(), "Failed to fetch usage");
+    let usage: UsageResponse = response.json().await.expect("Invalid response body");
+    assert_eq!(usage.totals.received_notifications, 1);
+    assert_eq!(usage.totals.delivered_notifications, 1);
+    assert_eq!(usage.totals.registrations, 1);
+    assert_eq!(usage.registered_clients, 1);
+    assert_eq!(usage.usage.len(), 1);
+    assert_eq!(usage.monthly_notification_quota, None);
+
+    let response = client
+        .get(format!("{usage_url}?from=2023-02-02&to=2023-02-01"))
+        .bearer_auth(&tenant.api_key)
+        .send()
+        .await
+        .expect("Call failed");
+    assert_eq!(response.status().as_u16(), 400);
+
+    // Only operators can set quotas, the notification already delivered this
+    // month uses it up
+    let quota_url = format!(
+        "http://{}/tenants/{}/quota",
+        ctx.server.public_addr, random_tenant_id
+    );
+    let quota = QuotaUpdateBody {
+        monthly_notification_quota: Some(1),
+    };
+    let response = client
+        .post(&quota_url)
+        .bearer_auth(&tenant.api_key)
+        .json(&quota)
+        .send()
+        .await
+        .expect("Call failed");
+    assert_eq!(response.status().as_u16(), 403);
+
+    let response = client
+        .post(&quota_url)
+        .bearer_auth(ADMIN_API_KEY)
+        .json(&quota)
+        .send()
+        .await
+        .expect("Call failed");
+    assert!(response.status().is_success(), "Failed to set quota");
+
+    let rejected = push();
+    let response = client
+        .post(&push_url)
+        .json(&rejected)
+        .send()
+        .await
+        .expect("Call failed");
+    assert_eq!(response.status().as_u16(), 429);
+    let body: serde_json::Value = response.json().await.expect("Invalid response body");
+    assert_eq!(body["errors"][0]["name"], "quota_exceeded");
+    assert_eq!(body["errors"][0]["code"], "quota_exceeded");
+
+    // The rejected push isn't left pending
+    let history: NotificationHistoryResponse = client
+        .get(format!(
+            "http://{}/{}/notifications/{}",
+            ctx.server.public_addr, random_tenant_id, rejected.id
+        ))
+        .bearer_auth(&tenant.api_key)
+        .send()
+        .await
+        .expect("Call failed")
+        .json()
+        .await
+        .expect("Invalid response body");
+    assert_eq!(history.delivery_status, NotificationStatus::Failed);
+}
+
+#[test_context(MultiTenantServerContext)]
+#[tokio::test]
+async fn test_tenant_rate_limit(ctx: &mut MultiTenantServerContext) {
+    let charset = "1234567890";
+    let random_tenant_id = generate(12, charset);
+    let random_client_id = generate(12, charset);
+    let client = reqwest::Client::new();
+
+    let response = client
+        .post(format!("http://{}/tenants", ctx.server.public_addr))
+        .bearer_auth(ADMIN_API_KEY)
+        .json(&TenantRegisterBody {
+            id: random_tenant_id.clone(),
+        })
+        .send()
+        .await
+        .expect("Call failed");
+    assert!(response.status().is_success(), "Failed to create tenant");
+    let tenant: TenantRegisterResponse = response.json().await.expect("Invalid response body");
+
+    let response = client
+        .post(format!(
+            "http://{}/{}/clients",
+            ctx.server.public_addr, random_tenant_id
+        ))
+        .json(&RegisterBody {
+            client_id: random_client_id.clone(),
+            push_type: "noop".to_string(),
+            token: "test".to_string(),
+            device_id: None,
+            metadata: None,
+        })
+        .send()
+        .await
+        .expect("Call failed");
+    assert!(response.status().is_success(), "Failed to register client");
+
+    // Only operators can set rate limits, and a limit of zero is rejected
+    let rate_limit_url = format!(
+        "http://{}/tenants/{}/rate-limit",
+        ctx.server.public_addr, random_tenant_id
+    );
+    let rate_limit = RateLimitUpdateBody {
+        push_rate_limit_per_second: NonZeroU32::new(1),
+    };
+    let response = client
+        .post(&rate_limit_url)
+        .bearer_auth(&tenant.api_key)
+        .json(&rate_limit)
+        .send()
+        .await
+        .expect("Call failed");
+    assert_eq!(response.status().as_u16(), 403);
+
+    let response = client
+        .post(&rate_limit_url)
+        .bearer_auth(ADMIN_API_KEY)
+        .json(&serde_json::json!({ "push_rate_limit_per_second": 0 }))
+        .send()
+        .await
+        .expect("Call failed");
+    assert!(response.status().is_client_error());
+
+    let response = client
+        .post(&rate_limit_url)
+        .bearer_auth(ADMIN_API_KEY)
+        .json(&rate_limit)
+        .send()
+        .await
+        .expect("Call failed");
+    assert!(response.status().is_success(), "Failed to set rate limit");
+
+    let push_url = format!(
+        "http://{}/{}/clients/{}",
+        ctx.server.public_addr, random_tenant_id, random_client_id
+    );
+    let push = || PushMessageBody {
+        id: Uuid::new_v4().to_string(),
+        payload: MessagePayload {
+            topic: None,
+            blob: Uuid::new_v4().to_string(),
+            flags: 0,
+        },
+    };
+
+    let response = client
+        .post(&push_url)
+        .json(&push())
+        .send()
+        .await
+        .expect("Call failed");
+    assert_eq!(response.status().as_u16(), 202);
+
+    let response = client
+        .post(&push_url)
+        .json(&push())
+        .send()
+        .await
+        .expect("Call failed");
+    assert_eq!(response.status().as_u16(), 429);
+    let body: serde_json::Value = response.json().await.expect("Invalid response body");
+    assert_eq!(body["errors"][0]["code"], "rate_limited");
+}
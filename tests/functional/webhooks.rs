@@ -0,0 +1,239 @@
+use {
+    crate::context::{MultiTenantServerContext, ADMIN_API_KEY},
+    axum::{extract::State, http::HeaderMap, routing::post, Router},
+    echo_server::{
+        handlers::{
+            create_tenant::{TenantRegisterBody, TenantRegisterResponse},
+            list_webhook_deliveries::WebhookDeliveriesResponse,
+            push_message::{MessagePayload, PushMessageBody},
+            register_client::RegisterBody,
+            update_quota::QuotaUpdateBody,
+            update_webhook::WebhookUpdateBody,
+        },
+        stores::tenant::{WebhookDeliveryStatus, WebhookEventType},
+        webhooks::{self, WebhookEvent, SIGNATURE_HEADER_NAME, TIMESTAMP_HEADER_NAME},
+    },
+    hyper::StatusCode,
+    random_string::generate,
+    serde_json::Value,
+    std::{
+        net::{SocketAddr, TcpListener},
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    },
+    test_context::test_context,
+    tokio::sync::mpsc,
+    uuid::Uuid,
+};
+
+const WEBHOOK_SECRET: &str = "webhook-secret-0123456789";
+
+/// Received requests are sent down the channel, the first request is rejected
+/// so the retry is exercised
+#[derive(Clone)]
+struct Receiver {
+    requests: mpsc::UnboundedSender<(HeaderMap, String)>,
+    calls: Arc<AtomicUsize>,
+}
+
+async fn receive(State(receiver): State<Receiver>, headers: HeaderMap, body: String) -> StatusCode {
+    receiver.requests.send((headers, body)).unwrap();
+    match receiver.calls.fetch_add(1, Ordering::SeqCst) {
+        0 => StatusCode::INTERNAL_SERVER_ERROR,
+        _ => StatusCode::OK,
+    }
+}
+
+fn spawn_receiver() -> (SocketAddr, mpsc::UnboundedReceiver<(HeaderMap, String)>) {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let listener = TcpListener::bind("127.0.0.1:0").expect("failed to bind");
+    let addr = listener.local_addr().unwrap();
+    let app = Router::new()
+        .route("/webhook", post(receive))
+        .with_state(Receiver {
+            requests: tx,
+            calls: Arc::new(AtomicUsize::new(0)),
+        });
+
+    tokio::spawn(async move {
+        axum::Server::from_tcp(listener)
+            .unwrap()
+            .serve(app.into_make_service())
+            .await
+            .unwrap();
+    });
+
+    (addr, rx)
+}
+
+#[test_context(MultiTenantServerContext)]
+#[tokio::test]
+async fn test_webhook_quota_exceeded(ctx: &mut MultiTenantServerContext) {
+    let charset = "1234567890";
+    let random_tenant_id = generate(12, charset);
+    let random_client_id = generate(12, charset);
+    let client = reqwest::Client::new();
+    let (receiver_addr, mut requests) = spawn_receiver();
+
+    let response = client
+        .post(format!("http://{}/tenants", ctx.server.public_addr))
+        .bearer_auth(ADMIN_API_KEY)
+        .json(&TenantRegisterBody {
+            id: random_tenant_id.clone(),
+        })
+        .send()
+        .await
+        .expect("Call failed");
+    assert!(response.status().is_success(), "Failed to create tenant");
+    let tenant: TenantRegisterResponse = response.json().await.expect("Invalid response body");
+
+    let tenant_url = format!(
+        "http://{}/tenants/{}",
+        ctx.server.public_addr, random_tenant_id
+    );
+    let response = client
+        .post(format!("{tenant_url}/webhook"))
+        .bearer_auth(&tenant.api_key)
+        .json(&WebhookUpdateBody {
+            url: "not a url".to_string(),
+            secret: "short".to_string(),
+        })
+        .send()
+        .await
+        .expect("Call failed");
+    assert_eq!(response.status().as_u16(), 400);
+    let body: Value = response.json().await.expect("Invalid response body");
+    let fields = body["fields"]
+        .as_array()
+        .expect("missing fields")
+        .iter()
+        .map(|field| field["field"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(fields, vec!["url", "secret"]);
+
+    let response = client
+        .post(format!("{tenant_url}/webhook"))
+        .bearer_auth(&tenant.api_key)
+        .json(&WebhookUpdateBody {
+            url: format!("http://{receiver_addr}/webhook"),
+            secret: WEBHOOK_SECRET.to_string(),
+        })
+        .send()
+        .await
+        .expect("Call failed");
+    assert!(response.status().is_success(), "Failed to set webhook");
+
+    let response = client
+        .post(format!("{tenant_url}/quota"))
+        .bearer_auth(ADMIN_API_KEY)
+        .json(&QuotaUpdateBody {
+            monthly_notification_quota: Some(0),
+        })
+        .send()
+        .await
+        .expect("Call failed");
+    assert!(response.status().is_success(), "Failed to set quota");
+
+    let response = client
+        .post(format!(
+            "http://{}/{}/clients",
+            ctx.server.public_addr, random_tenant_id
+        ))
+        .json(&RegisterBody {
+            client_id: random_client_id.clone(),
+            push_type: "noop".to_string(),
+            token: "test".to_string(),
+            device_id: None,
+            metadata: None,
+        })
+        .send()
+        .await
+        .expect("Call failed");
+    assert!(response.status().is_success(), "Failed to register client");
+
+    // Every push is rejected but the event is only sent for the first
+    for _ in 0..2 {
+        let response = client
+            .post(format!(
+                "http://{}/{}/clients/{}",
+                ctx.server.public_addr, random_tenant_id, random_client_id
+            ))
+            .json(&PushMessageBody {
+                id: Uuid::new_v4().to_string(),
+                payload: MessagePayload {
+                    topic: None,
+                    blob: Uuid::new_v4().to_string(),
+                    flags: 0,
+                },
+            })
+            .send()
+            .await
+            .expect("Call failed");
+        assert_eq!(response.status().as_u16(), 429);
+    }
+
+    // The first attempt is rejected and then retried
+    let mut event_ids = vec![];
+    for _ in 0..2 {
+        let (headers, body) = tokio::time::timeout(Duration::from_secs(5), requests.recv())
+            .await
+            .expect("webhook wasn't delivered")
+            .unwrap();
+
+        let timestamp = headers[TIMESTAMP_HEADER_NAME]
+            .to_str()
+            .unwrap()
+            .parse::<i64>()
+            .unwrap();
+        assert_eq!(
+            headers[SIGNATURE_HEADER_NAME].to_str().unwrap(),
+            format!(
+                "sha256={}",
+                webhooks::sign(WEBHOOK_SECRET, timestamp, &body)
+            )
+        );
+
+        let event: WebhookEvent = serde_json::from_str(&body).expect("Invalid webhook body");
+        assert_eq!(event.event_type, WebhookEventType::QuotaExceeded);
+        assert_eq!(event.tenant_id, random_tenant_id);
+        assert_eq!(event.data["monthly_notification_quota"], 0);
+        event_ids.push(event.id);
+    }
+    assert_eq!(event_ids[0], event_ids[1]);
+
+    // The delivery log is updated after the response is received
+    let mut delivery = None;
+    for _ in 0..50 {
+        let response = client
+            .get(format!("{tenant_url}/webhook/deliveries"))
+            .bearer_auth(&tenant.api_key)
+            .send()
+            .await
+            .expect("Call failed");
+        assert!(response.status().is_success(), "Failed to list deliveries");
+        let page: WebhookDeliveriesResponse = response.json().await.expect("Invalid response body");
+        assert_eq!(page.deliveries.len(), 1);
+
+        if page.deliveries[0].status == WebhookDeliveryStatus::Delivered {
+            delivery = page.deliveries.into_iter().next();
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    let delivery = delivery.expect("delivery wasn't marked as delivered");
+    assert_eq!(delivery.event_id, event_ids[0]);
+    assert_eq!(delivery.attempts, 2);
+    assert_eq!(delivery.response_status, Some(200));
+    assert_eq!(delivery.last_error, None);
+
+    let response = client
+        .delete(format!("{tenant_url}/webhook"))
+        .bearer_auth(&tenant.api_key)
+        .send()
+        .await
+        .expect("Call failed");
+    assert!(response.status().is_success(), "Failed to remove webhook");
+}
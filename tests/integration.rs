@@ -1,10 +1,16 @@
 // mod env;
 // mod providers;
 // mod store; // Comment this out for now
+mod client_auth;
 mod context;
+mod cors;
+mod errors;
 mod functional;
 mod messages;
+mod openapi;
+mod rate_limit;
 mod store;
+mod webhooks;
 
 pub type ErrorResult<T> = Result<T, TestError>;
 
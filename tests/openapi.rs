@@ -0,0 +1,79 @@
+use {
+    echo_server::{openapi, routes},
+    serde_json::Value,
+    std::{collections::BTreeSet, fs},
+};
+
+const SPEC_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/openapi.json");
+
+/// Fails when the handler types no longer match the committed spec, run with
+/// `UPDATE_OPENAPI=1` to regenerate it
+#[test]
+fn test_openapi_spec_is_up_to_date() {
+    let spec = openapi::spec();
+
+    if std::env::var("UPDATE_OPENAPI").is_ok() {
+        let contents = serde_json::to_string_pretty(&spec).expect("Failed to serialize spec");
+        fs::write(SPEC_PATH, contents + "\n").expect("Failed to write openapi.json");
+        return;
+    }
+
+    let committed: Value =
+        serde_json::from_str(&fs::read_to_string(SPEC_PATH).expect("Failed to read openapi.json"))
+            .expect("openapi.json isn't valid JSON");
+    assert!(
+        committed == spec,
+        "openapi.json is out of date with the handler types, regenerate it with `UPDATE_OPENAPI=1 \
+         cargo test openapi`"
+    );
+}
+
+/// Payloads are flattened into the `Response` envelope, building the spec
+/// panics if one has a field that would overwrite `status`, `errors` or
+/// `fields`
+#[test]
+fn test_openapi_payloads_dont_overwrite_envelope() {
+    openapi::spec();
+}
+
+/// `/v1/:tenant_id/clients` in the router is `/v1/{tenant_id}/clients` in the
+/// spec
+fn spec_path(path: &str) -> String {
+    let path = path
+        .split('/')
+        .map(|segment| match segment.strip_prefix(':') {
+            Some(param) => format!("{{{param}}}"),
+            None => segment.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("/");
+    format!("/v1{path}")
+}
+
+/// Fails when a route is added to the router without documenting it, or an
+/// operation is documented that isn't served
+#[test]
+fn test_openapi_spec_matches_routes() {
+    let spec = openapi::spec();
+    let documented = spec["paths"]
+        .as_object()
+        .expect("paths is an object")
+        .iter()
+        .flat_map(|(path, operations)| {
+            operations
+                .as_object()
+                .expect("operations are an object")
+                .keys()
+                .map(move |method| (method.to_uppercase(), path.clone()))
+        })
+        .collect::<BTreeSet<_>>();
+
+    // Hybrid mode serves every route
+    let served = routes::v1(true, true)
+        .registered()
+        .iter()
+        .map(|(method, path)| (method.to_string(), spec_path(path)))
+        .collect::<BTreeSet<_>>();
+
+    assert_eq!(served, documented);
+}
@@ -0,0 +1,124 @@
+use {
+    axum::{http::HeaderMap, response::IntoResponse},
+    echo_server::{
+        error::Error,
+        rate_limit::{client_ip, memory::InMemoryRateLimiter, RateLimit, RateLimiter},
+    },
+    std::{
+        net::{IpAddr, Ipv4Addr},
+        time::Duration,
+    },
+};
+
+#[tokio::test]
+async fn allows_burst_up_to_capacity() {
+    let limiter = InMemoryRateLimiter::new();
+    let limit = RateLimit::per_minute(3);
+
+    for _ in 0..3 {
+        assert!(limiter.check("client", limit).await.is_ok());
+    }
+
+    let retry_after = limiter
+        .check("client", limit)
+        .await
+        .expect_err("should be rate limited");
+    assert!(retry_after > Duration::ZERO);
+    assert!(retry_after <= Duration::from_secs(20));
+}
+
+#[tokio::test]
+async fn buckets_are_per_key() {
+    let limiter = InMemoryRateLimiter::new();
+    let limit = RateLimit::per_minute(1);
+
+    assert!(limiter.check("a", limit).await.is_ok());
+    assert!(limiter.check("a", limit).await.is_err());
+    assert!(limiter.check("b", limit).await.is_ok());
+}
+
+#[tokio::test]
+async fn refills_over_time() {
+    let limiter = InMemoryRateLimiter::new();
+    let limit = RateLimit {
+        capacity: 1,
+        period: Duration::from_millis(50),
+    };
+
+    assert!(limiter.check("tenant", limit).await.is_ok());
+    assert!(limiter.check("tenant", limit).await.is_err());
+
+    tokio::time::sleep(Duration::from_millis(60)).await;
+
+    assert!(limiter.check("tenant", limit).await.is_ok());
+}
+
+#[tokio::test]
+async fn evicts_least_recently_used_bucket() {
+    let limiter = InMemoryRateLimiter::with_capacity(2);
+    let limit = RateLimit::per_minute(1);
+
+    assert!(limiter.check("a", limit).await.is_ok());
+    assert!(limiter.check("b", limit).await.is_ok());
+    // Using `a` again makes `b` the least recently used
+    assert!(limiter.check("a", limit).await.is_err());
+    assert!(limiter.check("c", limit).await.is_ok());
+
+    // `b` was evicted so starts with a full bucket, `a` is still limited
+    assert!(limiter.check("a", limit).await.is_err());
+    assert!(limiter.check("b", limit).await.is_ok());
+}
+
+#[tokio::test]
+async fn zero_capacity_is_always_limited() {
+    let limiter = InMemoryRateLimiter::new();
+    let limit = RateLimit::per_second(0);
+
+    let retry_after = limiter
+        .check("tenant", limit)
+        .await
+        .expect_err("should be rate limited");
+    assert_eq!(retry_after, Duration::from_secs(1));
+}
+
+#[test]
+fn client_ip_from_forwarded_for() {
+    let connection_ip = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "X-Forwarded-For",
+        "203.0.113.9, 198.51.100.7".parse().unwrap(),
+    );
+
+    // The header is ignored unless there are trusted proxies
+    assert_eq!(client_ip(&headers, connection_ip, 0), connection_ip);
+    // Entries before those added by trusted proxies could be spoofed
+    assert_eq!(
+        client_ip(&headers, connection_ip, 1),
+        "198.51.100.7".parse::<IpAddr>().unwrap()
+    );
+    assert_eq!(
+        client_ip(&headers, connection_ip, 2),
+        "203.0.113.9".parse::<IpAddr>().unwrap()
+    );
+    // More trusted proxies than entries means the header is incomplete
+    assert_eq!(client_ip(&headers, connection_ip, 3), connection_ip);
+    assert_eq!(
+        client_ip(&HeaderMap::new(), connection_ip, 1),
+        connection_ip
+    );
+}
+
+#[test]
+fn rate_limited_response() {
+    let response = Error::RateLimited(Duration::from_millis(1500)).into_response();
+
+    assert_eq!(response.status().as_u16(), 429);
+    assert_eq!(
+        response
+            .headers()
+            .get("Retry-After")
+            .and_then(|value| value.to_str().ok()),
+        Some("2")
+    );
+}
@@ -0,0 +1,189 @@
+use {
+    crate::context::StoreContext,
+    chrono::{Duration, Utc},
+    echo_server::{
+        providers::ProviderKind,
+        stores::client::{Device, DeviceMetadata},
+    },
+    test_context::test_context,
+    uuid::Uuid,
+};
+
+const TENANT_ID: &str = "0000-0000-0000-0000";
+
+#[test_context(StoreContext)]
+#[tokio::test]
+async fn test_client_devices(ctx: &mut StoreContext) {
+    let id = Uuid::new_v4().to_string();
+
+    // Only the first device creates the client
+    for (device_id, should_create) in [("phone", true), ("tablet", false)] {
+        let created = ctx
+            .clients
+            .create_client(TENANT_ID, &id, Device {
+                id: device_id.to_string(),
+                push_type: ProviderKind::Noop,
+                token: format!("{device_id}-token"),
+                metadata: DeviceMetadata::default(),
+            })
+            .await
+            .expect("creation failed");
+        assert_eq!(created, should_create);
+    }
+
+    // Re-registering a device replaces its token rather than adding a device
+    let created = ctx
+        .clients
+        .create_client(TENANT_ID, &id, Device {
+            id: "phone".to_string(),
+            push_type: ProviderKind::Noop,
+            token: "new-phone-token".to_string(),
+            metadata: DeviceMetadata {
+                os: Some("iOS 16.3".to_string()),
+                sdk_name: Some("swift".to_string()),
+                sdk_version: Some("1.2.0".to_string()),
+                locale: Some("en-GB".to_string()),
+                ..Default::default()
+            },
+        })
+        .await
+        .expect("update failed");
+    assert!(!created);
+
+    let client = ctx
+        .clients
+        .get_client(TENANT_ID, &id)
+        .await
+        .expect("fetch failed");
+    assert_eq!(client.devices.len(), 2);
+    assert!(client.devices.iter().any(|d| d.id == "phone"
+        && d.token == "new-phone-token"
+        && d.metadata.sdk_version.as_deref() == Some("1.2.0")));
+
+    ctx.clients
+        .delete_device(TENANT_ID, &id, "tablet")
+        .await
+        .expect("device deletion failed");
+
+    let client = ctx
+        .clients
+        .get_client(TENANT_ID, &id)
+        .await
+        .expect("fetch failed");
+    assert_eq!(client.devices.len(), 1);
+
+    let deleted = ctx
+        .clients
+        .delete_client(TENANT_ID, &id)
+        .await
+        .expect("deletion failed");
+    assert!(deleted);
+
+    assert!(ctx.clients.get_client(TENANT_ID, &id).await.is_err());
+
+    // Deleting it again doesn't delete anything
+    let deleted = ctx
+        .clients
+        .delete_client(TENANT_ID, &id)
+        .await
+        .expect("deletion failed");
+    assert!(!deleted);
+}
+
+#[test_context(StoreContext)]
+#[tokio::test]
+async fn test_stale_clients(ctx: &mut StoreContext) {
+    let stale_ids = [Uuid::new_v4().to_string(), Uuid::new_v4().to_string()];
+    let active_id = Uuid::new_v4().to_string();
+
+    for id in stale_ids.iter().chain([&active_id]) {
+        ctx.clients
+            .create_client(TENANT_ID, id, Device {
+                id: "phone".to_string(),
+                push_type: ProviderKind::Noop,
+                token: "token".to_string(),
+                metadata: DeviceMetadata::default(),
+            })
+            .await
+            .expect("creation failed");
+    }
+
+    sqlx::query(
+        "UPDATE public.clients SET last_seen_at = now() - interval '400 days' WHERE id = ANY($1) \
+         and tenant_id = $2",
+    )
+    .bind(&stale_ids[..])
+    .bind(TENANT_ID)
+    .execute(ctx.pool.as_ref())
+    .await
+    .expect("backdating failed");
+
+    let idle_since = Utc::now() - Duration::days(365);
+
+    let count = ctx
+        .clients
+        .count_stale_clients(idle_since)
+        .await
+        .expect("count failed");
+    assert!(count >= 2);
+
+    // Deleted in batches of at most `limit`
+    let deleted = ctx
+        .clients
+        .delete_stale_clients(idle_since, 1)
+        .await
+        .expect("deletion failed");
+    assert_eq!(deleted, 1);
+
+    let deleted = ctx
+        .clients
+        .delete_stale_clients(idle_since, 1000)
+        .await
+        .expect("deletion failed");
+    assert!(deleted >= 1);
+
+    for id in &stale_ids {
+        assert!(ctx.clients.get_client(TENANT_ID, id).await.is_err());
+    }
+    assert!(ctx.clients.get_client(TENANT_ID, &active_id).await.is_ok());
+
+    // A successful push keeps a client alive
+    ctx.clients
+        .update_last_push_success(TENANT_ID, &active_id)
+        .await
+        .expect("update failed");
+    let client = ctx
+        .clients
+        .get_client(TENANT_ID, &active_id)
+        .await
+        .expect("fetch failed");
+    assert!(client.last_push_success_at.is_some());
+
+    ctx.clients
+        .delete_client(TENANT_ID, &active_id)
+        .await
+        .expect("deletion failed");
+}
+
+#[test]
+fn test_device_metadata_labels() {
+    let metadata = DeviceMetadata {
+        os: Some("iOS 16.3".to_string()),
+        sdk_name: Some("Swift".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(metadata.os_label(), "ios");
+    assert_eq!(metadata.sdk_label(), "swift");
+
+    let metadata = DeviceMetadata {
+        os: Some("TempleOS 5.03".to_string()),
+        sdk_name: Some("my-fork-of-the-swift-sdk".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(metadata.os_label(), "other");
+    assert_eq!(metadata.sdk_label(), "other");
+
+    let metadata = DeviceMetadata::default();
+    assert_eq!(metadata.os_label(), "unknown");
+    assert_eq!(metadata.sdk_label(), "unknown");
+}
@@ -1 +1,4 @@
+mod client;
+mod notification;
 mod tenant;
+mod usage;
@@ -0,0 +1,229 @@
+use {
+    crate::context::StoreContext,
+    chrono::{Duration, Utc},
+    echo_server::{
+        handlers::push_message::MessagePayload,
+        providers::ProviderKind,
+        stores::{
+            client::{Device, DeviceMetadata},
+            notification::{NotificationStatus, MAX_PAYLOAD_HISTORY},
+            StoreError,
+        },
+    },
+    test_context::test_context,
+    uuid::Uuid,
+};
+
+const TENANT_ID: &str = "0000-0000-0000-0000";
+
+#[test_context(StoreContext)]
+#[tokio::test]
+async fn test_notification_retention(ctx: &mut StoreContext) {
+    let client_id = Uuid::new_v4().to_string();
+    ctx.clients
+        .create_client(TENANT_ID, &client_id, Device {
+            id: "phone".to_string(),
+            push_type: ProviderKind::Noop,
+            token: "token".to_string(),
+            metadata: DeviceMetadata::default(),
+        })
+        .await
+        .expect("client creation failed");
+
+    let payload = MessagePayload {
+        topic: None,
+        flags: 0,
+        blob: "blob".to_string(),
+    };
+
+    let expired_id = Uuid::new_v4().to_string();
+    let recent_id = Uuid::new_v4().to_string();
+    for id in [&expired_id, &recent_id] {
+        ctx.notifications
+            .create_or_update_notification(id, TENANT_ID, &client_id, &payload)
+            .await
+            .expect("notification creation failed");
+    }
+
+    sqlx::query(
+        "UPDATE public.notifications SET last_received_at = now() - interval '40 days' WHERE id = \
+         $1",
+    )
+    .bind(&expired_id)
+    .execute(ctx.pool.as_ref())
+    .await
+    .expect("backdating failed");
+
+    let received_before = Utc::now() - Duration::days(30);
+    // Delete in batches of one until everything expired has gone
+    while ctx
+        .notifications
+        .delete_notifications_received_before(received_before, 1)
+        .await
+        .expect("deletion failed")
+        > 0
+    {}
+
+    let remaining = sqlx::query_as::<_, (String,)>(
+        "SELECT id FROM public.notifications WHERE client_id = $1 and tenant_id = $2",
+    )
+    .bind(&client_id)
+    .bind(TENANT_ID)
+    .fetch_all(ctx.pool.as_ref())
+    .await
+    .expect("fetch failed");
+    assert_eq!(remaining, vec![(recent_id.clone(),)]);
+
+    let size = ctx
+        .notifications
+        .get_notifications_table_size()
+        .await
+        .expect("size failed");
+    assert!(size > 0);
+
+    ctx.clients
+        .delete_client(TENANT_ID, &client_id)
+        .await
+        .expect("client deletion failed");
+
+    // The remaining notification goes with its client
+    assert!(ctx
+        .notifications
+        .get_notification(TENANT_ID, &recent_id)
+        .await
+        .is_err());
+}
+
+#[test_context(StoreContext)]
+#[tokio::test]
+async fn test_notification_ids_are_tenant_scoped(ctx: &mut StoreContext) {
+    let tenant_ids = [Uuid::new_v4().to_string(), Uuid::new_v4().to_string()];
+    let client_ids = [Uuid::new_v4().to_string(), Uuid::new_v4().to_string()];
+    for tenant_id in &tenant_ids {
+        for client_id in &client_ids {
+            ctx.clients
+                .create_client(tenant_id, client_id, Device {
+                    id: "phone".to_string(),
+                    push_type: ProviderKind::Noop,
+                    token: "token".to_string(),
+                    metadata: DeviceMetadata::default(),
+                })
+                .await
+                .expect("client creation failed");
+        }
+    }
+
+    let payload = MessagePayload {
+        topic: None,
+        flags: 0,
+        blob: "blob".to_string(),
+    };
+
+    // The same ID in each tenant is a separate notification
+    let id = Uuid::new_v4().to_string();
+    for tenant_id in &tenant_ids {
+        let notification = ctx
+            .notifications
+            .create_or_update_notification(&id, tenant_id, &client_ids[0], &payload)
+            .await
+            .expect("notification creation failed");
+        assert_eq!(notification.previous_payloads.len(), 1);
+    }
+
+    // But can't be reused for another client of the same tenant
+    let res = ctx
+        .notifications
+        .create_or_update_notification(&id, &tenant_ids[0], &client_ids[1], &payload)
+        .await;
+    assert!(matches!(res, Err(StoreError::AlreadyExists(_, _))));
+
+    // Only the most recent payloads are kept
+    for _ in 0..MAX_PAYLOAD_HISTORY + 5 {
+        ctx.notifications
+            .create_or_update_notification(&id, &tenant_ids[0], &client_ids[0], &payload)
+            .await
+            .expect("notification update failed");
+    }
+    let notification = ctx
+        .notifications
+        .get_notification(&tenant_ids[0], &id)
+        .await
+        .expect("fetch failed");
+    assert_eq!(
+        notification.previous_payloads.len(),
+        MAX_PAYLOAD_HISTORY as usize
+    );
+    let notification = ctx
+        .notifications
+        .get_notification(&tenant_ids[1], &id)
+        .await
+        .expect("fetch failed");
+    assert_eq!(notification.previous_payloads.len(), 1);
+
+    for tenant_id in &tenant_ids {
+        ctx.clients
+            .delete_tenant_clients(tenant_id)
+            .await
+            .expect("client deletion failed");
+    }
+}
+
+#[test_context(StoreContext)]
+#[tokio::test]
+async fn test_notification_claims(ctx: &mut StoreContext) {
+    let client_id = Uuid::new_v4().to_string();
+    ctx.clients
+        .create_client(TENANT_ID, &client_id, Device {
+            id: "phone".to_string(),
+            push_type: ProviderKind::Noop,
+            token: "token".to_string(),
+            metadata: DeviceMetadata::default(),
+        })
+        .await
+        .expect("client creation failed");
+
+    let id = Uuid::new_v4().to_string();
+    ctx.notifications
+        .create_or_update_notification(&id, TENANT_ID, &client_id, &MessagePayload {
+            topic: None,
+            flags: 0,
+            blob: "blob".to_string(),
+        })
+        .await
+        .expect("notification creation failed");
+
+    // Only one of the concurrent claims succeeds
+    let (first, second) = tokio::join!(
+        ctx.notifications.claim_notification(TENANT_ID, &id),
+        ctx.notifications.claim_notification(TENANT_ID, &id),
+    );
+    let claims = [first.expect("claim failed"), second.expect("claim failed")];
+    assert_eq!(claims.iter().filter(|claimed| **claimed).count(), 1);
+
+    // A failed delivery releases the claim so a retry can try again
+    ctx.notifications
+        .update_notification_status(TENANT_ID, &id, NotificationStatus::Failed)
+        .await
+        .expect("update failed");
+    assert!(ctx
+        .notifications
+        .claim_notification(TENANT_ID, &id)
+        .await
+        .expect("claim failed"));
+
+    // A delivered notification can't be claimed again
+    ctx.notifications
+        .update_notification_status(TENANT_ID, &id, NotificationStatus::Delivered)
+        .await
+        .expect("update failed");
+    assert!(!ctx
+        .notifications
+        .claim_notification(TENANT_ID, &id)
+        .await
+        .expect("claim failed"));
+
+    ctx.clients
+        .delete_client(TENANT_ID, &client_id)
+        .await
+        .expect("client deletion failed");
+}
@@ -1,26 +1,59 @@
 use {
     crate::context::StoreContext,
-    echo_server::stores::tenant::{
-        TenantApnsUpdateAuth,
-        TenantApnsUpdateParams,
-        TenantFcmUpdateParams,
-        TenantUpdateParams,
+    chrono::{Duration, NaiveDate, Utc},
+    echo_server::{
+        error::Error,
+        providers::ProviderKind,
+        stores::{
+            client::{Device, DeviceMetadata},
+            tenant::{
+                TenantApnsUpdateAuth,
+                TenantApnsUpdateParams,
+                TenantAuditAction,
+                TenantAuditParams,
+                TenantFcmUpdateParams,
+                TenantListParams,
+                TenantStore,
+                TenantUpdateParams,
+                WebhookDeliveryParams,
+                WebhookEventType,
+            },
+            tenant_cache::CachedTenantStore,
+        },
     },
+    serde_json::json,
+    std::time::Duration as StdDuration,
     test_context::test_context,
     uuid::Uuid,
 };
 
+const API_KEY_HASH: &str = "api-key-hash";
+
 #[test_context(StoreContext)]
 #[tokio::test]
 async fn test_tenant_creation(ctx: &mut StoreContext) {
-    let res = ctx
+    let tenant = ctx
         .tenants
-        .create_tenant(TenantUpdateParams {
-            id: Uuid::new_v4().to_string(),
-        })
-        .await;
+        .create_tenant(
+            TenantUpdateParams {
+                id: Uuid::new_v4().to_string(),
+            },
+            API_KEY_HASH,
+        )
+        .await
+        .expect("creation failed");
 
-    assert!(res.is_ok())
+    assert_eq!(tenant.api_key_hash.as_deref(), Some(API_KEY_HASH));
+
+    // A client key is generated for every tenant
+    let client_key = tenant.client_key.clone().expect("missing client key");
+    assert!(client_key.starts_with("pk_"));
+    let found = ctx
+        .tenants
+        .find_tenant_by_client_key(&client_key)
+        .await
+        .expect("lookup failed");
+    assert_eq!(found, Some(tenant));
 }
 
 #[test_context(StoreContext)]
@@ -28,9 +61,12 @@ async fn test_tenant_creation(ctx: &mut StoreContext) {
 async fn test_tenant_update(ctx: &mut StoreContext) {
     let tenant = ctx
         .tenants
-        .create_tenant(TenantUpdateParams {
-            id: Uuid::new_v4().to_string(),
-        })
+        .create_tenant(
+            TenantUpdateParams {
+                id: Uuid::new_v4().to_string(),
+            },
+            API_KEY_HASH,
+        )
         .await
         .expect("creation failed");
 
@@ -46,12 +82,69 @@ async fn test_tenant_update(ctx: &mut StoreContext) {
 
 #[test_context(StoreContext)]
 #[tokio::test]
-async fn test_tenant_fcm(ctx: &mut StoreContext) {
+async fn test_tenant_pending_rename(ctx: &mut StoreContext) {
+    let old_id = Uuid::new_v4().to_string();
+    let new_id = Uuid::new_v4().to_string();
+    ctx.tenants
+        .create_tenant(TenantUpdateParams { id: old_id.clone() }, API_KEY_HASH)
+        .await
+        .expect("creation failed");
+
     let tenant = ctx
         .tenants
-        .create_tenant(TenantUpdateParams {
+        .update_tenant(&old_id, TenantUpdateParams { id: new_id.clone() })
+        .await
+        .expect("rename failed");
+    assert_eq!(tenant.renamed_from.as_deref(), Some(old_id.as_str()));
+    let pending = ctx
+        .tenants
+        .list_pending_renames()
+        .await
+        .expect("listing failed");
+    assert!(pending.iter().any(|t| t.id == new_id));
+
+    // The old ID can't be reused and the tenant can't be renamed again until the
+    // rename is completed
+    let res = ctx
+        .tenants
+        .create_tenant(TenantUpdateParams { id: old_id.clone() }, API_KEY_HASH)
+        .await;
+    assert!(matches!(res, Err(Error::TenantIdTaken(_))));
+    let res = ctx
+        .tenants
+        .update_tenant(&new_id, TenantUpdateParams {
             id: Uuid::new_v4().to_string(),
         })
+        .await;
+    assert!(matches!(res, Err(Error::InvalidTenantId(_))));
+
+    ctx.tenants
+        .complete_tenant_rename(&new_id)
+        .await
+        .expect("completion failed");
+    let tenant = ctx
+        .tenants
+        .get_tenant(&new_id)
+        .await
+        .expect("lookup failed");
+    assert_eq!(tenant.renamed_from, None);
+    ctx.tenants
+        .create_tenant(TenantUpdateParams { id: old_id }, API_KEY_HASH)
+        .await
+        .expect("old ID should be free");
+}
+
+#[test_context(StoreContext)]
+#[tokio::test]
+async fn test_tenant_fcm(ctx: &mut StoreContext) {
+    let tenant = ctx
+        .tenants
+        .create_tenant(
+            TenantUpdateParams {
+                id: Uuid::new_v4().to_string(),
+            },
+            API_KEY_HASH,
+        )
         .await
         .expect("creation failed");
 
@@ -67,13 +160,72 @@ async fn test_tenant_fcm(ctx: &mut StoreContext) {
 
 #[test_context(StoreContext)]
 #[tokio::test]
-async fn test_tenant_apns(ctx: &mut StoreContext) {
+async fn test_tenant_remove_providers(ctx: &mut StoreContext) {
     let tenant = ctx
         .tenants
-        .create_tenant(TenantUpdateParams {
-            id: Uuid::new_v4().to_string(),
+        .create_tenant(
+            TenantUpdateParams {
+                id: Uuid::new_v4().to_string(),
+            },
+            API_KEY_HASH,
+        )
+        .await
+        .expect("creation failed");
+    ctx.tenants
+        .update_tenant_fcm(&tenant.id, TenantFcmUpdateParams {
+            fcm_api_key: "test-api-key".to_string(),
         })
         .await
+        .expect("update failed");
+    ctx.tenants
+        .update_tenant_apns(&tenant.id, TenantApnsUpdateParams {
+            apns_topic: "com.walletconect.exampleapp".to_string(),
+        })
+        .await
+        .expect("update failed");
+    let tenant = ctx
+        .tenants
+        .update_tenant_apns_auth(&tenant.id, TenantApnsUpdateAuth::Certificate {
+            apns_certificate: "example-certificate-string".to_string(),
+            apns_certificate_password: "password123".to_string(),
+        })
+        .await
+        .expect("update failed");
+    assert!(tenant.providers().contains(&ProviderKind::Fcm));
+    assert!(tenant.providers().contains(&ProviderKind::Apns));
+
+    let tenant = ctx
+        .tenants
+        .remove_tenant_fcm(&tenant.id)
+        .await
+        .expect("removal failed");
+    assert_eq!(tenant.fcm_api_key, None);
+    assert!(!tenant.providers().contains(&ProviderKind::Fcm));
+    assert!(tenant.providers().contains(&ProviderKind::Apns));
+
+    let tenant = ctx
+        .tenants
+        .remove_tenant_apns(&tenant.id)
+        .await
+        .expect("removal failed");
+    assert_eq!(tenant.apns_type, None);
+    assert_eq!(tenant.apns_topic, None);
+    assert_eq!(tenant.apns_certificate, None);
+    assert!(!tenant.providers().contains(&ProviderKind::Apns));
+}
+
+#[test_context(StoreContext)]
+#[tokio::test]
+async fn test_tenant_apns(ctx: &mut StoreContext) {
+    let tenant = ctx
+        .tenants
+        .create_tenant(
+            TenantUpdateParams {
+                id: Uuid::new_v4().to_string(),
+            },
+            API_KEY_HASH,
+        )
+        .await
         .expect("creation failed");
 
     let res = ctx
@@ -91,9 +243,12 @@ async fn test_tenant_apns(ctx: &mut StoreContext) {
 async fn test_tenant_apns_certificate_auth(ctx: &mut StoreContext) {
     let tenant = ctx
         .tenants
-        .create_tenant(TenantUpdateParams {
-            id: Uuid::new_v4().to_string(),
-        })
+        .create_tenant(
+            TenantUpdateParams {
+                id: Uuid::new_v4().to_string(),
+            },
+            API_KEY_HASH,
+        )
         .await
         .expect("creation failed");
 
@@ -113,9 +268,12 @@ async fn test_tenant_apns_certificate_auth(ctx: &mut StoreContext) {
 async fn test_tenant_apns_token_auth(ctx: &mut StoreContext) {
     let tenant = ctx
         .tenants
-        .create_tenant(TenantUpdateParams {
-            id: Uuid::new_v4().to_string(),
-        })
+        .create_tenant(
+            TenantUpdateParams {
+                id: Uuid::new_v4().to_string(),
+            },
+            API_KEY_HASH,
+        )
         .await
         .expect("creation failed");
 
@@ -130,3 +288,354 @@ async fn test_tenant_apns_token_auth(ctx: &mut StoreContext) {
 
     assert!(res.is_ok())
 }
+
+#[test_context(StoreContext)]
+#[tokio::test]
+async fn test_tenant_list_provider_filter(ctx: &mut StoreContext) {
+    let tenant = ctx
+        .tenants
+        .create_tenant(
+            TenantUpdateParams {
+                id: Uuid::new_v4().to_string(),
+            },
+            API_KEY_HASH,
+        )
+        .await
+        .expect("creation failed");
+    ctx.tenants
+        .update_tenant_fcm(&tenant.id, TenantFcmUpdateParams {
+            fcm_api_key: "test-api-key".to_string(),
+        })
+        .await
+        .expect("update failed");
+
+    let params = |provider| TenantListParams {
+        limit: 1000,
+        provider: Some(provider),
+        created_after: Some(tenant.created_at - Duration::seconds(1)),
+        ..Default::default()
+    };
+
+    let fcm = ctx
+        .tenants
+        .list_tenants(params(ProviderKind::Fcm))
+        .await
+        .expect("list failed");
+    assert!(fcm.iter().any(|t| t.id == tenant.id));
+
+    let apns = ctx
+        .tenants
+        .list_tenants(params(ProviderKind::Apns))
+        .await
+        .expect("list failed");
+    assert!(apns.iter().all(|t| t.id != tenant.id));
+
+    ctx.tenants
+        .delete_tenant(&tenant.id)
+        .await
+        .expect("deletion failed");
+}
+
+#[test_context(StoreContext)]
+#[tokio::test]
+async fn test_tenant_soft_delete_and_purge(ctx: &mut StoreContext) {
+    let tenant = ctx
+        .tenants
+        .create_tenant(
+            TenantUpdateParams {
+                id: Uuid::new_v4().to_string(),
+            },
+            API_KEY_HASH,
+        )
+        .await
+        .expect("creation failed");
+    ctx.clients
+        .create_client(&tenant.id, &Uuid::new_v4().to_string(), Device {
+            id: "phone".to_string(),
+            push_type: ProviderKind::Noop,
+            token: "token".to_string(),
+            metadata: DeviceMetadata::default(),
+        })
+        .await
+        .expect("client creation failed");
+
+    ctx.tenants
+        .delete_tenant(&tenant.id)
+        .await
+        .expect("deletion failed");
+    assert!(ctx.tenants.get_tenant(&tenant.id).await.is_err());
+
+    let restored = ctx
+        .tenants
+        .restore_tenant(&tenant.id)
+        .await
+        .expect("restore failed");
+    assert_eq!(restored.deleted_at, None);
+    assert!(ctx.tenants.get_tenant(&tenant.id).await.is_ok());
+
+    // Restoring a tenant that isn't deleted fails
+    assert!(ctx.tenants.restore_tenant(&tenant.id).await.is_err());
+
+    ctx.tenants
+        .delete_tenant(&tenant.id)
+        .await
+        .expect("deletion failed");
+
+    let deleted = ctx
+        .tenants
+        .list_deleted_tenants(Utc::now() + Duration::seconds(1))
+        .await
+        .expect("list failed");
+    assert!(deleted.iter().any(|t| t.id == tenant.id));
+
+    // Still within the grace period
+    let deleted = ctx
+        .tenants
+        .list_deleted_tenants(Utc::now() - Duration::days(1))
+        .await
+        .expect("list failed");
+    assert!(deleted.iter().all(|t| t.id != tenant.id));
+
+    ctx.tenants
+        .record_audit_entry(TenantAuditParams {
+            tenant_id: tenant.id.clone(),
+            actor: "admin".to_string(),
+            action: TenantAuditAction::DeleteTenant,
+            changes: json!({}),
+            request_id: Uuid::new_v4().to_string(),
+        })
+        .await
+        .expect("failed to record entry");
+    ctx.tenants
+        .create_webhook_delivery(WebhookDeliveryParams {
+            tenant_id: tenant.id.clone(),
+            event_id: Uuid::new_v4().to_string(),
+            event_type: WebhookEventType::QuotaExceeded,
+            url: "https://example.com/webhook".to_string(),
+            payload: json!({}),
+        })
+        .await
+        .expect("failed to record delivery");
+
+    let clients = ctx
+        .clients
+        .delete_tenant_clients(&tenant.id)
+        .await
+        .expect("client deletion failed");
+    assert_eq!(clients, 1);
+    ctx.tenants
+        .purge_tenant(&tenant.id)
+        .await
+        .expect("purge failed");
+
+    // Nothing is left for a new tenant with the same ID to inherit
+    let entries = ctx
+        .tenants
+        .list_audit_entries(&tenant.id, None, 10)
+        .await
+        .expect("list failed");
+    assert!(entries.is_empty());
+    let deliveries = ctx
+        .tenants
+        .list_webhook_deliveries(&tenant.id, None, 10)
+        .await
+        .expect("list failed");
+    assert!(deliveries.is_empty());
+
+    let deleted = ctx
+        .tenants
+        .list_deleted_tenants(Utc::now() + Duration::seconds(1))
+        .await
+        .expect("list failed");
+    assert!(deleted.iter().all(|t| t.id != tenant.id));
+}
+
+#[test_context(StoreContext)]
+#[tokio::test]
+async fn test_tenant_quota_exceeded_event(ctx: &mut StoreContext) {
+    let tenant = ctx
+        .tenants
+        .create_tenant(
+            TenantUpdateParams {
+                id: Uuid::new_v4().to_string(),
+            },
+            API_KEY_HASH,
+        )
+        .await
+        .expect("creation failed");
+    let march = NaiveDate::from_ymd_opt(2023, 3, 1).unwrap();
+    let april = NaiveDate::from_ymd_opt(2023, 4, 1).unwrap();
+
+    // Only claimed once per month
+    for (month, expected) in [(march, true), (march, false), (april, true)] {
+        let claimed = ctx
+            .tenants
+            .claim_quota_exceeded_event(&tenant.id, month)
+            .await
+            .expect("claim failed");
+        assert_eq!(claimed, expected);
+    }
+
+    // Changing the quota means the tenant should be told again
+    ctx.tenants
+        .update_tenant_quota(&tenant.id, Some(10))
+        .await
+        .expect("update failed");
+    let claimed = ctx
+        .tenants
+        .claim_quota_exceeded_event(&tenant.id, april)
+        .await
+        .expect("claim failed");
+    assert!(claimed);
+}
+
+#[test_context(StoreContext)]
+#[tokio::test]
+async fn test_tenant_audit_log(ctx: &mut StoreContext) {
+    let tenant_id = Uuid::new_v4().to_string();
+    for action in [
+        TenantAuditAction::CreateTenant,
+        TenantAuditAction::UpdateFcm,
+    ] {
+        ctx.tenants
+            .record_audit_entry(TenantAuditParams {
+                tenant_id: tenant_id.clone(),
+                actor: "admin".to_string(),
+                action,
+                changes: json!({ "id": tenant_id }),
+                request_id: Uuid::new_v4().to_string(),
+            })
+            .await
+            .expect("failed to record entry");
+    }
+
+    let entries = ctx
+        .tenants
+        .list_audit_entries(&tenant_id, None, 10)
+        .await
+        .expect("list failed");
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].action, TenantAuditAction::UpdateFcm);
+    assert_eq!(entries[1].action, TenantAuditAction::CreateTenant);
+    assert_eq!(entries[1].changes, json!({ "id": tenant_id }));
+
+    let older = ctx
+        .tenants
+        .list_audit_entries(&tenant_id, Some(entries[0].id), 10)
+        .await
+        .expect("list failed");
+    assert_eq!(older, entries[1..]);
+}
+
+#[test_context(StoreContext)]
+#[tokio::test]
+async fn test_tenant_cache_invalidation(ctx: &mut StoreContext) {
+    let cache = CachedTenantStore::new(ctx.tenants.clone(), StdDuration::from_secs(60), 10, None);
+    let tenant = cache
+        .create_tenant(
+            TenantUpdateParams {
+                id: Uuid::new_v4().to_string(),
+            },
+            API_KEY_HASH,
+        )
+        .await
+        .expect("creation failed");
+    assert!(cache
+        .get_tenant(&tenant.id)
+        .await
+        .unwrap()
+        .fcm_api_key
+        .is_none());
+
+    // Changes made behind the cache's back aren't seen until the entry expires
+    ctx.tenants
+        .update_tenant_fcm(&tenant.id, TenantFcmUpdateParams {
+            fcm_api_key: "behind-the-cache".to_string(),
+        })
+        .await
+        .expect("update failed");
+    assert!(cache
+        .get_tenant(&tenant.id)
+        .await
+        .unwrap()
+        .fcm_api_key
+        .is_none());
+
+    // Changes made through the cache invalidate the entry
+    cache
+        .update_tenant_fcm(&tenant.id, TenantFcmUpdateParams {
+            fcm_api_key: "through-the-cache".to_string(),
+        })
+        .await
+        .expect("update failed");
+    assert_eq!(
+        cache.get_tenant(&tenant.id).await.unwrap().fcm_api_key,
+        Some("through-the-cache".to_string())
+    );
+
+    cache
+        .delete_tenant(&tenant.id)
+        .await
+        .expect("delete failed");
+    assert!(cache.get_tenant(&tenant.id).await.is_err());
+}
+
+#[test_context(StoreContext)]
+#[tokio::test]
+async fn test_tenant_cache_expiry_and_eviction(ctx: &mut StoreContext) {
+    let cache = CachedTenantStore::new(ctx.tenants.clone(), StdDuration::from_millis(200), 1, None);
+    let first = ctx
+        .tenants
+        .create_tenant(
+            TenantUpdateParams {
+                id: Uuid::new_v4().to_string(),
+            },
+            API_KEY_HASH,
+        )
+        .await
+        .expect("creation failed");
+    let second = ctx
+        .tenants
+        .create_tenant(
+            TenantUpdateParams {
+                id: Uuid::new_v4().to_string(),
+            },
+            API_KEY_HASH,
+        )
+        .await
+        .expect("creation failed");
+
+    cache.get_tenant(&first.id).await.expect("get failed");
+    ctx.tenants
+        .update_tenant_fcm(&first.id, TenantFcmUpdateParams {
+            fcm_api_key: "expired".to_string(),
+        })
+        .await
+        .expect("update failed");
+    assert!(cache
+        .get_tenant(&first.id)
+        .await
+        .unwrap()
+        .fcm_api_key
+        .is_none());
+
+    // Expires after the TTL
+    tokio::time::sleep(StdDuration::from_millis(250)).await;
+    assert_eq!(
+        cache.get_tenant(&first.id).await.unwrap().fcm_api_key,
+        Some("expired".to_string())
+    );
+
+    // Caching the second tenant evicts the first
+    cache.get_tenant(&second.id).await.expect("get failed");
+    ctx.tenants
+        .update_tenant_fcm(&first.id, TenantFcmUpdateParams {
+            fcm_api_key: "evicted".to_string(),
+        })
+        .await
+        .expect("update failed");
+    assert_eq!(
+        cache.get_tenant(&first.id).await.unwrap().fcm_api_key,
+        Some("evicted".to_string())
+    );
+}
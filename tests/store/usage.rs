@@ -0,0 +1,156 @@
+use {
+    crate::context::StoreContext,
+    chrono::{Duration, Utc},
+    echo_server::{
+        providers::ProviderKind,
+        stores::{
+            client::{Device, DeviceMetadata},
+            usage::{UsageCounts, UsageStore},
+        },
+    },
+    test_context::test_context,
+    uuid::Uuid,
+};
+
+#[test_context(StoreContext)]
+#[tokio::test]
+async fn test_usage(ctx: &mut StoreContext) {
+    let tenant_id = Uuid::new_v4().to_string();
+    let today = Utc::now().date_naive();
+
+    for counts in [
+        UsageCounts {
+            received_notifications: 1,
+            sent_apns_notifications: 1,
+            delivered_notifications: 1,
+            ..Default::default()
+        },
+        UsageCounts {
+            received_notifications: 1,
+            sent_fcm_notifications: 2,
+            delivered_notifications: 1,
+            ..Default::default()
+        },
+        UsageCounts {
+            received_notifications: 1,
+            failed_notifications: 1,
+            ..Default::default()
+        },
+    ] {
+        ctx.pool
+            .record_usage(&tenant_id, today, counts)
+            .await
+            .expect("recording failed");
+    }
+
+    let usage = ctx
+        .pool
+        .get_usage(&tenant_id, today - Duration::days(1), today)
+        .await
+        .expect("fetch failed");
+    assert_eq!(usage.len(), 1);
+    assert_eq!(usage[0].day, today);
+    assert_eq!(usage[0].counts.received_notifications, 3);
+
+    let totals = ctx
+        .pool
+        .get_usage_totals(&tenant_id, today, today)
+        .await
+        .expect("fetch failed");
+    // Deliveries are counted per device, notifications once each
+    assert_eq!(totals.sent_notifications(), 3);
+    assert_eq!(totals.delivered_notifications, 2);
+    assert_eq!(totals.failed_notifications, 1);
+
+    let totals = ctx
+        .pool
+        .get_usage_totals(
+            &tenant_id,
+            today - Duration::days(7),
+            today - Duration::days(1),
+        )
+        .await
+        .expect("fetch failed");
+    assert_eq!(totals, UsageCounts::default());
+
+    // Usage is recorded against the day it's given rather than the database's
+    // date
+    let yesterday = today - Duration::days(1);
+    ctx.pool
+        .record_usage(&tenant_id, yesterday, UsageCounts {
+            registrations: 1,
+            ..Default::default()
+        })
+        .await
+        .expect("recording failed");
+    let usage = ctx
+        .pool
+        .get_usage(&tenant_id, yesterday, yesterday)
+        .await
+        .expect("fetch failed");
+    assert_eq!(usage.len(), 1);
+    assert_eq!(usage[0].counts.registrations, 1);
+
+    let deleted = ctx
+        .pool
+        .delete_usage(&tenant_id)
+        .await
+        .expect("deletion failed");
+    assert_eq!(deleted, 2);
+    let totals = ctx
+        .pool
+        .get_usage_totals(&tenant_id, yesterday, today)
+        .await
+        .expect("fetch failed");
+    assert_eq!(totals, UsageCounts::default());
+}
+
+#[test_context(StoreContext)]
+#[tokio::test]
+async fn test_registered_clients_snapshot(ctx: &mut StoreContext) {
+    let tenant_id = Uuid::new_v4().to_string();
+    let client_id = Uuid::new_v4().to_string();
+    let today = Utc::now().date_naive();
+
+    ctx.clients
+        .create_client(&tenant_id, &client_id, Device {
+            id: "phone".to_string(),
+            push_type: ProviderKind::Noop,
+            token: "token".to_string(),
+            metadata: DeviceMetadata::default(),
+        })
+        .await
+        .expect("client creation failed");
+
+    ctx.pool
+        .snapshot_registered_clients(today)
+        .await
+        .expect("snapshot failed");
+    let usage = ctx
+        .pool
+        .get_usage(&tenant_id, today, today)
+        .await
+        .expect("fetch failed");
+    assert_eq!(usage[0].registered_clients, Some(1));
+
+    // A later snapshot replaces the day's count, even once there are no clients
+    ctx.clients
+        .delete_client(&tenant_id, &client_id)
+        .await
+        .expect("client deletion failed");
+    ctx.pool
+        .snapshot_registered_clients(today)
+        .await
+        .expect("snapshot failed");
+    let usage = ctx
+        .pool
+        .get_usage(&tenant_id, today, today)
+        .await
+        .expect("fetch failed");
+    assert_eq!(usage[0].registered_clients, Some(0));
+
+    ctx.pool
+        .delete_usage(&tenant_id)
+        .await
+        .expect("deletion failed");
+}
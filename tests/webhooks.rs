@@ -0,0 +1,93 @@
+use {
+    echo_server::{
+        error::Error,
+        stores::tenant::WebhookEventType,
+        webhooks::{check_destination, event_type_for, is_public_address, sign},
+    },
+    std::net::IpAddr,
+};
+
+#[test]
+pub fn check_signature() {
+    let signature = sign(
+        "webhook-secret-0123456789",
+        1677888000,
+        "{\"type\":\"quota_exceeded\"}",
+    );
+
+    assert_eq!(
+        signature,
+        "1e2663e74c8f4580aef960d9e344cfb30c7ae11e0e5c33257dbc543e508217d0"
+    );
+}
+
+#[test]
+pub fn check_event_types() {
+    assert_eq!(
+        event_type_for(&Error::FcmMessage(fcm::ErrorReason::NotRegistered)),
+        Some(WebhookEventType::ClientTokenInvalid)
+    );
+    assert_eq!(
+        event_type_for(&Error::Fcm(fcm::FcmError::Unauthorized)),
+        Some(WebhookEventType::ProviderAuthFailed)
+    );
+    assert_eq!(
+        event_type_for(&Error::QuotaExceeded(10)),
+        Some(WebhookEventType::QuotaExceeded)
+    );
+
+    // Transient and configuration errors aren't reported
+    assert_eq!(
+        event_type_for(&Error::Fcm(fcm::FcmError::ServerError(None))),
+        None
+    );
+    assert_eq!(
+        event_type_for(&Error::ProviderNotAvailable("fcm".to_string())),
+        None
+    );
+}
+
+#[test]
+pub fn check_public_addresses() {
+    for address in ["1.1.1.1", "8.8.8.8", "2606:4700:4700::1111"] {
+        let ip: IpAddr = address.parse().unwrap();
+        assert!(is_public_address(ip), "{address} should be public");
+    }
+
+    for address in [
+        "0.0.0.0",
+        "127.0.0.1",
+        "10.0.0.1",
+        "172.16.0.1",
+        "192.168.1.1",
+        "169.254.169.254",
+        "100.64.0.1",
+        "255.255.255.255",
+        "::",
+        "::1",
+        "fd00::1",
+        "fe80::1",
+        "::ffff:127.0.0.1",
+    ] {
+        let ip: IpAddr = address.parse().unwrap();
+        assert!(!is_public_address(ip), "{address} shouldn't be public");
+    }
+}
+
+#[tokio::test]
+pub async fn check_destinations() {
+    assert!(check_destination("https://1.1.1.1/webhook").await.is_ok());
+
+    for url in [
+        "http://127.0.0.1:8080/webhook",
+        "http://localhost/webhook",
+        "http://169.254.169.254/latest/meta-data",
+        "http://[::1]/webhook",
+        "http://10.0.0.1/webhook",
+    ] {
+        assert!(
+            check_destination(url).await.is_err(),
+            "{url} should be rejected"
+        );
+    }
+}